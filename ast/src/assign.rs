@@ -1,4 +1,4 @@
-use std::fmt;
+use std::{fmt, ops::Range};
 
 use crate::{formatter::Formatter, RcLocal, SideEffects, Traverse};
 
@@ -10,6 +10,8 @@ pub struct Assign {
     pub right: Vec<RValue>,
     pub prefix: bool,
     pub parallel: bool,
+    // the instructions this statement was lifted from
+    pub pc_range: Option<Range<usize>>,
 }
 
 impl Assign {
@@ -19,6 +21,7 @@ impl Assign {
             right,
             prefix: false,
             parallel: false,
+            pc_range: None,
         }
     }
 }
@@ -22,6 +22,11 @@ pub enum BinaryOperation {
     And,
     Or,
     IDiv,
+    BitwiseAnd,
+    BitwiseOr,
+    BitwiseXor,
+    LeftShift,
+    RightShift,
 }
 
 impl BinaryOperation {
@@ -60,6 +65,11 @@ impl fmt::Display for BinaryOperation {
                 BinaryOperation::And => "and",
                 BinaryOperation::Or => "or",
                 BinaryOperation::IDiv => "//",
+                BinaryOperation::BitwiseAnd => "&",
+                BinaryOperation::BitwiseOr => "|",
+                BinaryOperation::BitwiseXor => "~",
+                BinaryOperation::LeftShift => "<<",
+                BinaryOperation::RightShift => ">>",
             }
         )
     }
@@ -277,13 +287,17 @@ impl Binary {
 
     pub fn precedence(&self) -> usize {
         match self.operation {
-            BinaryOperation::Pow => 8,
+            BinaryOperation::Pow => 12,
             BinaryOperation::Mul
             | BinaryOperation::Div
             | BinaryOperation::Mod
-            | BinaryOperation::IDiv => 6,
-            BinaryOperation::Add | BinaryOperation::Sub => 5,
-            BinaryOperation::Concat => 4,
+            | BinaryOperation::IDiv => 10,
+            BinaryOperation::Add | BinaryOperation::Sub => 9,
+            BinaryOperation::Concat => 8,
+            BinaryOperation::LeftShift | BinaryOperation::RightShift => 7,
+            BinaryOperation::BitwiseAnd => 6,
+            BinaryOperation::BitwiseXor => 5,
+            BinaryOperation::BitwiseOr => 4,
             BinaryOperation::LessThan
             | BinaryOperation::GreaterThan
             | BinaryOperation::LessThanOrEqual
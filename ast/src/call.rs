@@ -1,4 +1,4 @@
-use std::fmt;
+use std::{fmt, ops::Range};
 
 use crate::{formatter::Formatter, has_side_effects, LocalRw, RcLocal, Traverse};
 
@@ -8,6 +8,8 @@ use super::RValue;
 pub struct Call {
     pub value: Box<RValue>,
     pub arguments: Vec<RValue>,
+    // only set when the call is a statement
+    pub pc_range: Option<Range<usize>>,
 }
 
 impl Call {
@@ -15,6 +17,7 @@ impl Call {
         Self {
             value: Box::new(value),
             arguments,
+            pc_range: None,
         }
     }
 }
@@ -78,6 +81,8 @@ pub struct MethodCall {
     pub value: Box<RValue>,
     pub method: String,
     pub arguments: Vec<RValue>,
+    // only set when the call is a statement
+    pub pc_range: Option<Range<usize>>,
 }
 
 impl MethodCall {
@@ -86,6 +91,7 @@ impl MethodCall {
             value: Box::new(value),
             method,
             arguments,
+            pc_range: None,
         }
     }
 }
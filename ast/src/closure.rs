@@ -20,7 +20,11 @@ pub enum Upvalue {
 pub struct Function {
     pub name: Option<String>,
     pub parameters: Vec<RcLocal>,
+    // recorded by the compiler, may be shorter than `parameters`
+    pub parameter_types: Vec<Type>,
     pub is_variadic: bool,
+    // formatted as the `@native` attribute
+    pub is_native: bool,
     pub body: Block,
 }
 
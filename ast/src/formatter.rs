@@ -1,5 +1,4 @@
 use std::fmt::Write;
-use std::iter;
 use std::{
     borrow::Cow,
     fmt::{self},
@@ -9,9 +8,10 @@ use itertools::Itertools;
 
 use crate::{
     Assign, Binary, BinaryOperation, Block, Call, Closure, GenericFor, If, Index, LValue, Literal,
-    MethodCall, NumericFor, RValue, Repeat, Return, Select, Statement, Table, Unary, While,
+    MethodCall, NumericFor, RValue, Repeat, Return, Select, Statement, Table, Type, Unary, While,
 };
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum IndentationMode {
     Spaces(u8),
     Tab,
@@ -128,7 +128,7 @@ impl<'a, W: fmt::Write> Formatter<'a, W> {
                     Statement::Call(_) | Statement::MethodCall(_) => true,
                     Statement::Repeat(repeat) => is_ambiguous(&repeat.condition),
                     Statement::Assign(Assign { right: list, .. })
-                    | Statement::Return(Return { values: list }) => {
+                    | Statement::Return(Return { values: list, .. }) => {
                         if let Some(last) = list.last() {
                             is_ambiguous(last)
                         } else {
@@ -226,10 +226,18 @@ impl<'a, W: fmt::Write> Formatter<'a, W> {
                 }
             } else {
                 if !sequential_keys {
-                    if let Some(key) = key {
-                        write!(self.output, "[")?;
-                        self.format_rvalue(key)?;
-                        write!(self.output, "] = ")?;
+                    match key {
+                        Some(RValue::Literal(Literal::String(field)))
+                            if Self::is_valid_name(field) =>
+                        {
+                            write!(self.output, "{} = ", std::str::from_utf8(field).unwrap())?;
+                        }
+                        Some(key) => {
+                            write!(self.output, "[")?;
+                            self.format_rvalue(key)?;
+                            write!(self.output, "] = ")?;
+                        }
+                        None => {}
                     }
                 }
                 self.format_rvalue(value)?;
@@ -281,18 +289,19 @@ impl<'a, W: fmt::Write> Formatter<'a, W> {
 
     fn format_closure_parameters(&mut self, closure: &Closure) -> fmt::Result {
         let function = closure.function.lock();
+        let mut parameters = function.parameters.iter().enumerate().map(|(i, x)| {
+            match function.parameter_types.get(i) {
+                Some(r#type) if *r#type != Type::Any => format!("{}: {}", x, r#type.annotation()),
+                _ => x.to_string(),
+            }
+        });
         write!(
             self.output,
             "{}",
             if function.is_variadic {
-                function
-                    .parameters
-                    .iter()
-                    .map(|x| x.to_string())
-                    .chain(std::iter::once("...".into()))
-                    .join(", ")
+                parameters.chain(std::iter::once("...".into())).join(", ")
             } else {
-                function.parameters.iter().join(", ")
+                parameters.join(", ")
             }
         )
     }
@@ -339,7 +348,15 @@ impl<'a, W: fmt::Write> Formatter<'a, W> {
         }
     }
 
+    fn format_attributes(&mut self, closure: &Closure) -> fmt::Result {
+        if closure.function.lock().is_native {
+            write!(self.output, "@native ")?;
+        }
+        Ok(())
+    }
+
     pub(crate) fn format_closure(&mut self, closure: &Closure) -> fmt::Result {
+        self.format_attributes(closure)?;
         write!(self.output, "function(")?;
         self.format_closure_parameters(closure)?;
         write!(self.output, ")")?;
@@ -347,7 +364,17 @@ impl<'a, W: fmt::Write> Formatter<'a, W> {
         write!(self.output, "end")
     }
 
-    fn format_named_function(&mut self, name: &LValue, closure: &Closure) -> fmt::Result {
+    fn format_named_function(
+        &mut self,
+        name: &LValue,
+        closure: &Closure,
+        local: bool,
+    ) -> fmt::Result {
+        // attributes go before `local`
+        self.format_attributes(closure)?;
+        if local {
+            write!(self.output, "local ")?;
+        }
         write!(self.output, "function {}(", name)?;
         self.format_closure_parameters(closure)?;
         write!(self.output, ")")?;
@@ -366,7 +393,7 @@ impl<'a, W: fmt::Write> Formatter<'a, W> {
             RValue::Unary(unary) => self.format_unary(unary),
             RValue::Binary(binary) => self.format_binary(binary),
             RValue::Closure(closure) => self.format_closure(closure),
-            RValue::Literal(Literal::Number(n)) if n.is_infinite() => {
+            RValue::Literal(Literal::Number(n) | Literal::Float(n)) if n.is_infinite() => {
                 // TODO: only insert parentheses when necessary
                 write!(self.output, "(")?;
                 self.format_binary(&Binary::new(
@@ -376,7 +403,7 @@ impl<'a, W: fmt::Write> Formatter<'a, W> {
                 ))?;
                 write!(self.output, ")")
             }
-            RValue::Literal(Literal::Number(n)) if n.is_nan() => {
+            RValue::Literal(Literal::Number(n) | Literal::Float(n)) if n.is_nan() => {
                 // TODO: check that nan is appropriate for platform
                 // assert_eq!(n.to_bits(), 0x7ff8000000000000);
                 // TODO: only insert parentheses when necessary
@@ -388,6 +415,8 @@ impl<'a, W: fmt::Write> Formatter<'a, W> {
                 ))?;
                 write!(self.output, ")")
             }
+            // the literal 9223372036854775808 doesn't fit and would be read as a float
+            RValue::Literal(Literal::Integer(i64::MIN)) => write!(self.output, "math.mininteger"),
             _ => write!(self.output, "{}", rvalue),
         }
     }
@@ -465,7 +494,7 @@ impl<'a, W: fmt::Write> Formatter<'a, W> {
                             && let Some((_, next)) = iter.peek()
                             && next.is_ascii_digit()
                         {
-                            owned.extend(iter::repeat('0').take(3 - printed.len()));
+                            owned.push_str(&"0".repeat(3 - printed.len()));
                         }
                         owned.push_str(printed);
                     }
@@ -564,13 +593,10 @@ impl<'a, W: fmt::Write> Formatter<'a, W> {
     }
 
     pub(crate) fn format_assign(&mut self, assign: &Assign) -> fmt::Result {
-        if assign.prefix {
-            write!(self.output, "local ")?;
-        }
-
         if assign.left.len() == 1
             && assign.right.len() == 1
             && let RValue::Closure(closure) = &assign.right[0]
+            && !matches!(&assign.left[0], LValue::Local(local) if local.0 .0.lock().1.is_some())
         {
             let left = &assign.left[0];
             if assign.prefix || left.as_global().is_some() || {
@@ -599,15 +625,28 @@ impl<'a, W: fmt::Write> Formatter<'a, W> {
                     false
                 }
             } {
-                return self.format_named_function(left, closure);
+                return self.format_named_function(left, closure, assign.prefix);
             }
         }
 
+        if assign.prefix {
+            write!(self.output, "local ")?;
+        }
+
         for (i, lvalue) in assign.left.iter().enumerate() {
             if i != 0 {
                 write!(self.output, ", ")?;
             }
             self.format_lvalue(lvalue)?;
+            // `local x <const>` without a value can't be assigned later, so only
+            // declarations with values get attributes
+            if assign.prefix
+                && !assign.right.is_empty()
+                && let LValue::Local(local) = lvalue
+                && let Some(attribute) = local.0 .0.lock().1
+            {
+                write!(self.output, " {}", attribute)?;
+            }
         }
 
         if !assign.right.is_empty() {
@@ -660,10 +699,10 @@ impl<'a, W: fmt::Write> Formatter<'a, W> {
         self.format_rvalue(&numeric_for.initial)?;
         write!(self.output, ", ")?;
         self.format_rvalue(&numeric_for.limit)?;
-        let skip_step = if let RValue::Literal(Literal::Number(n)) = numeric_for.step {
-            n == 1.0
-        } else {
-            false
+        let skip_step = match numeric_for.step {
+            RValue::Literal(Literal::Number(n)) => n == 1.0,
+            RValue::Literal(Literal::Integer(n)) => n == 1,
+            _ => false,
         };
         if !skip_step {
             write!(self.output, ", ")?;
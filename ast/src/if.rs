@@ -5,13 +5,14 @@ use crate::{formatter::Formatter, LocalRw, RcLocal, SideEffects, Traverse};
 
 use super::{Block, RValue};
 
-use std::fmt;
+use std::{fmt, ops::Range};
 
 #[derive(Debug, Clone)]
 pub struct If {
     pub condition: RValue,
     pub then_block: Arc<Mutex<Block>>,
     pub else_block: Arc<Mutex<Block>>,
+    pub pc_range: Option<Range<usize>>,
 }
 
 impl PartialEq for If {
@@ -27,6 +28,7 @@ impl If {
             condition,
             then_block: Arc::new(then_block.into()),
             else_block: Arc::new(else_block.into()),
+            pc_range: None,
         }
     }
 }
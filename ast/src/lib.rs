@@ -9,7 +9,7 @@ use itertools::Either;
 
 use std::{
     fmt,
-    ops::{Deref, DerefMut},
+    ops::{Deref, DerefMut, Range},
 };
 
 mod assign;
@@ -153,10 +153,19 @@ impl RValue {
         match self {
             Self::Binary(binary) => binary.precedence(),
             Self::Unary(unary) => unary.precedence(),
-            RValue::Literal(Literal::Number(n)) if n.is_finite() && n.is_sign_negative() => {
-                return 7;
+            _ if self.is_negative_number() => 11,
+            _ => 13,
+        }
+    }
+
+    // printed with a leading `-`, so it groups like a unary expression
+    pub fn is_negative_number(&self) -> bool {
+        match *self {
+            RValue::Literal(Literal::Number(n) | Literal::Float(n) | Literal::Imaginary(n)) => {
+                n.is_finite() && n.is_sign_negative()
             }
-            _ => 9,
+            RValue::Literal(Literal::Integer(n) | Literal::Int64(n)) => n < 0,
+            _ => false,
         }
     }
 
@@ -305,6 +314,30 @@ impl fmt::Display for Comment {
     }
 }
 
+impl Statement {
+    pub fn pc_range(&self) -> Option<&Range<usize>> {
+        match self {
+            Statement::Assign(assign) => assign.pc_range.as_ref(),
+            Statement::Call(call) => call.pc_range.as_ref(),
+            Statement::MethodCall(method_call) => method_call.pc_range.as_ref(),
+            Statement::If(r#if) => r#if.pc_range.as_ref(),
+            Statement::Return(r#return) => r#return.pc_range.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn set_pc_range(&mut self, pc_range: Range<usize>) {
+        match self {
+            Statement::Assign(assign) => assign.pc_range = Some(pc_range),
+            Statement::Call(call) => call.pc_range = Some(pc_range),
+            Statement::MethodCall(method_call) => method_call.pc_range = Some(pc_range),
+            Statement::If(r#if) => r#if.pc_range = Some(pc_range),
+            Statement::Return(r#return) => r#return.pc_range = Some(pc_range),
+            _ => {}
+        }
+    }
+}
+
 impl fmt::Display for Statement {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
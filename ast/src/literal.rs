@@ -11,9 +11,33 @@ use crate::{
 pub enum Literal {
     Nil,
     Boolean(bool),
+    // a number without an integer subtype, as in Lua 5.1, 5.2 and Luau
     Number(f64),
+    // the Lua 5.3 number subtypes
+    Integer(i64),
+    #[from(ignore)]
+    Float(f64),
     String(Vec<u8>),
-    Vector(f32, f32, f32),
+    Vector(Vector),
+    // the 64-bit integer cdata of LuaJIT, e.g. `123LL` and `123ULL`
+    #[from(ignore)]
+    Int64(i64),
+    #[from(ignore)]
+    UInt64(u64),
+    // the imaginary part of a LuaJIT complex cdata, e.g. `2i`
+    #[from(ignore)]
+    Imaginary(f64),
+}
+
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct Vector {
+    // e.g. `Vector3.new` or `vector.create`
+    pub constructor: String,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    // only printed for VMs built with 4-wide vectors
+    pub w: Option<f32>,
 }
 
 impl Reduce for Literal {
@@ -26,8 +50,13 @@ impl Reduce for Literal {
             Literal::Boolean(false) | Literal::Nil => false,
             Literal::Boolean(true)
             | Literal::Number(_)
+            | Literal::Integer(_)
+            | Literal::Float(_)
             | Literal::String(_)
-            | Literal::Vector(..) => true,
+            | Literal::Vector(..)
+            | Literal::Int64(_)
+            | Literal::UInt64(_)
+            | Literal::Imaginary(_) => true,
         })
         .into()
     }
@@ -38,9 +67,10 @@ impl Infer for Literal {
         match self {
             Literal::Nil => Type::Nil,
             Literal::Boolean(_) => Type::Boolean,
-            Literal::Number(_) => Type::Number,
+            Literal::Number(_) | Literal::Integer(_) | Literal::Float(_) => Type::Number,
             Literal::String(_) => Type::String,
             Literal::Vector(..) => Type::Vector,
+            Literal::Int64(_) | Literal::UInt64(_) | Literal::Imaginary(_) => Type::Any,
         }
     }
 }
@@ -71,6 +101,13 @@ impl fmt::Display for Literal {
                 let printed = buffer.format_finite(value);
                 write!(f, "{}", printed.strip_suffix(".0").unwrap_or(printed))
             }
+            Literal::Integer(value) => write!(f, "{}", value),
+            &Literal::Float(value) => {
+                debug_assert!(value.is_finite());
+                // keep the ".0" so that it isn't read back as an integer
+                let mut buffer = ryu::Buffer::new();
+                write!(f, "{}", buffer.format_finite(value))
+            }
             Literal::String(value) => {
                 write!(
                     f,
@@ -78,7 +115,25 @@ impl fmt::Display for Literal {
                     Formatter::<fmt::Formatter>::escape_string(value)
                 )
             }
-            Literal::Vector(x, y, z) => write!(f, "Vector3.new({}, {}, {})", x, y, z),
+            Literal::Vector(vector) => {
+                write!(
+                    f,
+                    "{}({}, {}, {}",
+                    vector.constructor, vector.x, vector.y, vector.z
+                )?;
+                if let Some(w) = vector.w {
+                    write!(f, ", {}", w)?;
+                }
+                write!(f, ")")
+            }
+            Literal::Int64(value) => write!(f, "{}LL", value),
+            Literal::UInt64(value) => write!(f, "{}ULL", value),
+            &Literal::Imaginary(value) => {
+                debug_assert!(value.is_finite());
+                let mut buffer = ryu::Buffer::new();
+                let printed = buffer.format_finite(value);
+                write!(f, "{}i", printed.strip_suffix(".0").unwrap_or(printed))
+            }
         }
     }
 }
@@ -10,12 +10,28 @@ use std::{
 };
 use triomphe::Arc;
 
+// a Lua 5.4 local variable attribute, ex. `local x <const> = ...`
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Ord, Eq, Hash)]
+pub enum Attribute {
+    Const,
+    Close,
+}
+
+impl fmt::Display for Attribute {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Const => write!(f, "<const>"),
+            Self::Close => write!(f, "<close>"),
+        }
+    }
+}
+
 #[derive(Debug, Default, From, Clone, PartialEq, PartialOrd, Ord, Eq, Hash)]
-pub struct Local(pub Option<String>);
+pub struct Local(pub Option<String>, pub Option<Attribute>);
 
 impl Local {
     pub fn new(name: Option<String>) -> Self {
-        Self(name)
+        Self(name, None)
     }
 }
 
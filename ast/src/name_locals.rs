@@ -1,72 +1,142 @@
-use rustc_hash::FxHashSet;
+use std::fmt;
+
+use itertools::Either;
+use rustc_hash::{FxHashMap, FxHashSet};
 use triomphe::Arc;
 
-use crate::{Block, RValue, RcLocal, Statement, Traverse, Upvalue};
+use crate::{
+    formatter::Formatter, Assign, Block, LValue, RValue, RcLocal, Statement, Traverse, Upvalue,
+};
 
 struct Namer {
     rename: bool,
     counter: usize,
     upvalues: FxHashSet<RcLocal>,
+    globals: FxHashSet<String>,
+    // the locals in scope by name, innermost last
+    scopes: Vec<FxHashMap<String, RcLocal>>,
 }
 
 impl Namer {
-    fn name_local(&mut self, prefix: &str, local: &RcLocal) {
+    fn is_taken(&self, name: &str) -> bool {
+        self.globals.contains(name) || self.scopes.iter().any(|scope| scope.contains_key(name))
+    }
+
+    // whether naming `local` `name` would hide a global or a local in scope that `scope`,
+    // the statements `local` is visible in, refers to
+    fn is_shadowed(&self, name: &str, local: &RcLocal, scope: &mut [Statement]) -> bool {
+        if !self.is_taken(name) {
+            return false;
+        }
+        let hidden = self
+            .scopes
+            .iter()
+            .filter_map(|locals| locals.get(name))
+            .filter(|&hidden| hidden != local)
+            .cloned()
+            .collect::<Vec<_>>();
+        refers_to(scope, name, &hidden)
+    }
+
+    fn name_local(&mut self, prefix: &str, local: &RcLocal, scope: &mut [Statement]) {
         let mut lock = local.0 .0.lock();
-        if self.rename || lock.0.is_none() {
+        let name = match lock.0.take() {
+            // keep existing names (i.e. from debug info) as long as they don't shadow anything
+            // that is used while they're in scope
+            Some(name)
+                if !self.rename && Formatter::<fmt::Formatter>::is_valid_name(name.as_bytes()) =>
+            {
+                if self.is_shadowed(&name, local, scope) {
+                    (2..)
+                        .map(|suffix| format!("{}_{}", name, suffix))
+                        .find(|name| !self.is_taken(name))
+                        .unwrap()
+                } else {
+                    name
+                }
+            }
             // TODO: hacky and slow
-            if Arc::count(&local.0 .0) == 1 {
-                lock.0 = Some("_".to_string());
-            } else {
+            _ if Arc::count(&local.0 .0) == 1 => "_".to_string(),
+            _ => {
                 let prefix = prefix.to_string()
                     + if self.upvalues.contains(local) {
                         "_u_"
                     } else {
                         ""
                     };
-                lock.0 = Some(format!("{}{}", prefix, self.counter));
-                self.counter += 1;
+                loop {
+                    let name = format!("{}{}", prefix, self.counter);
+                    self.counter += 1;
+                    if !self.is_taken(&name) {
+                        break name;
+                    }
+                }
             }
+        };
+        if name != "_" {
+            self.scopes
+                .last_mut()
+                .unwrap()
+                .insert(name.clone(), local.clone());
         }
+        lock.0 = Some(name);
+    }
+
+    fn name_scope(&mut self, block: &mut Block, locals: &[(&str, &RcLocal)]) {
+        self.scopes.push(FxHashMap::default());
+        for &(prefix, local) in locals {
+            self.name_local(prefix, local, &mut block.0);
+        }
+        self.name_locals(block);
+        self.scopes.pop();
     }
 
     fn name_locals(&mut self, block: &mut Block) {
-        for statement in &mut block.0 {
+        for index in 0..block.0.len() {
+            let (statement, scope) = block.0[index..].split_first_mut().unwrap();
             // TODO: traverse_rvalues
             statement.post_traverse_values(&mut |value| -> Option<()> {
                 if let itertools::Either::Right(RValue::Closure(closure)) = value {
                     let mut function = closure.function.lock();
-                    for param in &function.parameters {
-                        self.name_local("p", param);
-                    }
-                    self.name_locals(&mut function.body);
+                    let function = &mut *function;
+                    let parameters = function
+                        .parameters
+                        .iter()
+                        .map(|param| ("p", param))
+                        .collect::<Vec<_>>();
+                    self.name_scope(&mut function.body, &parameters);
                 };
                 None
             });
             match statement {
                 Statement::Assign(assign) if assign.prefix => {
                     for lvalue in &assign.left {
-                        self.name_local("v", lvalue.as_local().unwrap());
+                        self.name_local("v", lvalue.as_local().unwrap(), scope);
                     }
                 }
                 Statement::If(r#if) => {
-                    self.name_locals(&mut r#if.then_block.lock());
-                    self.name_locals(&mut r#if.else_block.lock());
+                    self.name_scope(&mut r#if.then_block.lock(), &[]);
+                    self.name_scope(&mut r#if.else_block.lock(), &[]);
                 }
                 Statement::While(r#while) => {
-                    self.name_locals(&mut r#while.block.lock());
+                    self.name_scope(&mut r#while.block.lock(), &[]);
                 }
                 Statement::Repeat(repeat) => {
-                    self.name_locals(&mut repeat.block.lock());
+                    self.name_scope(&mut repeat.block.lock(), &[]);
                 }
                 Statement::NumericFor(numeric_for) => {
-                    self.name_local("v", &numeric_for.counter);
-                    self.name_locals(&mut numeric_for.block.lock());
+                    self.name_scope(
+                        &mut numeric_for.block.lock(),
+                        &[("v", &numeric_for.counter)],
+                    );
                 }
                 Statement::GenericFor(generic_for) => {
-                    for res_local in &generic_for.res_locals {
-                        self.name_local("v", res_local);
-                    }
-                    self.name_locals(&mut generic_for.block.lock());
+                    let res_locals = generic_for
+                        .res_locals
+                        .iter()
+                        .map(|res_local| ("v", res_local))
+                        .collect::<Vec<_>>();
+                    self.name_scope(&mut generic_for.block.lock(), &res_locals);
                 }
                 _ => {}
             }
@@ -79,6 +149,14 @@ impl Namer {
             // TODO: traverse_values
             // TODO: doesnt need to be mut
             statement.post_traverse_values(&mut |value| -> Option<()> {
+                match &value {
+                    itertools::Either::Left(LValue::Global(global))
+                    | itertools::Either::Right(RValue::Global(global)) => {
+                        self.globals
+                            .insert(String::from_utf8_lossy(&global.0).into_owned());
+                    }
+                    _ => {}
+                }
                 if let itertools::Either::Right(RValue::Closure(closure)) = value {
                     self.upvalues.extend(
                         closure
@@ -116,12 +194,104 @@ impl Namer {
     }
 }
 
+// whether `statements` refer to a global named `name` or to one of `locals`, including in
+// nested blocks and closures
+fn refers_to(statements: &mut [Statement], name: &str, locals: &[RcLocal]) -> bool {
+    statements.iter_mut().any(|statement| {
+        statement
+            .post_traverse_values(&mut |value| -> Option<()> {
+                match value {
+                    Either::Left(LValue::Local(local)) | Either::Right(RValue::Local(local))
+                        if locals.contains(local) =>
+                    {
+                        Some(())
+                    }
+                    Either::Left(LValue::Global(global))
+                    | Either::Right(RValue::Global(global))
+                        if global.0 == name.as_bytes() =>
+                    {
+                        Some(())
+                    }
+                    Either::Right(RValue::Closure(closure)) => {
+                        let captures = closure.upvalues.iter().any(|upvalue| match upvalue {
+                            Upvalue::Copy(local) | Upvalue::Ref(local) => locals.contains(local),
+                        });
+                        (captures || refers_to(&mut closure.function.lock().body.0, name, locals))
+                            .then_some(())
+                    }
+                    _ => None,
+                }
+            })
+            .is_some()
+            || match statement {
+                Statement::If(r#if) => {
+                    refers_to(&mut r#if.then_block.lock().0, name, locals)
+                        || refers_to(&mut r#if.else_block.lock().0, name, locals)
+                }
+                Statement::While(r#while) => refers_to(&mut r#while.block.lock().0, name, locals),
+                Statement::Repeat(repeat) => refers_to(&mut repeat.block.lock().0, name, locals),
+                Statement::NumericFor(numeric_for) => {
+                    refers_to(&mut numeric_for.block.lock().0, name, locals)
+                }
+                Statement::GenericFor(generic_for) => {
+                    refers_to(&mut generic_for.block.lock().0, name, locals)
+                }
+                _ => false,
+            }
+    })
+}
+
 pub fn name_locals(block: &mut Block, rename: bool) {
     let mut namer = Namer {
         rename,
         counter: 1,
         upvalues: FxHashSet::default(),
+        globals: FxHashSet::default(),
+        scopes: vec![FxHashMap::default()],
     };
     namer.find_upvalues(block);
     namer.name_locals(block);
 }
+
+// debug info tells us the pc a local is live from, which is right after the
+// instructions that initialize it. `named_local` gives the local to name a written
+// local after, if any. the write is assigned to that local and copied into the
+// written one, so the name survives ssa construction.
+pub fn name_written_locals(
+    statements: &mut Vec<Statement>,
+    first_statement: usize,
+    mut named_local: impl FnMut(&RcLocal) -> Option<RcLocal>,
+) {
+    let mut copies = Vec::new();
+    for statement in &mut statements[first_statement..] {
+        let Statement::Assign(assign) = statement else {
+            continue;
+        };
+        // a recursive closure would capture the register before the copy
+        let captured = assign
+            .right
+            .iter()
+            .filter_map(|rvalue| rvalue.as_closure())
+            .flat_map(|closure| &closure.upvalues)
+            .map(|upvalue| match upvalue {
+                Upvalue::Copy(local) | Upvalue::Ref(local) => local.clone(),
+            })
+            .collect::<Vec<_>>();
+        for lvalue in &mut assign.left {
+            let LValue::Local(local) = lvalue else {
+                continue;
+            };
+            if captured.contains(local) {
+                continue;
+            }
+            let Some(named_local) = named_local(local) else {
+                continue;
+            };
+            copies.push(
+                Assign::new(vec![local.clone().into()], vec![named_local.clone().into()]).into(),
+            );
+            *local = named_local;
+        }
+    }
+    statements.extend(copies);
+}
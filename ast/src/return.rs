@@ -1,4 +1,4 @@
-use std::fmt;
+use std::{fmt, ops::Range};
 
 use crate::{formatter::Formatter, has_side_effects, LocalRw, RcLocal, Traverse};
 
@@ -7,13 +7,17 @@ use super::RValue;
 #[derive(Debug, PartialEq, Clone, Default)]
 pub struct Return {
     pub values: Vec<RValue>,
+    pub pc_range: Option<Range<usize>>,
 }
 
 has_side_effects!(Return);
 
 impl Return {
     pub fn new(values: Vec<RValue>) -> Self {
-        Self { values }
+        Self {
+            values,
+            pc_range: None,
+        }
     }
 }
 
@@ -23,6 +23,10 @@ pub enum Type {
     Intersection(BTreeSet<Type>),
     VarArg,
     Vector,
+    Thread,
+    Buffer,
+    // host types are named if the compiler was told about them
+    Userdata(Option<String>),
 }
 
 impl Type {
@@ -72,16 +76,54 @@ impl Type {
             Self::Intersection(_) => 2,
             Self::VarArg => 0,
             Self::Vector => 0,
+            Self::Thread => 0,
+            Self::Buffer => 0,
+            Self::Userdata(_) => 0,
+        }
+    }
+}
+
+impl Type {
+    /// Displays the type as it's written in an annotation, where a variadic pack needs
+    /// an element type
+    pub fn annotation(&self) -> impl Display + '_ {
+        TypeDisplay {
+            r#type: self,
+            annotation: true,
         }
     }
 }
 
 impl Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        TypeDisplay {
+            r#type: self,
+            annotation: false,
+        }
+        .fmt(f)
+    }
+}
+
+struct TypeDisplay<'a> {
+    r#type: &'a Type,
+    annotation: bool,
+}
+
+impl TypeDisplay<'_> {
+    fn nested<'a>(&self, r#type: &'a Type) -> TypeDisplay<'a> {
+        TypeDisplay {
+            r#type,
+            annotation: self.annotation,
+        }
+    }
+}
+
+impl Display for TypeDisplay<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
             "{}",
-            match self {
+            match self.r#type {
                 Type::Any => Cow::Borrowed("any"),
                 Type::Nil => Cow::Borrowed("nil"),
                 Type::Boolean => Cow::Borrowed("boolean"),
@@ -93,37 +135,54 @@ impl Display for Type {
                     Cow::Owned(format!(
                         "{{{}{}{}}}",
                         if indexer_type == &Type::Number && fields.is_empty() {
-                            element_type.to_string()
+                            self.nested(element_type).to_string()
                         } else {
-                            format!("[{}]: {}", indexer_type, element_type)
+                            format!(
+                                "[{}]: {}",
+                                self.nested(indexer_type),
+                                self.nested(element_type)
+                            )
                         },
-                        (!fields.is_empty()).then_some(", ").unwrap_or_default(),
+                        if fields.is_empty() { "" } else { ", " },
                         fields
                             .iter()
-                            .map(|(field, r#type)| { format!("{}: {}", field, r#type) })
+                            .map(|(field, r#type)| {
+                                format!("{}: {}", field, self.nested(r#type))
+                            })
                             .join(", ")
                     ))
                 }
                 Type::Function(domain, codomain) => Cow::Owned(format!(
                     "({}) -> {}",
-                    domain.iter().join(", "),
-                    if (codomain.len() == 1 && self.precedence() >= codomain[0].precedence())
+                    domain.iter().map(|r#type| self.nested(r#type)).join(", "),
+                    if (codomain.len() == 1 && self.r#type.precedence() >= codomain[0].precedence())
                         || codomain.len() > 1
                     {
-                        format!("({})", codomain.iter().join(", "))
+                        format!(
+                            "({})",
+                            codomain.iter().map(|r#type| self.nested(r#type)).join(", ")
+                        )
                     } else {
-                        codomain.iter().join(", ")
+                        codomain.iter().map(|r#type| self.nested(r#type)).join(", ")
                     }
                 )),
-                Type::Optional(r#type) => Cow::Owned(format!("{}?", r#type)),
+                // `?` binds tighter than function types, unions and intersections
+                Type::Optional(r#type) if r#type.precedence() > 0 => {
+                    Cow::Owned(format!("({})?", self.nested(r#type)))
+                }
+                Type::Optional(r#type) => Cow::Owned(format!("{}?", self.nested(r#type))),
                 Type::Union(types) => {
-                    Cow::Owned(types.iter().join(" | "))
+                    Cow::Owned(types.iter().map(|r#type| self.nested(r#type)).join(" | "))
                 }
                 Type::Intersection(types) => {
-                    Cow::Owned(types.iter().join(" & "))
+                    Cow::Owned(types.iter().map(|r#type| self.nested(r#type)).join(" & "))
                 }
+                Type::VarArg if self.annotation => Cow::Borrowed("...any"),
                 Type::VarArg => Cow::Borrowed("..."),
                 Type::Vector => Cow::Borrowed("vector"),
+                Type::Thread => Cow::Borrowed("thread"),
+                Type::Buffer => Cow::Borrowed("buffer"),
+                Type::Userdata(name) => Cow::Borrowed(name.as_deref().unwrap_or("userdata")),
             }
         )
     }
@@ -363,7 +363,7 @@ impl<'a> SsaConstructor<'a> {
             // search globally
             if !self.sealed_blocks.contains(&node) {
                 // TODO: this code is repeated multiple times, create new_local function
-                let param_local = RcLocal::default();
+                let param_local = new_version(local);
                 self.old_locals.insert(param_local.clone(), local.clone());
                 if let Some(upvalues) = self.new_upvalues_in.get_mut(local) {
                     upvalues.insert(param_local.clone());
@@ -377,7 +377,7 @@ impl<'a> SsaConstructor<'a> {
             } else if let Ok(pred) = self.function.predecessor_blocks(node).exactly_one() {
                 self.find_local(pred, local)
             } else {
-                let param_local = RcLocal::default();
+                let param_local = new_version(local);
                 self.old_locals.insert(param_local.clone(), local.clone());
                 if let Some(upvalues) = self.new_upvalues_in.get_mut(local) {
                     upvalues.insert(param_local.clone());
@@ -523,7 +523,7 @@ impl<'a> SsaConstructor<'a> {
                     && let Some(local) = assign.left[0].as_local().cloned()
                     && assign.right[0].as_closure().is_some()
                 {
-                    let new_local = RcLocal::default();
+                    let new_local = new_version(&local);
                     self.old_locals.insert(new_local.clone(), local.clone());
                     if let Some(upvalues) = self.new_upvalues_in.get_mut(&local) {
                         upvalues.insert(new_local.clone());
@@ -549,7 +549,7 @@ impl<'a> SsaConstructor<'a> {
                     self.read(node, stat_index);
                     // write
                     for (local_index, local) in written.iter().enumerate() {
-                        let new_local = RcLocal::default();
+                        let new_local = new_version(local);
                         self.old_locals.insert(new_local.clone(), local.clone());
                         if let Some(upvalues) = self.new_upvalues_in.get_mut(local) {
                             upvalues.insert(new_local.clone());
@@ -606,7 +606,9 @@ impl<'a> SsaConstructor<'a> {
         // TODO: this is a bit meh, maybe we should have an argument rvalue
         if let Some(mut incomplete_params) = self.incomplete_params.remove(&entry) {
             for param in &mut self.function.parameters {
-                *param = incomplete_params.remove(param).unwrap_or_default();
+                *param = incomplete_params
+                    .remove(param)
+                    .unwrap_or_else(|| new_version(param));
             }
         }
         assert!(self.incomplete_params.is_empty());
@@ -635,6 +637,11 @@ impl<'a> SsaConstructor<'a> {
     }
 }
 
+// versions keep the name of the local they were created from
+fn new_version(local: &RcLocal) -> RcLocal {
+    RcLocal::new(local.0 .0.lock().clone())
+}
+
 pub fn construct(
     function: &mut Function,
     upvalues_in: &Vec<RcLocal>,
@@ -242,6 +242,20 @@ impl<'a> Destructor<'a> {
         for (local, con_class) in &self.congruence_classes {
             let con_class = con_class.borrow();
             let new_local = con_class.iter().next().unwrap().1;
+            // keep a debug name if any local in the class has one
+            let unnamed = new_local.0 .0.lock().0.is_none();
+            if unnamed {
+                let name = con_class
+                    .iter()
+                    .find_map(|(_, local)| local.0 .0.lock().0.clone());
+                new_local.0 .0.lock().0 = name;
+            }
+            // same for a lua 5.4 attribute
+            let no_attribute = new_local.0 .0.lock().1.is_none();
+            if no_attribute {
+                let attribute = con_class.iter().find_map(|(_, local)| local.0 .0.lock().1);
+                new_local.0 .0.lock().1 = attribute;
+            }
             // TODO: see apply_local_map TODO,
             // we dont want to handle this here
             if local != new_local {
@@ -812,6 +826,7 @@ impl<'a> Destructor<'a> {
                     right: param_map.values().map(|v| v.clone().into()).collect(),
                     prefix: false,
                     parallel: true,
+                    pc_range: None,
                 }
                 .into(),
             );
@@ -852,6 +867,7 @@ impl<'a> Destructor<'a> {
                     right: Vec::with_capacity(args.len()),
                     prefix: false,
                     parallel: true,
+                    pc_range: None,
                 };
 
                 for (param, arg) in args {
@@ -504,7 +504,7 @@ pub fn inline(
                     {
                         if has_side_effects {
                             // TODO: PERF: dont clone
-                            let new_stat = match rvalue {
+                            let new_stat: Option<ast::Statement> = match rvalue {
                                 ast::RValue::Call(call)
                                 | ast::RValue::Select(ast::Select::Call(call)) => {
                                     Some(call.clone().into())
@@ -515,7 +515,10 @@ pub fn inline(
                                 }
                                 _ => None,
                             };
-                            if let Some(new_stat) = new_stat {
+                            if let Some(mut new_stat) = new_stat {
+                                if let Some(pc_range) = assign.pc_range.clone() {
+                                    new_stat.set_pc_range(pc_range);
+                                }
                                 block[stat_index] = new_stat;
                                 changed = true;
                             }
@@ -689,10 +689,12 @@ fn structure_bool_conditional(function: &mut Function, node: NodeIndex) -> bool
             && function.successor_blocks(else_target).next().is_none()
             && let Ok(ast::Statement::Return(ast::Return {
                 values: then_values,
+                ..
             })) = function.block(then_target).unwrap().iter().exactly_one()
             && let Ok(then_value) = then_values.iter().exactly_one()
             && let Ok(ast::Statement::Return(ast::Return {
                 values: else_values,
+                ..
             })) = function.block(else_target).unwrap().iter().exactly_one()
             && let Ok(else_value) = else_values.iter().exactly_one()
         {
@@ -868,6 +870,7 @@ fn try_remove_unnecessary_condition(function: &mut Function, node: NodeIndex) ->
                     right: vec![cond],
                     prefix: true,
                     parallel: false,
+                    pc_range: None,
                 }
                 .into(),
             ),
@@ -1,7 +1,10 @@
 use nom::{
     bytes::complete::tag,
     error::{Error, ErrorKind, ParseError},
-    number::complete::le_u8,
+    number::{
+        self,
+        complete::{f32, f64, i32, i64, le_u8, u32, u64},
+    },
     Err, IResult,
 };
 
@@ -76,3 +79,66 @@ impl Header {
         ))
     }
 }
+
+// readers for the types whose width and byte order are given by the header
+impl Header {
+    fn nom_endianness(&self) -> number::Endianness {
+        match self.endianness {
+            Endianness::Big => number::Endianness::Big,
+            Endianness::Little => number::Endianness::Little,
+        }
+    }
+
+    fn parse_unsigned<'a>(&self, input: &'a [u8], width: u8) -> IResult<&'a [u8], u64> {
+        match width {
+            4 => u32(self.nom_endianness())(input).map(|(input, value)| (input, value.into())),
+            8 => u64(self.nom_endianness())(input),
+            _ => Err(Err::Failure(Error::from_error_kind(
+                input,
+                ErrorKind::Switch,
+            ))),
+        }
+    }
+
+    // fails rather than truncating 8-byte values that don't fit
+    fn parse_narrow<'a, T: TryFrom<u64>>(
+        &self,
+        input: &'a [u8],
+        width: u8,
+    ) -> IResult<&'a [u8], T> {
+        let (rest, value) = self.parse_unsigned(input, width)?;
+        match T::try_from(value) {
+            Ok(value) => Ok((rest, value)),
+            Err(_) => Err(Err::Failure(Error::from_error_kind(
+                input,
+                ErrorKind::TooLarge,
+            ))),
+        }
+    }
+
+    pub fn parse_int<'a>(&self, input: &'a [u8]) -> IResult<&'a [u8], u32> {
+        self.parse_narrow(input, self.int_width)
+    }
+
+    pub fn parse_size_t<'a>(&self, input: &'a [u8]) -> IResult<&'a [u8], usize> {
+        self.parse_narrow(input, self.size_t_width)
+    }
+
+    pub fn parse_instruction<'a>(&self, input: &'a [u8]) -> IResult<&'a [u8], u32> {
+        self.parse_narrow(input, self.instr_width)
+    }
+
+    pub fn parse_number<'a>(&self, input: &'a [u8]) -> IResult<&'a [u8], f64> {
+        let endianness = self.nom_endianness();
+        match (self.number_width, self.number_is_integral) {
+            (4, false) => f32(endianness)(input).map(|(input, value)| (input, value.into())),
+            (8, false) => f64(endianness)(input),
+            (4, true) => i32(endianness)(input).map(|(input, value)| (input, value.into())),
+            (8, true) => i64(endianness)(input).map(|(input, value)| (input, value as f64)),
+            _ => Err(Err::Failure(Error::from_error_kind(
+                input,
+                ErrorKind::Switch,
+            ))),
+        }
+    }
+}
@@ -1,13 +1,11 @@
-use std::mem;
-
-use nom::IResult;
+use nom::{
+    error::{Error, ErrorKind, ParseError},
+    Err, IResult,
+};
 
 pub use header::Header;
 
-use crate::{
-    chunk::header::{Endianness, Format},
-    function::Function,
-};
+use crate::{chunk::header::Format, function::Function};
 
 pub mod header;
 
@@ -19,16 +17,20 @@ pub struct Chunk<'a> {
 impl<'a> Chunk<'a> {
     pub fn parse(input: &'a [u8]) -> IResult<&[u8], Self> {
         let (input, header) = Header::parse(input)?;
-        // TODO: pass header to Function::parse
-        assert_eq!(header.version_number, 0x51);
-        assert_eq!(header.format, Format::Official);
-        assert_eq!(header.endianness, Endianness::Little);
-        assert_eq!(header.int_width as usize, mem::size_of::<i32>());
-        assert_eq!(header.size_t_width as usize, mem::size_of::<u32>());
-        assert_eq!(header.instr_width as usize, mem::size_of::<u32>());
-        assert_eq!(header.number_width as usize, mem::size_of::<f64>());
-        assert!(!header.number_is_integral);
-        let (input, function) = Function::parse(input)?;
+        // instructions are always 32 bits wide in 5.1
+        if header.version_number != 0x51
+            || header.format != Format::Official
+            || header.instr_width != 4
+            || ![4, 8].contains(&header.int_width)
+            || ![4, 8].contains(&header.size_t_width)
+            || ![4, 8].contains(&header.number_width)
+        {
+            return Err(Err::Failure(Error::from_error_kind(
+                input,
+                ErrorKind::Verify,
+            )));
+        }
+        let (input, function) = Function::parse(input, &header)?;
 
         Ok((input, Self { function }))
     }
@@ -0,0 +1,47 @@
+use std::fmt;
+
+use nom::error::ErrorKind;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeserializeError {
+    UnexpectedEof { offset: usize },
+    Malformed { offset: usize, kind: ErrorKind },
+}
+
+impl DeserializeError {
+    pub fn offset(&self) -> usize {
+        match *self {
+            Self::UnexpectedEof { offset } | Self::Malformed { offset, .. } => offset,
+        }
+    }
+
+    pub(crate) fn from_parse_error(
+        input: &[u8],
+        error: nom::Err<nom::error::Error<&[u8]>>,
+    ) -> Self {
+        match error {
+            nom::Err::Incomplete(_) => Self::UnexpectedEof {
+                offset: input.len(),
+            },
+            nom::Err::Error(error) | nom::Err::Failure(error) => {
+                let offset = input.len() - error.input.len();
+                match error.code {
+                    ErrorKind::Eof => Self::UnexpectedEof { offset },
+                    kind => Self::Malformed { offset, kind },
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnexpectedEof { .. } => write!(f, "unexpected end of input")?,
+            Self::Malformed { kind, .. } => write!(f, "malformed input ({:?})", kind)?,
+        }
+        write!(f, " at offset {:#x}", self.offset())
+    }
+}
+
+impl std::error::Error for DeserializeError {}
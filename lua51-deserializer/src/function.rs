@@ -1,11 +1,7 @@
-use nom::{
-    combinator::opt,
-    multi::count,
-    number::complete::{le_u32, le_u8},
-    IResult,
-};
+use nom::{combinator::opt, multi::count, number::complete::le_u8, IResult};
 
 use crate::{
+    chunk::Header,
     instruction::{position::Position, Instruction},
     local::Local,
     value::{self, Value},
@@ -29,23 +25,30 @@ pub struct Function<'a> {
 }
 
 impl<'a> Function<'a> {
-    pub fn parse(input: &'a [u8]) -> IResult<&'a [u8], Self> {
-        let (input, name) = value::parse_string(input)?;
-        let (input, line_defined) = le_u32(input)?;
-        let (input, last_line_defined) = le_u32(input)?;
+    pub fn parse(input: &'a [u8], header: &Header) -> IResult<&'a [u8], Self> {
+        let (input, name) = value::parse_string(input, header)?;
+        let (input, line_defined) = header.parse_int(input)?;
+        let (input, last_line_defined) = header.parse_int(input)?;
         let (input, number_of_upvalues) = le_u8(input)?;
         let (input, number_of_parameters) = le_u8(input)?;
         let (input, vararg_flag) = le_u8(input)?;
         let (input, maximum_stack_size) = le_u8(input)?;
-        let (input, code_length) = le_u32(input)?;
-        let (input, code) = count(Instruction::parse, code_length as usize)(input)?;
-        let (input, constants_length) = le_u32(input)?;
-        let (input, constants) = count(Value::parse, constants_length as usize)(input)?;
-        let (input, closures_length) = le_u32(input)?;
-        let (input, closures) = count(Self::parse, closures_length as usize)(input)?;
-        let (input, positions) = opt(Position::parse)(input)?;
-        let (input, locals) = opt(Local::parse_list)(input)?;
-        let (input, upvalues) = opt(value::parse_strings)(input)?;
+        let (input, code_length) = header.parse_int(input)?;
+        let (input, code) = count(
+            |input| Instruction::parse(input, header),
+            code_length as usize,
+        )(input)?;
+        let (input, constants_length) = header.parse_int(input)?;
+        let (input, constants) = count(
+            |input| Value::parse(input, header),
+            constants_length as usize,
+        )(input)?;
+        let (input, closures_length) = header.parse_int(input)?;
+        let (input, closures) =
+            count(|input| Self::parse(input, header), closures_length as usize)(input)?;
+        let (input, positions) = opt(|input| Position::parse(input, header))(input)?;
+        let (input, locals) = opt(|input| Local::parse_list(input, header))(input)?;
+        let (input, upvalues) = opt(|input| value::parse_strings(input, header))(input)?;
 
         Ok((
             input,
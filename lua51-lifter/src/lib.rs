@@ -0,0 +1,37 @@
+#![feature(let_chains)]
+
+use lifter::Lifter;
+use parking_lot::Mutex;
+use pipeline::{isolate, Lifted};
+use triomphe::Arc;
+
+pub use lua51_deserializer::DeserializeError;
+pub use pipeline::install_panic_hook;
+
+mod lifter;
+
+pub type DecompileError = pipeline::DecompileError<DeserializeError>;
+
+pub fn decompile_bytecode(bytecode: &[u8]) -> Result<String, DecompileError> {
+    let chunk = lua51_deserializer::deserialize(bytecode).map_err(DecompileError::Deserialize)?;
+    let mut lifted = Vec::new();
+    let (function, upvalues) = isolate(false, || Lifter::lift(&chunk.function, &mut lifted))
+        .map_err(DecompileError::Lift)?;
+    let main = Arc::<Mutex<_>>::default();
+    lifted.push((main.clone(), function, upvalues));
+
+    let lifted = lifted
+        .into_iter()
+        .rev()
+        .map(|(ast_function, function, upvalues_in)| Lifted {
+            ast_function,
+            function,
+            upvalues_in,
+            data: (),
+        })
+        .collect();
+    let (upvalues, _) = pipeline::decompile_functions(lifted, &Default::default(), |_, _| {
+        "failed to decompile".to_string()
+    });
+    Ok(pipeline::link(main, upvalues, false).to_string())
+}
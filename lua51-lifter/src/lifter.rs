@@ -6,7 +6,7 @@ use itertools::Itertools;
 use parking_lot::Mutex;
 use rustc_hash::FxHashMap;
 
-use ast::{RcLocal, Statement};
+use ast::{name_locals::name_written_locals, RcLocal, Statement};
 use cfg::function::Function;
 
 use lua51_deserializer::{
@@ -26,21 +26,48 @@ pub struct Lifter<'a, 'b> {
     constants: FxHashMap<usize, ast::Literal>,
     function: Function,
     upvalues: Vec<RcLocal>,
+    // debug names of locals by the pc they're live from and their register
+    local_names: FxHashMap<(usize, Register), String>,
     lifted_functions: &'b mut Vec<(Arc<Mutex<ast::Function>>, Function, Vec<RcLocal>)>,
 }
 
 impl<'a, 'b> Lifter<'a, 'b> {
+    // 5.1 doesn't record the register of a local, it's the number of locals
+    // that are still live when it's declared
+    fn collect_local_names(&mut self) {
+        let locals = &self.bytecode.locals;
+        for (index, local) in locals.iter().enumerate() {
+            let register = locals[..index]
+                .iter()
+                .filter(|outer| outer.range.contains(&local.range.start))
+                .count();
+            self.local_names.insert(
+                (local.range.start as usize, Register(register as u8)),
+                String::from_utf8_lossy(local.name).into_owned(),
+            );
+        }
+    }
+
     fn allocate_locals(&mut self) {
         self.upvalues
             .reserve(self.bytecode.number_of_upvalues as usize);
-        for _ in 0..self.bytecode.number_of_upvalues {
-            self.upvalues.push(RcLocal::default());
+        for i in 0..self.bytecode.number_of_upvalues {
+            // upvalue names include the null terminator
+            let name = self.bytecode.upvalues.get(i as usize).map(|name| {
+                String::from_utf8_lossy(name.strip_suffix(b"\0").unwrap_or(name)).into_owned()
+            });
+            self.upvalues.push(RcLocal::new(ast::Local::new(name)));
         }
 
         self.locals
             .reserve(self.bytecode.maximum_stack_size as usize);
         for i in 0..self.bytecode.maximum_stack_size {
-            let local = RcLocal::default();
+            let local = if i < self.bytecode.number_of_parameters {
+                let name = self.local_names.get(&(0, Register(i))).cloned();
+                RcLocal::new(ast::Local::new(name))
+            } else {
+                RcLocal::default()
+            };
             if i < self.bytecode.number_of_parameters {
                 self.function.parameters.push(local.clone());
             }
@@ -159,6 +186,7 @@ impl<'a, 'b> Lifter<'a, 'b> {
         // TODO: we should consume the instructions, reducing clones
         let mut iter = self.bytecode.code[start..=end].iter();
         while let Some(instruction) = iter.next() {
+            let first_statement = statements.len();
             match instruction {
                 Instruction::Move {
                     destination,
@@ -785,12 +813,36 @@ impl<'a, 'b> Lifter<'a, 'b> {
                 }
             }
 
+            let next_pc = end + 1 - iter.len();
+            self.name_written_locals(statements, first_statement, next_pc);
+
             if matches!(instruction, Instruction::Return { .. }) {
                 break;
             }
         }
     }
 
+    // debug info names a local from the pc right after the instructions that initialize it
+    fn name_written_locals(
+        &self,
+        statements: &mut Vec<Statement>,
+        first_statement: usize,
+        next_pc: usize,
+    ) {
+        if self.local_names.is_empty() {
+            return;
+        }
+
+        name_written_locals(statements, first_statement, |local| {
+            let (&register, _) = self
+                .locals
+                .iter()
+                .find(|(_, register_local)| *register_local == local)?;
+            let name = self.local_names.get(&(next_pc, register))?;
+            Some(RcLocal::new(ast::Local::new(Some(name.clone()))))
+        });
+    }
+
     // TODO: REFACTOR: this function doesnt need to exist
     fn get_node(&'a self, index: &'a usize) -> NodeIndex {
         self.nodes[index]
@@ -886,10 +938,12 @@ impl<'a, 'b> Lifter<'a, 'b> {
             constants: FxHashMap::default(),
             function: Function::new(0),
             upvalues: Vec::new(),
+            local_names: FxHashMap::default(),
             lifted_functions,
         };
 
         context.create_block_map();
+        context.collect_local_names();
         context.allocate_locals();
         context.lift_blocks();
 
@@ -0,0 +1,47 @@
+use lua51_lifter::{decompile_bytecode, DecompileError, DeserializeError};
+use nom::error::ErrorKind;
+
+// a little-endian header with 4-byte ints, sizes and instructions, and 8-byte numbers
+const HEADER: &[u8] = b"\x1BLua\x51\x00\x01\x04\x04\x04\x08\x00";
+
+fn deserialize_error(bytecode: &[u8]) -> DeserializeError {
+    match decompile_bytecode(bytecode) {
+        Err(DecompileError::Deserialize(err)) => err,
+        Err(err) => panic!("expected a deserialize error, got {}", err),
+        Ok(source) => panic!("expected a deserialize error, got:\n{}", source),
+    }
+}
+
+#[test]
+fn rejects_unknown_signatures() {
+    let mut bytecode = HEADER.to_vec();
+    bytecode[1] = b'l';
+    assert_eq!(
+        deserialize_error(&bytecode),
+        DeserializeError::Malformed {
+            offset: 0,
+            kind: ErrorKind::Tag,
+        }
+    );
+}
+
+#[test]
+fn rejects_unknown_byte_orders() {
+    let mut bytecode = HEADER.to_vec();
+    bytecode[6] = 2;
+    assert_eq!(
+        deserialize_error(&bytecode),
+        DeserializeError::Malformed {
+            offset: 6,
+            kind: ErrorKind::Switch,
+        }
+    );
+}
+
+#[test]
+fn rejects_truncated_headers() {
+    assert!(matches!(
+        deserialize_error(&HEADER[..8]),
+        DeserializeError::UnexpectedEof { .. }
+    ));
+}
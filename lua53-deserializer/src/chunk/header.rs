@@ -0,0 +1,231 @@
+use nom::{
+    bytes::complete::{tag, take},
+    error::{Error, ErrorKind, ParseError},
+    number::{
+        self,
+        complete::{f32, f64, i32, i64, le_u8, u32, u64},
+    },
+    Err, IResult,
+};
+
+const LUAC_DATA: &[u8] = b"\x19\x93\r\n\x1a\n";
+const LUAC_INT: i64 = 0x5678;
+const LUAC_NUM: f64 = 370.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Version {
+    Lua52,
+    Lua53,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Endianness {
+    Big,
+    Little,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Format {
+    Official,
+}
+
+#[derive(Debug)]
+pub struct Header {
+    pub(crate) version: Version,
+    pub(crate) format: Format,
+    pub(crate) endianness: Endianness,
+    pub(crate) int_width: u8,
+    pub(crate) size_t_width: u8,
+    pub(crate) instr_width: u8,
+    // `lua_Integer`, 5.3 only
+    pub(crate) integer_width: u8,
+    pub(crate) number_width: u8,
+    // 5.2 can be built with an integral `lua_Number`
+    pub(crate) number_is_integral: bool,
+}
+
+impl Header {
+    pub fn parse(input: &[u8]) -> IResult<&[u8], Self> {
+        let (input, _) = tag("\x1BLua")(input)?;
+        let (input, version) = match le_u8(input)? {
+            (input, 0x52) => Ok((input, Version::Lua52)),
+            (input, 0x53) => Ok((input, Version::Lua53)),
+            _ => Err(Err::Failure(Error::from_error_kind(
+                input,
+                ErrorKind::Verify,
+            ))),
+        }?;
+        let (input, format) = match le_u8(input)? {
+            (input, 0) => Ok((input, Format::Official)),
+            _ => Err(Err::Failure(Error::from_error_kind(
+                input,
+                ErrorKind::Switch,
+            ))),
+        }?;
+        match version {
+            Version::Lua52 => Self::parse_52(input, format),
+            Version::Lua53 => Self::parse_53(input, format),
+        }
+    }
+
+    fn parse_52(input: &[u8], format: Format) -> IResult<&[u8], Self> {
+        let (input, endianness) = match le_u8(input)? {
+            (input, 0) => Ok((input, Endianness::Big)),
+            (input, 1) => Ok((input, Endianness::Little)),
+            _ => Err(Err::Failure(Error::from_error_kind(
+                input,
+                ErrorKind::Switch,
+            ))),
+        }?;
+        let (input, int_width) = le_u8(input)?;
+        let (input, size_t_width) = le_u8(input)?;
+        let (input, instr_width) = le_u8(input)?;
+        let (input, number_width) = le_u8(input)?;
+        let (input, number_is_integral) = match le_u8(input)? {
+            (input, 0) => Ok((input, false)),
+            (input, 1) => Ok((input, true)),
+            _ => Err(Err::Failure(Error::from_error_kind(
+                input,
+                ErrorKind::Switch,
+            ))),
+        }?;
+        let (input, _) = tag(LUAC_DATA)(input)?;
+
+        Ok((
+            input,
+            Self {
+                version: Version::Lua52,
+                format,
+                endianness,
+                int_width,
+                size_t_width,
+                instr_width,
+                integer_width: 0,
+                number_width,
+                number_is_integral,
+            },
+        ))
+    }
+
+    fn parse_53(input: &[u8], format: Format) -> IResult<&[u8], Self> {
+        let (input, _) = tag(LUAC_DATA)(input)?;
+        let (input, int_width) = le_u8(input)?;
+        let (input, size_t_width) = le_u8(input)?;
+        let (input, instr_width) = le_u8(input)?;
+        let (input, integer_width) = le_u8(input)?;
+        let (input, number_width) = le_u8(input)?;
+        // 5.3 doesn't store the byte order, it's inferred from a known integer
+        let (_, check) = take(integer_width)(input)?;
+        let endianness = if check.first() == Some(&0x78) {
+            Endianness::Little
+        } else {
+            Endianness::Big
+        };
+        let header = Self {
+            version: Version::Lua53,
+            format,
+            endianness,
+            int_width,
+            size_t_width,
+            instr_width,
+            integer_width,
+            number_width,
+            number_is_integral: false,
+        };
+        let (input, integer) = header.parse_integer(input)?;
+        if integer != LUAC_INT {
+            return Err(Err::Failure(Error::from_error_kind(
+                input,
+                ErrorKind::Verify,
+            )));
+        }
+        let (input, number) = header.parse_number(input)?;
+        if number != LUAC_NUM {
+            return Err(Err::Failure(Error::from_error_kind(
+                input,
+                ErrorKind::Verify,
+            )));
+        }
+
+        Ok((input, header))
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+}
+
+// readers for the types whose width and byte order are given by the header
+impl Header {
+    fn nom_endianness(&self) -> number::Endianness {
+        match self.endianness {
+            Endianness::Big => number::Endianness::Big,
+            Endianness::Little => number::Endianness::Little,
+        }
+    }
+
+    fn parse_unsigned<'a>(&self, input: &'a [u8], width: u8) -> IResult<&'a [u8], u64> {
+        match width {
+            4 => u32(self.nom_endianness())(input).map(|(input, value)| (input, value.into())),
+            8 => u64(self.nom_endianness())(input),
+            _ => Err(Err::Failure(Error::from_error_kind(
+                input,
+                ErrorKind::Switch,
+            ))),
+        }
+    }
+
+    // fails rather than truncating 8-byte values that don't fit
+    fn parse_narrow<'a, T: TryFrom<u64>>(
+        &self,
+        input: &'a [u8],
+        width: u8,
+    ) -> IResult<&'a [u8], T> {
+        let (rest, value) = self.parse_unsigned(input, width)?;
+        match T::try_from(value) {
+            Ok(value) => Ok((rest, value)),
+            Err(_) => Err(Err::Failure(Error::from_error_kind(
+                input,
+                ErrorKind::TooLarge,
+            ))),
+        }
+    }
+
+    pub fn parse_int<'a>(&self, input: &'a [u8]) -> IResult<&'a [u8], u32> {
+        self.parse_narrow(input, self.int_width)
+    }
+
+    pub fn parse_size_t<'a>(&self, input: &'a [u8]) -> IResult<&'a [u8], usize> {
+        self.parse_narrow(input, self.size_t_width)
+    }
+
+    pub fn parse_instruction<'a>(&self, input: &'a [u8]) -> IResult<&'a [u8], u32> {
+        self.parse_narrow(input, self.instr_width)
+    }
+
+    pub fn parse_integer<'a>(&self, input: &'a [u8]) -> IResult<&'a [u8], i64> {
+        let endianness = self.nom_endianness();
+        match self.integer_width {
+            4 => i32(endianness)(input).map(|(input, value)| (input, value.into())),
+            8 => i64(endianness)(input),
+            _ => Err(Err::Failure(Error::from_error_kind(
+                input,
+                ErrorKind::Switch,
+            ))),
+        }
+    }
+
+    pub fn parse_number<'a>(&self, input: &'a [u8]) -> IResult<&'a [u8], f64> {
+        let endianness = self.nom_endianness();
+        match (self.number_width, self.number_is_integral) {
+            (4, false) => f32(endianness)(input).map(|(input, value)| (input, value.into())),
+            (8, false) => f64(endianness)(input),
+            (4, true) => i32(endianness)(input).map(|(input, value)| (input, value.into())),
+            (8, true) => i64(endianness)(input).map(|(input, value)| (input, value as f64)),
+            _ => Err(Err::Failure(Error::from_error_kind(
+                input,
+                ErrorKind::Switch,
+            ))),
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use nom::{
+    error::{Error, ErrorKind, ParseError},
+    number::complete::le_u8,
+    Err, IResult,
+};
+
+pub use header::{Header, Version};
+
+use crate::{chunk::header::Format, function::Function};
+
+pub mod header;
+
+#[derive(Debug)]
+pub struct Chunk<'a> {
+    pub version: Version,
+    pub function: Function<'a>,
+}
+
+impl<'a> Chunk<'a> {
+    pub fn parse(input: &'a [u8]) -> IResult<&'a [u8], Self> {
+        let (input, header) = Header::parse(input)?;
+        // instructions are always 32 bits wide in 5.2 and 5.3
+        if header.format != Format::Official
+            || header.instr_width != 4
+            || ![4, 8].contains(&header.int_width)
+            || ![4, 8].contains(&header.size_t_width)
+            || ![4, 8].contains(&header.number_width)
+        {
+            return Err(Err::Failure(Error::from_error_kind(
+                input,
+                ErrorKind::Verify,
+            )));
+        }
+        // 5.3 stores the number of upvalues of the main function twice
+        let input = match header.version {
+            Version::Lua52 => input,
+            Version::Lua53 => le_u8(input)?.0,
+        };
+        let (input, function) = Function::parse(input, &header, b"")?;
+
+        Ok((
+            input,
+            Self {
+                version: header.version,
+                function,
+            },
+        ))
+    }
+}
@@ -0,0 +1,45 @@
+use strum_macros::EnumDiscriminants;
+
+use super::OperationCode;
+
+#[derive(Debug, EnumDiscriminants)]
+pub enum Layout {
+    BC { a: u8, b: u16, c: u16 },
+    // b extended
+    BX { a: u8, b_x: u32 },
+    // b signed, extended
+    BSx { a: u8, b_sx: i32 },
+    // a extended, only used by EXTRAARG
+    AX { a_x: u32 },
+}
+
+impl Layout {
+    pub fn parse(instruction: u32, operation_code: &OperationCode) -> Self {
+        match operation_code.instruction_layout() {
+            LayoutDiscriminants::BC => {
+                let a = ((instruction >> 6) & 0xFF) as u8;
+                let c = ((instruction >> 14) & 0x1FF) as u16;
+                let b = ((instruction >> 23) & 0x1FF) as u16;
+
+                Self::BC { a, b, c }
+            }
+            LayoutDiscriminants::BX => {
+                let a = ((instruction >> 6) & 0xFF) as u8;
+                let b_x = (instruction >> 14) & 0x3FFFF;
+
+                Self::BX { a, b_x }
+            }
+            LayoutDiscriminants::BSx => {
+                let a = ((instruction >> 6) & 0xFF) as u8;
+                let b_x = (instruction >> 14) & 0x3FFFF;
+                // subtract maximum 18 bit signed int
+                let b_sx = b_x as i32 - (((1 << 18) - 1) >> 1);
+
+                Self::BSx { a, b_sx }
+            }
+            LayoutDiscriminants::AX => Self::AX {
+                a_x: instruction >> 6,
+            },
+        }
+    }
+}
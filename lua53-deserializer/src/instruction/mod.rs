@@ -0,0 +1,531 @@
+use nom::{
+    error::{Error, ErrorKind, ParseError},
+    multi::count,
+    Err, IResult,
+};
+
+use crate::chunk::Header;
+
+use argument::{Constant, Function, Register, RegisterOrConstant, Upvalue};
+use layout::Layout;
+use operation_code::OperationCode;
+
+pub mod argument;
+mod layout;
+mod operation_code;
+pub mod position;
+
+#[derive(Debug)]
+struct RawInstruction(OperationCode, Layout);
+
+impl RawInstruction {
+    pub fn parse<'a>(input: &'a [u8], header: &Header) -> IResult<&'a [u8], Self> {
+        let (input, instruction) = header.parse_instruction(input)?;
+        let operation_code = OperationCode::from_instruction(instruction, header.version)
+            .ok_or_else(|| Err::Failure(Error::from_error_kind(input, ErrorKind::Switch)))?;
+        let layout = Layout::parse(instruction, &operation_code);
+
+        Ok((input, Self(operation_code, layout)))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Instruction {
+    Move {
+        destination: Register,
+        source: Register,
+    },
+    LoadConstant {
+        destination: Register,
+        source: Constant,
+    },
+    LoadBoolean {
+        destination: Register,
+        value: bool,
+        skip_next: bool,
+    },
+    LoadNil(Vec<Register>),
+    GetUpvalue {
+        destination: Register,
+        upvalue: Upvalue,
+    },
+    // ex. `_ENV.print` for a global
+    GetTableUpvalue {
+        destination: Register,
+        upvalue: Upvalue,
+        key: RegisterOrConstant,
+    },
+    GetIndex {
+        destination: Register,
+        object: Register,
+        key: RegisterOrConstant,
+    },
+    SetTableUpvalue {
+        upvalue: Upvalue,
+        key: RegisterOrConstant,
+        value: RegisterOrConstant,
+    },
+    SetUpvalue {
+        destination: Upvalue,
+        source: Register,
+    },
+    SetIndex {
+        object: Register,
+        key: RegisterOrConstant,
+        value: RegisterOrConstant,
+    },
+    NewTable {
+        destination: Register,
+        array_size: u8,
+        hash_size: u8,
+    },
+    PrepMethodCall {
+        destination: Register,
+        self_arg: Register,
+        object: Register,
+        method: RegisterOrConstant,
+    },
+    Add {
+        destination: Register,
+        lhs: RegisterOrConstant,
+        rhs: RegisterOrConstant,
+    },
+    Sub {
+        destination: Register,
+        lhs: RegisterOrConstant,
+        rhs: RegisterOrConstant,
+    },
+    Mul {
+        destination: Register,
+        lhs: RegisterOrConstant,
+        rhs: RegisterOrConstant,
+    },
+    Div {
+        destination: Register,
+        lhs: RegisterOrConstant,
+        rhs: RegisterOrConstant,
+    },
+    Mod {
+        destination: Register,
+        lhs: RegisterOrConstant,
+        rhs: RegisterOrConstant,
+    },
+    Pow {
+        destination: Register,
+        lhs: RegisterOrConstant,
+        rhs: RegisterOrConstant,
+    },
+    IDiv {
+        destination: Register,
+        lhs: RegisterOrConstant,
+        rhs: RegisterOrConstant,
+    },
+    BitwiseAnd {
+        destination: Register,
+        lhs: RegisterOrConstant,
+        rhs: RegisterOrConstant,
+    },
+    BitwiseOr {
+        destination: Register,
+        lhs: RegisterOrConstant,
+        rhs: RegisterOrConstant,
+    },
+    BitwiseXor {
+        destination: Register,
+        lhs: RegisterOrConstant,
+        rhs: RegisterOrConstant,
+    },
+    ShiftLeft {
+        destination: Register,
+        lhs: RegisterOrConstant,
+        rhs: RegisterOrConstant,
+    },
+    ShiftRight {
+        destination: Register,
+        lhs: RegisterOrConstant,
+        rhs: RegisterOrConstant,
+    },
+    Minus {
+        destination: Register,
+        operand: Register,
+    },
+    BitwiseNot {
+        destination: Register,
+        operand: Register,
+    },
+    Not {
+        destination: Register,
+        operand: Register,
+    },
+    Length {
+        destination: Register,
+        operand: Register,
+    },
+    Concatenate {
+        destination: Register,
+        operands: Vec<Register>,
+    },
+    Jump {
+        skip: i32,
+        // upvalues from this register up are closed before jumping
+        close: Option<Register>,
+    },
+    Equal {
+        lhs: RegisterOrConstant,
+        rhs: RegisterOrConstant,
+        invert: bool,
+    },
+    LessThan {
+        lhs: RegisterOrConstant,
+        rhs: RegisterOrConstant,
+        invert: bool,
+    },
+    LessThanOrEqual {
+        lhs: RegisterOrConstant,
+        rhs: RegisterOrConstant,
+        invert: bool,
+    },
+    Test {
+        value: Register,
+        invert: bool,
+    },
+    TestSet {
+        destination: Register,
+        value: Register,
+        invert: bool,
+    },
+    Call {
+        function: Register,
+        arguments: u8,
+        return_values: u8,
+    },
+    TailCall {
+        function: Register,
+        arguments: u8,
+    },
+    Return(Register, u8),
+    IterateNumericForLoop {
+        // internal_counter, limit, step, external_counter
+        control: Vec<Register>,
+        skip: i32,
+    },
+    InitNumericForLoop {
+        // internal_counter, limit, step, external_counter
+        // the name "control" refers to just the counter
+        control: Vec<Register>,
+        skip: i32,
+    },
+    // TFORCALL, always followed by TFORLOOP
+    GenericForCall {
+        // ex. `next` in `for i, v in next, {}, 5`
+        generator: Register,
+        // ex. `{}` in `for i, v in next, {}, 5`
+        state: Register,
+        // internal control variable
+        // initial value ex. `5` in `for i, v in next, {}, 5`
+        internal_control: Register,
+        // variables returned by generator call, starting with the external control
+        vars: Vec<Register>,
+    },
+    // TFORLOOP, jumps back to the body if the external control isn't nil
+    IterateGenericForLoop {
+        internal_control: Register,
+        external_control: Register,
+        skip: i32,
+    },
+    SetList {
+        table: Register,
+        number_of_elements: u8,
+        block_number: u32,
+    },
+    Closure {
+        destination: Register,
+        function: Function,
+    },
+    VarArg(Register, u8),
+    // the argument of the previous instruction, already folded into it
+    ExtraArgument(u32),
+}
+
+impl Instruction {
+    pub fn parse_list<'a>(input: &'a [u8], header: &Header) -> IResult<&'a [u8], Vec<Self>> {
+        let (input, code_length) = header.parse_int(input)?;
+        let (input, code) = count(
+            |input| RawInstruction::parse(input, header),
+            code_length as usize,
+        )(input)?;
+
+        let instructions = code
+            .iter()
+            .enumerate()
+            .map(|(index, instruction)| {
+                // LOADKX and SETLIST can take their argument from the next instruction
+                let extra_argument = match code.get(index + 1) {
+                    Some(&RawInstruction(OperationCode::ExtraArgument, Layout::AX { a_x })) => {
+                        Some(a_x)
+                    }
+                    _ => None,
+                };
+                Self::from_raw(instruction, extra_argument)
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| Err::Failure(Error::from_error_kind(input, ErrorKind::Switch)))?;
+
+        Ok((input, instructions))
+    }
+
+    fn from_raw(instruction: &RawInstruction, extra_argument: Option<u32>) -> Option<Self> {
+        let instruction = match *instruction {
+            RawInstruction(OperationCode::Move, Layout::BC { a, b, .. }) => Self::Move {
+                destination: Register(a),
+                source: Register(b as u8),
+            },
+            RawInstruction(OperationCode::LoadConstant, Layout::BX { a, b_x }) => {
+                Self::LoadConstant {
+                    destination: Register(a),
+                    source: Constant(b_x),
+                }
+            }
+            RawInstruction(OperationCode::LoadConstantExtended, Layout::BC { a, .. }) => {
+                Self::LoadConstant {
+                    destination: Register(a),
+                    source: Constant(extra_argument?),
+                }
+            }
+            RawInstruction(OperationCode::LoadBoolean, Layout::BC { a, b, c }) => {
+                Self::LoadBoolean {
+                    destination: Register(a),
+                    value: b == 1,
+                    skip_next: c == 1,
+                }
+            }
+            RawInstruction(OperationCode::LoadNil, Layout::BC { a, b, .. }) => {
+                Self::LoadNil((a..=a + b as u8).map(Register).collect())
+            }
+            RawInstruction(OperationCode::GetUpvalue, Layout::BC { a, b, .. }) => {
+                Self::GetUpvalue {
+                    destination: Register(a),
+                    upvalue: Upvalue(b as u8),
+                }
+            }
+            RawInstruction(OperationCode::GetTableUpvalue, Layout::BC { a, b, c }) => {
+                Self::GetTableUpvalue {
+                    destination: Register(a),
+                    upvalue: Upvalue(b as u8),
+                    key: RegisterOrConstant::from(c as u32),
+                }
+            }
+            RawInstruction(OperationCode::GetIndex, Layout::BC { a, b, c }) => Self::GetIndex {
+                destination: Register(a),
+                object: Register(b as u8),
+                key: RegisterOrConstant::from(c as u32),
+            },
+            RawInstruction(OperationCode::SetTableUpvalue, Layout::BC { a, b, c }) => {
+                Self::SetTableUpvalue {
+                    upvalue: Upvalue(a),
+                    key: RegisterOrConstant::from(b as u32),
+                    value: RegisterOrConstant::from(c as u32),
+                }
+            }
+            RawInstruction(OperationCode::SetUpvalue, Layout::BC { a, b, .. }) => {
+                Self::SetUpvalue {
+                    destination: Upvalue(b as u8),
+                    source: Register(a),
+                }
+            }
+            RawInstruction(OperationCode::SetIndex, Layout::BC { a, b, c }) => Self::SetIndex {
+                object: Register(a),
+                key: RegisterOrConstant::from(b as u32),
+                value: RegisterOrConstant::from(c as u32),
+            },
+            RawInstruction(OperationCode::NewTable, Layout::BC { a, b, c }) => Self::NewTable {
+                destination: Register(a),
+                array_size: b as u8,
+                hash_size: c as u8,
+            },
+            RawInstruction(OperationCode::PrepMethodCall, Layout::BC { a, b, c }) => {
+                Self::PrepMethodCall {
+                    destination: Register(a),
+                    self_arg: Register(a + 1),
+                    object: Register(b as u8),
+                    method: RegisterOrConstant::from(c as u32),
+                }
+            }
+            RawInstruction(OperationCode::Add, Layout::BC { a, b, c }) => Self::Add {
+                destination: Register(a),
+                lhs: RegisterOrConstant::from(b as u32),
+                rhs: RegisterOrConstant::from(c as u32),
+            },
+            RawInstruction(OperationCode::Subtract, Layout::BC { a, b, c }) => Self::Sub {
+                destination: Register(a),
+                lhs: RegisterOrConstant::from(b as u32),
+                rhs: RegisterOrConstant::from(c as u32),
+            },
+            RawInstruction(OperationCode::Multiply, Layout::BC { a, b, c }) => Self::Mul {
+                destination: Register(a),
+                lhs: RegisterOrConstant::from(b as u32),
+                rhs: RegisterOrConstant::from(c as u32),
+            },
+            RawInstruction(OperationCode::Divide, Layout::BC { a, b, c }) => Self::Div {
+                destination: Register(a),
+                lhs: RegisterOrConstant::from(b as u32),
+                rhs: RegisterOrConstant::from(c as u32),
+            },
+            RawInstruction(OperationCode::Modulo, Layout::BC { a, b, c }) => Self::Mod {
+                destination: Register(a),
+                lhs: RegisterOrConstant::from(b as u32),
+                rhs: RegisterOrConstant::from(c as u32),
+            },
+            RawInstruction(OperationCode::Power, Layout::BC { a, b, c }) => Self::Pow {
+                destination: Register(a),
+                lhs: RegisterOrConstant::from(b as u32),
+                rhs: RegisterOrConstant::from(c as u32),
+            },
+            RawInstruction(OperationCode::FloorDivide, Layout::BC { a, b, c }) => Self::IDiv {
+                destination: Register(a),
+                lhs: RegisterOrConstant::from(b as u32),
+                rhs: RegisterOrConstant::from(c as u32),
+            },
+            RawInstruction(OperationCode::BitwiseAnd, Layout::BC { a, b, c }) => Self::BitwiseAnd {
+                destination: Register(a),
+                lhs: RegisterOrConstant::from(b as u32),
+                rhs: RegisterOrConstant::from(c as u32),
+            },
+            RawInstruction(OperationCode::BitwiseOr, Layout::BC { a, b, c }) => Self::BitwiseOr {
+                destination: Register(a),
+                lhs: RegisterOrConstant::from(b as u32),
+                rhs: RegisterOrConstant::from(c as u32),
+            },
+            RawInstruction(OperationCode::BitwiseXor, Layout::BC { a, b, c }) => Self::BitwiseXor {
+                destination: Register(a),
+                lhs: RegisterOrConstant::from(b as u32),
+                rhs: RegisterOrConstant::from(c as u32),
+            },
+            RawInstruction(OperationCode::ShiftLeft, Layout::BC { a, b, c }) => Self::ShiftLeft {
+                destination: Register(a),
+                lhs: RegisterOrConstant::from(b as u32),
+                rhs: RegisterOrConstant::from(c as u32),
+            },
+            RawInstruction(OperationCode::ShiftRight, Layout::BC { a, b, c }) => Self::ShiftRight {
+                destination: Register(a),
+                lhs: RegisterOrConstant::from(b as u32),
+                rhs: RegisterOrConstant::from(c as u32),
+            },
+            RawInstruction(OperationCode::Minus, Layout::BC { a, b, .. }) => Self::Minus {
+                destination: Register(a),
+                operand: Register(b as u8),
+            },
+            RawInstruction(OperationCode::BitwiseNot, Layout::BC { a, b, .. }) => {
+                Self::BitwiseNot {
+                    destination: Register(a),
+                    operand: Register(b as u8),
+                }
+            }
+            RawInstruction(OperationCode::Not, Layout::BC { a, b, .. }) => Self::Not {
+                destination: Register(a),
+                operand: Register(b as u8),
+            },
+            RawInstruction(OperationCode::Length, Layout::BC { a, b, .. }) => Self::Length {
+                destination: Register(a),
+                operand: Register(b as u8),
+            },
+            RawInstruction(OperationCode::Concatenate, Layout::BC { a, b, c }) => {
+                Self::Concatenate {
+                    destination: Register(a),
+                    operands: (b..=c).map(|r| Register(r as u8)).collect(),
+                }
+            }
+            RawInstruction(OperationCode::Jump, Layout::BSx { a, b_sx }) => Self::Jump {
+                skip: b_sx,
+                close: a.checked_sub(1).map(Register),
+            },
+            RawInstruction(OperationCode::Equal, Layout::BC { a, b, c }) => Self::Equal {
+                lhs: RegisterOrConstant::from(b as u32),
+                rhs: RegisterOrConstant::from(c as u32),
+                invert: a != 1,
+            },
+            RawInstruction(OperationCode::LessThan, Layout::BC { a, b, c }) => Self::LessThan {
+                lhs: RegisterOrConstant::from(b as u32),
+                rhs: RegisterOrConstant::from(c as u32),
+                invert: a != 1,
+            },
+            RawInstruction(OperationCode::LessThanOrEqual, Layout::BC { a, b, c }) => {
+                Self::LessThanOrEqual {
+                    lhs: RegisterOrConstant::from(b as u32),
+                    rhs: RegisterOrConstant::from(c as u32),
+                    invert: a != 1,
+                }
+            }
+            RawInstruction(OperationCode::Test, Layout::BC { a, c, .. }) => Self::Test {
+                value: Register(a),
+                invert: c != 1,
+            },
+            RawInstruction(OperationCode::TestSet, Layout::BC { a, b, c }) => Self::TestSet {
+                destination: Register(a),
+                value: Register(b as u8),
+                invert: c != 1,
+            },
+            RawInstruction(OperationCode::Call, Layout::BC { a, b, c }) => Self::Call {
+                function: Register(a),
+                arguments: b as u8,
+                return_values: c as u8,
+            },
+            RawInstruction(OperationCode::TailCall, Layout::BC { a, b, .. }) => Self::TailCall {
+                function: Register(a),
+                arguments: b as u8,
+            },
+            RawInstruction(OperationCode::Return, Layout::BC { a, b, .. }) => {
+                Self::Return(Register(a), b as u8)
+            }
+            RawInstruction(OperationCode::IterateNumericForLoop, Layout::BSx { a, b_sx }) => {
+                Self::IterateNumericForLoop {
+                    control: (a..=a + 3).map(Register).collect(),
+                    skip: b_sx,
+                }
+            }
+            RawInstruction(OperationCode::InitNumericForLoop, Layout::BSx { a, b_sx }) => {
+                Self::InitNumericForLoop {
+                    control: (a..=a + 3).map(Register).collect(),
+                    skip: b_sx,
+                }
+            }
+            RawInstruction(OperationCode::GenericForCall, Layout::BC { a, c, .. }) => {
+                // must have at least external control variable
+                if c == 0 {
+                    return None;
+                }
+                Self::GenericForCall {
+                    generator: Register(a),
+                    state: Register(a + 1),
+                    internal_control: Register(a + 2),
+                    vars: (a + 3..a + 3 + c as u8).map(Register).collect(),
+                }
+            }
+            RawInstruction(OperationCode::IterateGenericForLoop, Layout::BSx { a, b_sx }) => {
+                Self::IterateGenericForLoop {
+                    internal_control: Register(a),
+                    external_control: Register(a + 1),
+                    skip: b_sx,
+                }
+            }
+            RawInstruction(OperationCode::SetList, Layout::BC { a, b, c }) => Self::SetList {
+                table: Register(a),
+                number_of_elements: b as u8,
+                block_number: if c == 0 { extra_argument? } else { c as u32 },
+            },
+            RawInstruction(OperationCode::Closure, Layout::BX { a, b_x }) => Self::Closure {
+                destination: Register(a),
+                function: Function(b_x),
+            },
+            RawInstruction(OperationCode::VarArg, Layout::BC { a, b, .. }) => {
+                Self::VarArg(Register(a), b as u8)
+            }
+            RawInstruction(OperationCode::ExtraArgument, Layout::AX { a_x }) => {
+                Self::ExtraArgument(a_x)
+            }
+            _ => return None,
+        };
+
+        Some(instruction)
+    }
+}
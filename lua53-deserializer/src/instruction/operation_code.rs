@@ -0,0 +1,168 @@
+use crate::{chunk::Version, instruction::layout::LayoutDiscriminants};
+
+#[derive(Debug, Clone, Copy)]
+pub enum OperationCode {
+    Move,
+    LoadConstant,
+    LoadConstantExtended,
+    LoadBoolean,
+    LoadNil,
+    GetUpvalue,
+    GetTableUpvalue,
+    GetIndex,
+    SetTableUpvalue,
+    SetUpvalue,
+    SetIndex,
+    NewTable,
+    PrepMethodCall,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    Power,
+    FloorDivide,
+    BitwiseAnd,
+    BitwiseOr,
+    BitwiseXor,
+    ShiftLeft,
+    ShiftRight,
+    Minus,
+    BitwiseNot,
+    Not,
+    Length,
+    Concatenate,
+    Jump,
+    Equal,
+    LessThan,
+    LessThanOrEqual,
+    Test,
+    TestSet,
+    Call,
+    TailCall,
+    Return,
+    IterateNumericForLoop,
+    InitNumericForLoop,
+    GenericForCall,
+    IterateGenericForLoop,
+    SetList,
+    Closure,
+    VarArg,
+    ExtraArgument,
+}
+
+// in the order of `lopcodes.h`
+const OPERATION_CODES_52: [OperationCode; 40] = [
+    OperationCode::Move,
+    OperationCode::LoadConstant,
+    OperationCode::LoadConstantExtended,
+    OperationCode::LoadBoolean,
+    OperationCode::LoadNil,
+    OperationCode::GetUpvalue,
+    OperationCode::GetTableUpvalue,
+    OperationCode::GetIndex,
+    OperationCode::SetTableUpvalue,
+    OperationCode::SetUpvalue,
+    OperationCode::SetIndex,
+    OperationCode::NewTable,
+    OperationCode::PrepMethodCall,
+    OperationCode::Add,
+    OperationCode::Subtract,
+    OperationCode::Multiply,
+    OperationCode::Divide,
+    OperationCode::Modulo,
+    OperationCode::Power,
+    OperationCode::Minus,
+    OperationCode::Not,
+    OperationCode::Length,
+    OperationCode::Concatenate,
+    OperationCode::Jump,
+    OperationCode::Equal,
+    OperationCode::LessThan,
+    OperationCode::LessThanOrEqual,
+    OperationCode::Test,
+    OperationCode::TestSet,
+    OperationCode::Call,
+    OperationCode::TailCall,
+    OperationCode::Return,
+    OperationCode::IterateNumericForLoop,
+    OperationCode::InitNumericForLoop,
+    OperationCode::GenericForCall,
+    OperationCode::IterateGenericForLoop,
+    OperationCode::SetList,
+    OperationCode::Closure,
+    OperationCode::VarArg,
+    OperationCode::ExtraArgument,
+];
+
+const OPERATION_CODES_53: [OperationCode; 47] = [
+    OperationCode::Move,
+    OperationCode::LoadConstant,
+    OperationCode::LoadConstantExtended,
+    OperationCode::LoadBoolean,
+    OperationCode::LoadNil,
+    OperationCode::GetUpvalue,
+    OperationCode::GetTableUpvalue,
+    OperationCode::GetIndex,
+    OperationCode::SetTableUpvalue,
+    OperationCode::SetUpvalue,
+    OperationCode::SetIndex,
+    OperationCode::NewTable,
+    OperationCode::PrepMethodCall,
+    OperationCode::Add,
+    OperationCode::Subtract,
+    OperationCode::Multiply,
+    OperationCode::Modulo,
+    OperationCode::Power,
+    OperationCode::Divide,
+    OperationCode::FloorDivide,
+    OperationCode::BitwiseAnd,
+    OperationCode::BitwiseOr,
+    OperationCode::BitwiseXor,
+    OperationCode::ShiftLeft,
+    OperationCode::ShiftRight,
+    OperationCode::Minus,
+    OperationCode::BitwiseNot,
+    OperationCode::Not,
+    OperationCode::Length,
+    OperationCode::Concatenate,
+    OperationCode::Jump,
+    OperationCode::Equal,
+    OperationCode::LessThan,
+    OperationCode::LessThanOrEqual,
+    OperationCode::Test,
+    OperationCode::TestSet,
+    OperationCode::Call,
+    OperationCode::TailCall,
+    OperationCode::Return,
+    OperationCode::IterateNumericForLoop,
+    OperationCode::InitNumericForLoop,
+    OperationCode::GenericForCall,
+    OperationCode::IterateGenericForLoop,
+    OperationCode::SetList,
+    OperationCode::Closure,
+    OperationCode::VarArg,
+    OperationCode::ExtraArgument,
+];
+
+impl OperationCode {
+    pub fn from_instruction(instruction: u32, version: Version) -> Option<Self> {
+        let operation_codes: &[Self] = match version {
+            Version::Lua52 => &OPERATION_CODES_52,
+            Version::Lua53 => &OPERATION_CODES_53,
+        };
+        operation_codes.get((instruction & 0x3F) as usize).copied()
+    }
+
+    pub fn instruction_layout(&self) -> LayoutDiscriminants {
+        match self {
+            Self::LoadConstant | Self::Closure => LayoutDiscriminants::BX,
+            Self::Jump
+            | Self::IterateNumericForLoop
+            | Self::InitNumericForLoop
+            | Self::IterateGenericForLoop => LayoutDiscriminants::BSx,
+            Self::ExtraArgument => LayoutDiscriminants::AX,
+            _ => LayoutDiscriminants::BC,
+        }
+    }
+}
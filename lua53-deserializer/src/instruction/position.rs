@@ -0,0 +1,29 @@
+use nom::{multi::count, IResult};
+
+use crate::chunk::Header;
+
+#[derive(Debug)]
+pub struct Position {
+    pub instruction: usize,
+    pub source: u32,
+}
+
+impl Position {
+    pub fn parse<'a>(input: &'a [u8], header: &Header) -> IResult<&'a [u8], Vec<Self>> {
+        let (input, positions_length) = header.parse_int(input)?;
+        let (input, source_positions) =
+            count(|input| header.parse_int(input), positions_length as usize)(input)?;
+
+        Ok((
+            input,
+            source_positions
+                .iter()
+                .enumerate()
+                .map(|(instruction, &source)| Self {
+                    instruction,
+                    source,
+                })
+                .collect(),
+        ))
+    }
+}
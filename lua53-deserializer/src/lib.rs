@@ -0,0 +1,21 @@
+pub use error::DeserializeError;
+pub use function::Function;
+pub use instruction::{argument, Instruction};
+pub use upvalue::UpvalueDescriptor;
+pub use value::Value;
+
+pub mod chunk;
+pub mod error;
+pub mod function;
+pub mod instruction;
+pub mod local;
+pub mod upvalue;
+pub mod value;
+
+// Lua 5.2 and 5.3, which share an instruction encoding
+pub fn deserialize(bytecode: &[u8]) -> Result<chunk::Chunk<'_>, DeserializeError> {
+    match chunk::Chunk::parse(bytecode) {
+        Ok((_, chunk)) => Ok(chunk),
+        Err(err) => Err(DeserializeError::from_parse_error(bytecode, err)),
+    }
+}
@@ -0,0 +1,32 @@
+use nom::{multi::count, number::complete::le_u8, IResult};
+
+use crate::chunk::Header;
+
+// where a closure captures an upvalue from when it's created
+#[derive(Debug, Clone, Copy)]
+pub struct UpvalueDescriptor {
+    // a register of the enclosing function, otherwise one of its upvalues
+    pub in_stack: bool,
+    pub index: u8,
+}
+
+impl UpvalueDescriptor {
+    pub fn parse_list<'a>(input: &'a [u8], header: &Header) -> IResult<&'a [u8], Vec<Self>> {
+        let (input, length) = header.parse_int(input)?;
+
+        count(Self::parse, length as usize)(input)
+    }
+
+    fn parse(input: &[u8]) -> IResult<&[u8], Self> {
+        let (input, in_stack) = le_u8(input)?;
+        let (input, index) = le_u8(input)?;
+
+        Ok((
+            input,
+            Self {
+                in_stack: in_stack != 0,
+                index,
+            },
+        ))
+    }
+}
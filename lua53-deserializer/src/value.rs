@@ -0,0 +1,90 @@
+use enum_as_inner::EnumAsInner;
+use nom::{
+    bytes::complete::take,
+    error::{Error, ErrorKind, ParseError},
+    multi::count,
+    number::complete::le_u8,
+    Err, IResult,
+};
+
+use crate::chunk::{Header, Version};
+
+#[derive(Debug, EnumAsInner)]
+pub enum Value<'a> {
+    Nil,
+    Boolean(bool),
+    // 5.2 numbers have no subtype
+    Number(f64),
+    Integer(i64),
+    Float(f64),
+    String(&'a [u8]),
+}
+
+impl<'a> Value<'a> {
+    pub fn parse(input: &'a [u8], header: &Header) -> IResult<&'a [u8], Self> {
+        let (input, kind) = le_u8(input)?;
+
+        match (header.version, kind) {
+            (_, 0) => Ok((input, Self::Nil)),
+            (_, 1) => {
+                let (input, value) = le_u8(input)?;
+
+                Ok((input, Self::Boolean(value != 0)))
+            }
+            (Version::Lua52, 3) => {
+                let (input, value) = header.parse_number(input)?;
+
+                Ok((input, Self::Number(value)))
+            }
+            (Version::Lua53, 3) => {
+                let (input, value) = header.parse_number(input)?;
+
+                Ok((input, Self::Float(value)))
+            }
+            (Version::Lua53, 19) => {
+                let (input, value) = header.parse_integer(input)?;
+
+                Ok((input, Self::Integer(value)))
+            }
+            // 5.3 distinguishes short (4) and long (20) strings
+            (_, 4) | (Version::Lua53, 20) => {
+                let (input, value) = parse_string(input, header)?;
+
+                Ok((input, Self::String(value)))
+            }
+            _ => Err(Err::Failure(Error::from_error_kind(
+                input,
+                ErrorKind::Switch,
+            ))),
+        }
+    }
+}
+
+// a null string is read as empty, the null terminator is excluded
+pub fn parse_string<'a>(input: &'a [u8], header: &Header) -> IResult<&'a [u8], &'a [u8]> {
+    match header.version {
+        Version::Lua52 => {
+            let (input, string_length) = header.parse_size_t(input)?;
+            let (input, string) = take(string_length)(input)?;
+
+            Ok((input, string.strip_suffix(b"\0").unwrap_or(string)))
+        }
+        // 5.3 doesn't store the null terminator, but counts it in the length
+        Version::Lua53 => {
+            let (input, string_length) = match le_u8(input)? {
+                (input, 0xFF) => header.parse_size_t(input)?,
+                (input, string_length) => (input, string_length as usize),
+            };
+
+            take(string_length.saturating_sub(1))(input)
+        }
+    }
+}
+
+pub fn parse_strings<'a>(input: &'a [u8], header: &Header) -> IResult<&'a [u8], Vec<&'a [u8]>> {
+    let (input, string_count) = header.parse_int(input)?;
+    let (input, strings) =
+        count(|input| parse_string(input, header), string_count as usize)(input)?;
+
+    Ok((input, strings))
+}
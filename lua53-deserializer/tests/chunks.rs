@@ -0,0 +1,289 @@
+use lua53_deserializer::{
+    chunk::Version, deserialize, error::DeserializeError, Instruction, Value,
+};
+use nom::error::ErrorKind;
+
+const LUAC_DATA: &[u8] = b"\x19\x93\r\n\x1a\n";
+
+// writes chunks with 4-byte ints and instructions and 8-byte size_t, integers and numbers
+struct Writer {
+    version: Version,
+    big_endian: bool,
+}
+
+enum Constant<'a> {
+    Integer(i64),
+    Number(f64),
+    String(&'a [u8]),
+}
+
+struct Prototype<'a> {
+    source: Option<&'a [u8]>,
+    code: Vec<u32>,
+    constants: Vec<Constant<'a>>,
+    upvalues: Vec<(u8, u8)>,
+    closures: Vec<Vec<u8>>,
+}
+
+impl Writer {
+    fn new(version: Version) -> Self {
+        Self {
+            version,
+            big_endian: false,
+        }
+    }
+
+    fn bytes<const N: usize>(&self, little: [u8; N], big: [u8; N]) -> Vec<u8> {
+        if self.big_endian {
+            big.to_vec()
+        } else {
+            little.to_vec()
+        }
+    }
+
+    fn int(&self, value: u32) -> Vec<u8> {
+        self.bytes(value.to_le_bytes(), value.to_be_bytes())
+    }
+
+    fn size_t(&self, value: u64) -> Vec<u8> {
+        self.bytes(value.to_le_bytes(), value.to_be_bytes())
+    }
+
+    fn integer(&self, value: i64) -> Vec<u8> {
+        self.bytes(value.to_le_bytes(), value.to_be_bytes())
+    }
+
+    fn number(&self, value: f64) -> Vec<u8> {
+        self.bytes(value.to_le_bytes(), value.to_be_bytes())
+    }
+
+    fn header(&self) -> Vec<u8> {
+        let mut out = b"\x1BLua".to_vec();
+        match self.version {
+            Version::Lua52 => {
+                out.extend([0x52, 0, !self.big_endian as u8, 4, 8, 4, 8, 0]);
+                out.extend(LUAC_DATA);
+            }
+            Version::Lua53 => {
+                out.extend([0x53, 0]);
+                out.extend(LUAC_DATA);
+                out.extend([4, 8, 4, 8, 8]);
+                out.extend(self.integer(0x5678));
+                out.extend(self.number(370.5));
+            }
+        }
+        out
+    }
+
+    fn string(&self, string: Option<&[u8]>) -> Vec<u8> {
+        let mut out = Vec::new();
+        match (self.version, string) {
+            (Version::Lua52, None) => out.extend(self.size_t(0)),
+            (Version::Lua52, Some(string)) => {
+                out.extend(self.size_t(string.len() as u64 + 1));
+                out.extend(string);
+                out.push(0);
+            }
+            (Version::Lua53, None) => out.push(0),
+            (Version::Lua53, Some(string)) => {
+                let length = string.len() + 1;
+                if length < 0xFF {
+                    out.push(length as u8);
+                } else {
+                    out.push(0xFF);
+                    out.extend(self.size_t(length as u64));
+                }
+                out.extend(string);
+            }
+        }
+        out
+    }
+
+    fn function(&self, prototype: &Prototype) -> Vec<u8> {
+        let mut out = Vec::new();
+        if self.version == Version::Lua53 {
+            out.extend(self.string(prototype.source));
+        }
+        out.extend(self.int(0));
+        out.extend(self.int(0));
+        out.extend([0, 1, 8]);
+        out.extend(self.int(prototype.code.len() as u32));
+        for &instruction in &prototype.code {
+            out.extend(self.int(instruction));
+        }
+        out.extend(self.int(prototype.constants.len() as u32));
+        for constant in &prototype.constants {
+            match (self.version, constant) {
+                (Version::Lua52, &Constant::Integer(value)) => {
+                    out.push(3);
+                    out.extend(self.number(value as f64));
+                }
+                (Version::Lua53, &Constant::Integer(value)) => {
+                    out.push(19);
+                    out.extend(self.integer(value));
+                }
+                (_, &Constant::Number(value)) => {
+                    out.push(3);
+                    out.extend(self.number(value));
+                }
+                (_, &Constant::String(string)) => {
+                    out.push(4);
+                    out.extend(self.string(Some(string)));
+                }
+            }
+        }
+        let mut upvalues = self.int(prototype.upvalues.len() as u32);
+        for &(in_stack, index) in &prototype.upvalues {
+            upvalues.extend([in_stack, index]);
+        }
+        let mut closures = self.int(prototype.closures.len() as u32);
+        for closure in &prototype.closures {
+            closures.extend(closure);
+        }
+        match self.version {
+            Version::Lua52 => {
+                out.extend(closures);
+                out.extend(upvalues);
+                out.extend(self.string(prototype.source));
+            }
+            Version::Lua53 => {
+                out.extend(upvalues);
+                out.extend(closures);
+            }
+        }
+        // no line info, locals or upvalue names
+        out.extend(self.int(0));
+        out.extend(self.int(0));
+        out.extend(self.int(0));
+        out
+    }
+
+    // `RETURN 0 1`
+    fn r#return(&self) -> u32 {
+        let op_code = match self.version {
+            Version::Lua52 => 31,
+            Version::Lua53 => 38,
+        };
+        op_code | 1 << 23
+    }
+
+    // a main function with `_ENV` that returns, and a child function
+    fn chunk(&self, constants: Vec<Constant>) -> Vec<u8> {
+        let child = self.function(&Prototype {
+            source: None,
+            code: vec![self.r#return()],
+            constants: Vec::new(),
+            upvalues: vec![(0, 0)],
+            closures: Vec::new(),
+        });
+        let main = self.function(&Prototype {
+            source: Some(b"@test.lua"),
+            code: vec![self.r#return()],
+            constants,
+            upvalues: vec![(1, 0)],
+            closures: vec![child],
+        });
+        let mut out = self.header();
+        if self.version == Version::Lua53 {
+            out.push(1);
+        }
+        out.extend(main);
+        out
+    }
+}
+
+#[test]
+fn parses_52_chunks() {
+    let writer = Writer::new(Version::Lua52);
+    let bytecode = writer.chunk(vec![Constant::Integer(1), Constant::String(b"x")]);
+    let chunk = deserialize(&bytecode).unwrap();
+    assert_eq!(chunk.version, Version::Lua52);
+    let main = chunk.function;
+    assert_eq!(main.name, b"@test.lua");
+    assert!(matches!(main.code[..], [Instruction::Return(..)]));
+    // 5.2 numbers have no subtype
+    assert!(matches!(
+        main.constants[..],
+        [Value::Number(value), Value::String(b"x")] if value == 1.0
+    ));
+    assert_eq!(main.upvalues.len(), 1);
+    assert!(main.upvalues[0].in_stack);
+    // closures come before upvalues in 5.2
+    assert_eq!(main.closures.len(), 1);
+    assert!(!main.closures[0].upvalues[0].in_stack);
+}
+
+#[test]
+fn parses_53_chunks() {
+    for big_endian in [false, true] {
+        let writer = Writer {
+            version: Version::Lua53,
+            big_endian,
+        };
+        let bytecode = writer.chunk(vec![
+            Constant::Integer(-2),
+            Constant::Number(0.5),
+            Constant::String(b"x"),
+        ]);
+        let chunk = deserialize(&bytecode).unwrap();
+        assert_eq!(chunk.version, Version::Lua53);
+        let main = chunk.function;
+        assert_eq!(main.name, b"@test.lua");
+        assert!(matches!(main.code[..], [Instruction::Return(..)]));
+        assert!(matches!(
+            main.constants[..],
+            [Value::Integer(-2), Value::Float(value), Value::String(b"x")] if value == 0.5
+        ));
+        // upvalues come before closures in 5.3
+        assert!(main.upvalues[0].in_stack);
+        assert_eq!(main.closures.len(), 1);
+        assert!(!main.closures[0].upvalues[0].in_stack);
+        // the source of a child is omitted when it's the same as its parent's
+        assert_eq!(main.closures[0].name, b"@test.lua");
+    }
+}
+
+#[test]
+fn parses_long_53_strings() {
+    let writer = Writer::new(Version::Lua53);
+    let string = vec![b'x'; 300];
+    let bytecode = writer.chunk(vec![Constant::String(&string)]);
+    let chunk = deserialize(&bytecode).unwrap();
+    assert!(matches!(
+        chunk.function.constants[..],
+        [Value::String(value)] if value == &string[..]
+    ));
+}
+
+#[test]
+fn rejects_unknown_byte_orders() {
+    // LUAC_INT reads as neither byte order
+    let writer = Writer::new(Version::Lua53);
+    let mut bytecode = writer.chunk(Vec::new());
+    let offset = 4 + 2 + LUAC_DATA.len() + 5;
+    bytecode[offset..offset + 8].copy_from_slice(&0x1234i64.to_le_bytes());
+    assert_eq!(
+        deserialize(&bytecode).unwrap_err(),
+        DeserializeError::Malformed {
+            offset: offset + 8,
+            kind: ErrorKind::Verify,
+        }
+    );
+}
+
+#[test]
+fn rejects_ints_that_dont_fit() {
+    // `int` is 8 bytes wide, but the values are read into 32 bits
+    let writer = Writer::new(Version::Lua52);
+    let mut bytecode = writer.header();
+    bytecode[7] = 8;
+    let offset = bytecode.len();
+    bytecode.extend(writer.size_t(1 << 32));
+    assert_eq!(
+        deserialize(&bytecode).unwrap_err(),
+        DeserializeError::Malformed {
+            offset,
+            kind: ErrorKind::TooLarge,
+        }
+    );
+}
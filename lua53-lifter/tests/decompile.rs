@@ -0,0 +1,266 @@
+use lua53_lifter::decompile_bytecode;
+
+const LUAC_DATA: &[u8] = b"\x19\x93\r\n\x1a\n";
+
+// `RK` operands with this bit set index the constants
+const K: u32 = 256;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Version {
+    Lua52,
+    Lua53,
+}
+
+enum Constant<'a> {
+    Integer(i64),
+    Float(f64),
+    String(&'a [u8]),
+}
+
+// writes little-endian chunks with 4-byte ints and 8-byte size_t, integers and numbers
+struct Writer {
+    version: Version,
+}
+
+impl Writer {
+    fn op_code(&self, name: &str) -> u32 {
+        let op_codes: &[&str] = match self.version {
+            Version::Lua52 => &[
+                "MOVE", "LOADK", "LOADKX", "LOADBOOL", "LOADNIL", "GETUPVAL", "GETTABUP",
+                "GETTABLE", "SETTABUP", "SETUPVAL", "SETTABLE", "NEWTABLE", "SELF", "ADD", "SUB",
+                "MUL", "DIV", "MOD", "POW", "UNM", "NOT", "LEN", "CONCAT", "JMP", "EQ", "LT", "LE",
+                "TEST", "TESTSET", "CALL", "TAILCALL", "RETURN", "FORLOOP", "FORPREP", "TFORCALL",
+                "TFORLOOP", "SETLIST", "CLOSURE", "VARARG", "EXTRAARG",
+            ],
+            Version::Lua53 => &[
+                "MOVE", "LOADK", "LOADKX", "LOADBOOL", "LOADNIL", "GETUPVAL", "GETTABUP",
+                "GETTABLE", "SETTABUP", "SETUPVAL", "SETTABLE", "NEWTABLE", "SELF", "ADD", "SUB",
+                "MUL", "MOD", "POW", "DIV", "IDIV", "BAND", "BOR", "BXOR", "SHL", "SHR", "UNM",
+                "BNOT", "NOT", "LEN", "CONCAT", "JMP", "EQ", "LT", "LE", "TEST", "TESTSET", "CALL",
+                "TAILCALL", "RETURN", "FORLOOP", "FORPREP", "TFORCALL", "TFORLOOP", "SETLIST",
+                "CLOSURE", "VARARG", "EXTRAARG",
+            ],
+        };
+        op_codes
+            .iter()
+            .position(|&op_code| op_code == name)
+            .unwrap() as u32
+    }
+
+    fn abc(&self, op_code: &str, a: u32, b: u32, c: u32) -> u32 {
+        self.op_code(op_code) | a << 6 | c << 14 | b << 23
+    }
+
+    fn abx(&self, op_code: &str, a: u32, bx: u32) -> u32 {
+        self.op_code(op_code) | a << 6 | bx << 14
+    }
+
+    fn int(&self, value: u32) -> Vec<u8> {
+        value.to_le_bytes().to_vec()
+    }
+
+    fn string(&self, string: Option<&[u8]>) -> Vec<u8> {
+        let mut out = Vec::new();
+        match (self.version, string) {
+            (Version::Lua52, None) => out.extend(0u64.to_le_bytes()),
+            (Version::Lua52, Some(string)) => {
+                out.extend((string.len() as u64 + 1).to_le_bytes());
+                out.extend(string);
+                out.push(0);
+            }
+            (Version::Lua53, None) => out.push(0),
+            (Version::Lua53, Some(string)) => {
+                out.push(string.len() as u8 + 1);
+                out.extend(string);
+            }
+        }
+        out
+    }
+
+    fn header(&self) -> Vec<u8> {
+        let mut out = b"\x1BLua".to_vec();
+        match self.version {
+            Version::Lua52 => {
+                out.extend([0x52, 0, 1, 4, 8, 4, 8, 0]);
+                out.extend(LUAC_DATA);
+            }
+            Version::Lua53 => {
+                out.extend([0x53, 0]);
+                out.extend(LUAC_DATA);
+                out.extend([4, 8, 4, 8, 8]);
+                out.extend(0x5678i64.to_le_bytes());
+                out.extend(370.5f64.to_le_bytes());
+                // the number of upvalues of the main function
+                out.push(1);
+            }
+        }
+        out
+    }
+
+    fn function(
+        &self,
+        code: &[u32],
+        constants: &[Constant],
+        closures: &[Vec<u8>],
+        upvalues: &[(u8, u8)],
+        locals: &[(&[u8], u32, u32)],
+    ) -> Vec<u8> {
+        let source = b"@test.lua".as_slice();
+        let mut out = Vec::new();
+        if self.version == Version::Lua53 {
+            out.extend(self.string(Some(source)));
+        }
+        out.extend(self.int(0));
+        out.extend(self.int(0));
+        out.extend([0, 1, 12]);
+        out.extend(self.int(code.len() as u32));
+        for &instruction in code {
+            out.extend(self.int(instruction));
+        }
+        out.extend(self.int(constants.len() as u32));
+        for constant in constants {
+            match (self.version, constant) {
+                (Version::Lua52, &Constant::Integer(value)) => {
+                    out.push(3);
+                    out.extend((value as f64).to_le_bytes());
+                }
+                (Version::Lua53, &Constant::Integer(value)) => {
+                    out.push(19);
+                    out.extend(value.to_le_bytes());
+                }
+                (_, &Constant::Float(value)) => {
+                    out.push(3);
+                    out.extend(value.to_le_bytes());
+                }
+                (_, &Constant::String(string)) => {
+                    out.push(4);
+                    out.extend(self.string(Some(string)));
+                }
+            }
+        }
+        let mut upvalue_descriptors = self.int(upvalues.len() as u32);
+        for &(in_stack, index) in upvalues {
+            upvalue_descriptors.extend([in_stack, index]);
+        }
+        let mut closure_list = self.int(closures.len() as u32);
+        for closure in closures {
+            closure_list.extend(closure);
+        }
+        match self.version {
+            Version::Lua52 => {
+                out.extend(closure_list);
+                out.extend(upvalue_descriptors);
+                out.extend(self.string(Some(source)));
+            }
+            Version::Lua53 => {
+                out.extend(upvalue_descriptors);
+                out.extend(closure_list);
+            }
+        }
+        // no line info or upvalue names
+        out.extend(self.int(0));
+        out.extend(self.int(locals.len() as u32));
+        for &(name, start, end) in locals {
+            out.extend(self.string(Some(name)));
+            out.extend(self.int(start));
+            out.extend(self.int(end));
+        }
+        out.extend(self.int(0));
+        out
+    }
+}
+
+#[test]
+fn lifts_integers_and_floats() {
+    let writer = Writer {
+        version: Version::Lua53,
+    };
+    let code = [
+        writer.abx("LOADK", 0, 0),
+        writer.abx("LOADK", 1, 1),
+        writer.abc("SETTABUP", 0, K + 2, 0),
+        writer.abc("SETTABUP", 0, K + 3, 1),
+        writer.abc("RETURN", 0, 1, 0),
+    ];
+    let constants = [
+        Constant::Integer(2),
+        Constant::Float(2.0),
+        Constant::String(b"i"),
+        Constant::String(b"f"),
+    ];
+    let mut bytecode = writer.header();
+    bytecode.extend(writer.function(&code, &constants, &[], &[(1, 0)], &[]));
+    assert_eq!(decompile_bytecode(&bytecode).unwrap(), "i = 2\nf = 2.0");
+}
+
+#[test]
+fn lifts_bitwise_operators() {
+    let writer = Writer {
+        version: Version::Lua53,
+    };
+    let code = [
+        writer.abx("LOADK", 0, 0),
+        writer.abx("LOADK", 1, 1),
+        writer.abc("GETTABUP", 2, 0, K + 2),
+        writer.abc("BAND", 3, 0, K + 3),
+        writer.abc("BOR", 4, 0, 1),
+        writer.abc("BXOR", 5, 0, K + 4),
+        writer.abc("SHL", 6, 0, K + 3),
+        writer.abc("SHR", 7, 0, K),
+        writer.abc("BNOT", 8, 0, 0),
+        writer.abc("IDIV", 9, 0, K + 1),
+        writer.abc("CALL", 2, 8, 1),
+        writer.abc("SETTABUP", 0, K + 5, K + 1),
+        writer.abc("RETURN", 0, 1, 0),
+    ];
+    let constants = [
+        Constant::Integer(1),
+        Constant::Float(2.0),
+        Constant::String(b"print"),
+        Constant::Integer(3),
+        Constant::Integer(5),
+        Constant::String(b"g"),
+    ];
+    let locals: [(&[u8], u32, u32); 2] = [(b"x", 1, 13), (b"y", 2, 13)];
+    let mut bytecode = writer.header();
+    bytecode.extend(writer.function(&code, &constants, &[], &[(1, 0)], &locals));
+    assert_eq!(
+        decompile_bytecode(&bytecode).unwrap(),
+        "local x = 1\nprint(x & 3, x | 2.0, x ~ 5, x << 3, x >> 1, ~x, x // 2.0)\ng = 2.0"
+    );
+}
+
+#[test]
+fn lifts_52_closures() {
+    let writer = Writer {
+        version: Version::Lua52,
+    };
+    let closure = writer.function(
+        &[
+            writer.abc("GETUPVAL", 0, 0, 0),
+            writer.abc("ADD", 0, 0, K),
+            writer.abc("SETUPVAL", 0, 0, 0),
+            writer.abc("GETTABUP", 0, 1, K + 1),
+            writer.abc("GETUPVAL", 1, 0, 0),
+            writer.abc("CALL", 0, 2, 1),
+            writer.abc("RETURN", 0, 1, 0),
+        ],
+        &[Constant::Integer(1), Constant::String(b"print")],
+        &[],
+        &[(1, 0), (0, 0)],
+        &[],
+    );
+    let code = [
+        writer.abx("LOADK", 0, 0),
+        writer.abx("CLOSURE", 1, 0),
+        writer.abc("MOVE", 2, 1, 0),
+        writer.abc("CALL", 2, 1, 1),
+        writer.abc("RETURN", 0, 1, 0),
+    ];
+    let mut bytecode = writer.header();
+    bytecode.extend(writer.function(&code, &[Constant::Integer(0)], &[closure], &[(1, 0)], &[]));
+    assert_eq!(
+        decompile_bytecode(&bytecode).unwrap(),
+        "local v_u_1 = 0\n(function()\n\t-- upvalues: (ref) v_u_1\n\tv_u_1 = v_u_1 + 1\n\tprint(v_u_1)\nend)()"
+    );
+}
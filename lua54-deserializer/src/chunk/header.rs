@@ -0,0 +1,143 @@
+use nom::{
+    bytes::complete::{tag, take},
+    error::{Error, ErrorKind, ParseError},
+    number::{
+        self,
+        complete::{f32, f64, i32, i64, le_u8, u32},
+    },
+    Err, IResult,
+};
+
+const LUAC_DATA: &[u8] = b"\x19\x93\r\n\x1a\n";
+const LUAC_INT: i64 = 0x5678;
+const LUAC_NUM: f64 = 370.5;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Endianness {
+    Big,
+    Little,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Format {
+    Official,
+}
+
+#[derive(Debug)]
+pub struct Header {
+    pub(crate) format: Format,
+    pub(crate) endianness: Endianness,
+    pub(crate) instr_width: u8,
+    pub(crate) integer_width: u8,
+    pub(crate) number_width: u8,
+}
+
+impl Header {
+    pub fn parse(input: &[u8]) -> IResult<&[u8], Self> {
+        let (input, _) = tag("\x1BLua")(input)?;
+        let (input, _) = tag([0x54])(input)?;
+        let (input, format) = match le_u8(input)? {
+            (input, 0) => Ok((input, Format::Official)),
+            _ => Err(Err::Failure(Error::from_error_kind(
+                input,
+                ErrorKind::Switch,
+            ))),
+        }?;
+        let (input, _) = tag(LUAC_DATA)(input)?;
+        // `int` and `size_t` are stored as variable length integers since 5.4
+        let (input, instr_width) = le_u8(input)?;
+        let (input, integer_width) = le_u8(input)?;
+        let (input, number_width) = le_u8(input)?;
+        // the byte order isn't stored, it's inferred from a known integer
+        let (_, check) = take(integer_width)(input)?;
+        let endianness = if check.first() == Some(&0x78) {
+            Endianness::Little
+        } else {
+            Endianness::Big
+        };
+        let header = Self {
+            format,
+            endianness,
+            instr_width,
+            integer_width,
+            number_width,
+        };
+        let (input, integer) = header.parse_integer(input)?;
+        if integer != LUAC_INT {
+            return Err(Err::Failure(Error::from_error_kind(
+                input,
+                ErrorKind::Verify,
+            )));
+        }
+        let (input, number) = header.parse_number(input)?;
+        if number != LUAC_NUM {
+            return Err(Err::Failure(Error::from_error_kind(
+                input,
+                ErrorKind::Verify,
+            )));
+        }
+
+        Ok((input, header))
+    }
+}
+
+// readers for the types whose width and byte order are given by the header
+impl Header {
+    fn nom_endianness(&self) -> number::Endianness {
+        match self.endianness {
+            Endianness::Big => number::Endianness::Big,
+            Endianness::Little => number::Endianness::Little,
+        }
+    }
+
+    // most significant group first, the last byte has its high bit set
+    pub fn parse_size<'a>(&self, mut input: &'a [u8]) -> IResult<&'a [u8], usize> {
+        let mut value: usize = 0;
+        loop {
+            let (rest, byte) = le_u8(input)?;
+            input = rest;
+            value = value
+                .checked_mul(1 << 7)
+                .ok_or_else(|| Err::Failure(Error::from_error_kind(input, ErrorKind::TooLarge)))?
+                | (byte & 0x7F) as usize;
+            if byte & 0x80 != 0 {
+                return Ok((input, value));
+            }
+        }
+    }
+
+    pub fn parse_int<'a>(&self, input: &'a [u8]) -> IResult<&'a [u8], u32> {
+        let (input, value) = self.parse_size(input)?;
+        let value = u32::try_from(value)
+            .map_err(|_| Err::Failure(Error::from_error_kind(input, ErrorKind::TooLarge)))?;
+        Ok((input, value))
+    }
+
+    pub fn parse_instruction<'a>(&self, input: &'a [u8]) -> IResult<&'a [u8], u32> {
+        u32(self.nom_endianness())(input)
+    }
+
+    pub fn parse_integer<'a>(&self, input: &'a [u8]) -> IResult<&'a [u8], i64> {
+        let endianness = self.nom_endianness();
+        match self.integer_width {
+            4 => i32(endianness)(input).map(|(input, value)| (input, value.into())),
+            8 => i64(endianness)(input),
+            _ => Err(Err::Failure(Error::from_error_kind(
+                input,
+                ErrorKind::Switch,
+            ))),
+        }
+    }
+
+    pub fn parse_number<'a>(&self, input: &'a [u8]) -> IResult<&'a [u8], f64> {
+        let endianness = self.nom_endianness();
+        match self.number_width {
+            4 => f32(endianness)(input).map(|(input, value)| (input, value.into())),
+            8 => f64(endianness)(input),
+            _ => Err(Err::Failure(Error::from_error_kind(
+                input,
+                ErrorKind::Switch,
+            ))),
+        }
+    }
+}
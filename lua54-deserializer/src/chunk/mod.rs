@@ -0,0 +1,33 @@
+use nom::{
+    error::{Error, ErrorKind, ParseError},
+    number::complete::le_u8,
+    Err, IResult,
+};
+
+pub use header::Header;
+
+use crate::{chunk::header::Format, function::Function};
+
+pub mod header;
+
+#[derive(Debug)]
+pub struct Chunk<'a> {
+    pub function: Function<'a>,
+}
+
+impl<'a> Chunk<'a> {
+    pub fn parse(input: &'a [u8]) -> IResult<&'a [u8], Self> {
+        let (input, header) = Header::parse(input)?;
+        if header.format != Format::Official || header.instr_width != 4 {
+            return Err(Err::Failure(Error::from_error_kind(
+                input,
+                ErrorKind::Verify,
+            )));
+        }
+        // the number of upvalues of the main function
+        let (input, _) = le_u8(input)?;
+        let (input, function) = Function::parse(input, &header, b"")?;
+
+        Ok((input, Self { function }))
+    }
+}
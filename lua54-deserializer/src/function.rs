@@ -0,0 +1,86 @@
+use nom::{multi::count, number::complete::le_u8, IResult};
+
+use crate::{
+    chunk::Header,
+    instruction::{position::Position, Instruction},
+    local::Local,
+    upvalue::UpvalueDescriptor,
+    value::{self, Value},
+};
+
+#[derive(Debug)]
+pub struct Function<'a> {
+    pub name: &'a [u8],
+    pub line_defined: u32,
+    pub last_line_defined: u32,
+    pub vararg_flag: u8,
+    pub maximum_stack_size: u8,
+    pub code: Vec<Instruction>,
+    pub constants: Vec<Value<'a>>,
+    pub upvalues: Vec<UpvalueDescriptor>,
+    pub closures: Vec<Function<'a>>,
+    pub positions: Vec<Position>,
+    pub locals: Vec<Local<'a>>,
+    pub upvalue_names: Vec<&'a [u8]>,
+    pub number_of_parameters: u8,
+}
+
+impl<'a> Function<'a> {
+    pub fn parse(
+        input: &'a [u8],
+        header: &Header,
+        parent_name: &'a [u8],
+    ) -> IResult<&'a [u8], Self> {
+        // the source is omitted if it's the parent's
+        let (input, name) = value::parse_string(input, header)?;
+        let name = if name.is_empty() { parent_name } else { name };
+        let (input, line_defined) = header.parse_int(input)?;
+        let (input, last_line_defined) = header.parse_int(input)?;
+        let (input, number_of_parameters) = le_u8(input)?;
+        let (input, vararg_flag) = le_u8(input)?;
+        let (input, maximum_stack_size) = le_u8(input)?;
+        let (input, code) = Instruction::parse_list(input, header)?;
+        let (input, constants_length) = header.parse_int(input)?;
+        let (input, constants) = count(
+            |input| Value::parse(input, header),
+            constants_length as usize,
+        )(input)?;
+        let (input, upvalues) = UpvalueDescriptor::parse_list(input, header)?;
+        let (input, closures) = Self::parse_list(input, header, name)?;
+        let (input, positions) = Position::parse(input, header, line_defined)?;
+        let (input, locals) = Local::parse_list(input, header)?;
+        let (input, upvalue_names) = value::parse_strings(input, header)?;
+
+        Ok((
+            input,
+            Self {
+                name,
+                line_defined,
+                last_line_defined,
+                vararg_flag,
+                maximum_stack_size,
+                code,
+                constants,
+                upvalues,
+                closures,
+                positions,
+                locals,
+                upvalue_names,
+                number_of_parameters,
+            },
+        ))
+    }
+
+    fn parse_list(
+        input: &'a [u8],
+        header: &Header,
+        parent_name: &'a [u8],
+    ) -> IResult<&'a [u8], Vec<Self>> {
+        let (input, closures_length) = header.parse_int(input)?;
+
+        count(
+            |input| Self::parse(input, header, parent_name),
+            closures_length as usize,
+        )(input)
+    }
+}
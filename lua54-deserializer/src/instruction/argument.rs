@@ -0,0 +1,50 @@
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Register(pub u8);
+
+impl From<u8> for Register {
+    fn from(value: u8) -> Self {
+        Self(value)
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Constant(pub u32);
+
+// 5.4 has variants of most instructions that take a constant or an immediate
+// number in place of a register
+#[derive(Debug, Copy, Clone)]
+pub enum Operand {
+    Register(Register),
+    Constant(Constant),
+    Integer(i64),
+    Float(f64),
+}
+
+impl From<Register> for Operand {
+    fn from(value: Register) -> Self {
+        Self::Register(value)
+    }
+}
+
+impl From<Constant> for Operand {
+    fn from(value: Constant) -> Self {
+        Self::Constant(value)
+    }
+}
+
+impl Operand {
+    // the `k` bit selects between a register and a constant
+    pub(crate) fn register_or_constant(value: u8, k: bool) -> Self {
+        if k {
+            Self::Constant(Constant(value.into()))
+        } else {
+            Self::Register(Register(value))
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Upvalue(pub u8);
+
+#[derive(Debug, Clone)]
+pub struct Function(pub u32);
@@ -0,0 +1,52 @@
+use strum_macros::EnumDiscriminants;
+
+use super::OperationCode;
+
+#[derive(Debug, EnumDiscriminants)]
+pub enum Layout {
+    BC { a: u8, b: u8, c: u8, k: bool },
+    // b extended
+    BX { a: u8, b_x: u32 },
+    // b signed, extended
+    BSx { a: u8, b_sx: i32 },
+    // a extended, only used by EXTRAARG
+    AX { a_x: u32 },
+    // a signed jump, only used by JMP
+    SJ { s_j: i32 },
+}
+
+impl Layout {
+    pub fn parse(instruction: u32, operation_code: &OperationCode) -> Self {
+        let a = ((instruction >> 7) & 0xFF) as u8;
+        match operation_code.instruction_layout() {
+            LayoutDiscriminants::BC => {
+                let k = (instruction >> 15) & 1 == 1;
+                let b = ((instruction >> 16) & 0xFF) as u8;
+                let c = ((instruction >> 24) & 0xFF) as u8;
+
+                Self::BC { a, b, c, k }
+            }
+            LayoutDiscriminants::BX => {
+                let b_x = instruction >> 15;
+
+                Self::BX { a, b_x }
+            }
+            LayoutDiscriminants::BSx => {
+                let b_x = instruction >> 15;
+                // subtract maximum 17 bit signed int
+                let b_sx = b_x as i32 - (((1 << 17) - 1) >> 1);
+
+                Self::BSx { a, b_sx }
+            }
+            LayoutDiscriminants::AX => Self::AX {
+                a_x: instruction >> 7,
+            },
+            LayoutDiscriminants::SJ => {
+                // subtract maximum 25 bit signed int
+                let s_j = (instruction >> 7) as i32 - (((1 << 25) - 1) >> 1);
+
+                Self::SJ { s_j }
+            }
+        }
+    }
+}
@@ -0,0 +1,765 @@
+use nom::{
+    error::{Error, ErrorKind, ParseError},
+    multi::count,
+    Err, IResult,
+};
+
+use crate::chunk::Header;
+
+use argument::{Constant, Function, Operand, Register, Upvalue};
+use layout::Layout;
+use operation_code::OperationCode;
+
+pub mod argument;
+mod layout;
+mod operation_code;
+pub mod position;
+
+// the metamethod events of `ltm.h` that change how an arithmetic instruction is read
+const TM_SUB: u8 = 7;
+const TM_SHL: u8 = 16;
+
+// subtracted from an unsigned `sB` or `sC` argument
+const OFFSET_SC: i64 = 127;
+
+#[derive(Debug)]
+struct RawInstruction(OperationCode, Layout);
+
+impl RawInstruction {
+    pub fn parse<'a>(input: &'a [u8], header: &Header) -> IResult<&'a [u8], Self> {
+        let (input, instruction) = header.parse_instruction(input)?;
+        let operation_code = OperationCode::from_instruction(instruction)
+            .ok_or_else(|| Err::Failure(Error::from_error_kind(input, ErrorKind::Switch)))?;
+        let layout = Layout::parse(instruction, &operation_code);
+
+        Ok((input, Self(operation_code, layout)))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Instruction {
+    Move {
+        destination: Register,
+        source: Register,
+    },
+    LoadInteger {
+        destination: Register,
+        value: i64,
+    },
+    LoadFloat {
+        destination: Register,
+        value: f64,
+    },
+    LoadConstant {
+        destination: Register,
+        source: Constant,
+    },
+    LoadBoolean {
+        destination: Register,
+        value: bool,
+        skip_next: bool,
+    },
+    LoadNil(Vec<Register>),
+    GetUpvalue {
+        destination: Register,
+        upvalue: Upvalue,
+    },
+    SetUpvalue {
+        destination: Upvalue,
+        source: Register,
+    },
+    // ex. `_ENV.print` for a global
+    GetTableUpvalue {
+        destination: Register,
+        upvalue: Upvalue,
+        key: Operand,
+    },
+    SetTableUpvalue {
+        upvalue: Upvalue,
+        key: Operand,
+        value: Operand,
+    },
+    GetIndex {
+        destination: Register,
+        object: Register,
+        key: Operand,
+    },
+    SetIndex {
+        object: Register,
+        key: Operand,
+        value: Operand,
+    },
+    NewTable {
+        destination: Register,
+        array_size: u32,
+        hash_size: u32,
+    },
+    PrepMethodCall {
+        destination: Register,
+        self_arg: Register,
+        object: Register,
+        method: Operand,
+    },
+    Add {
+        destination: Register,
+        lhs: Operand,
+        rhs: Operand,
+    },
+    Sub {
+        destination: Register,
+        lhs: Operand,
+        rhs: Operand,
+    },
+    Mul {
+        destination: Register,
+        lhs: Operand,
+        rhs: Operand,
+    },
+    Div {
+        destination: Register,
+        lhs: Operand,
+        rhs: Operand,
+    },
+    Mod {
+        destination: Register,
+        lhs: Operand,
+        rhs: Operand,
+    },
+    Pow {
+        destination: Register,
+        lhs: Operand,
+        rhs: Operand,
+    },
+    IDiv {
+        destination: Register,
+        lhs: Operand,
+        rhs: Operand,
+    },
+    BitwiseAnd {
+        destination: Register,
+        lhs: Operand,
+        rhs: Operand,
+    },
+    BitwiseOr {
+        destination: Register,
+        lhs: Operand,
+        rhs: Operand,
+    },
+    BitwiseXor {
+        destination: Register,
+        lhs: Operand,
+        rhs: Operand,
+    },
+    ShiftLeft {
+        destination: Register,
+        lhs: Operand,
+        rhs: Operand,
+    },
+    ShiftRight {
+        destination: Register,
+        lhs: Operand,
+        rhs: Operand,
+    },
+    // the metamethod fallback of the previous instruction, already folded into it
+    MetaMethod,
+    Minus {
+        destination: Register,
+        operand: Register,
+    },
+    BitwiseNot {
+        destination: Register,
+        operand: Register,
+    },
+    Not {
+        destination: Register,
+        operand: Register,
+    },
+    Length {
+        destination: Register,
+        operand: Register,
+    },
+    Concatenate {
+        destination: Register,
+        operands: Vec<Register>,
+    },
+    // upvalues and to-be-closed variables from this register up are closed
+    Close(Register),
+    // marks a register as a to-be-closed variable, ex. `local x <close> = y`
+    ToBeClosed(Register),
+    Jump {
+        skip: i32,
+    },
+    Equal {
+        lhs: Operand,
+        rhs: Operand,
+        invert: bool,
+    },
+    LessThan {
+        lhs: Operand,
+        rhs: Operand,
+        invert: bool,
+    },
+    LessThanOrEqual {
+        lhs: Operand,
+        rhs: Operand,
+        invert: bool,
+    },
+    Test {
+        value: Register,
+        invert: bool,
+    },
+    TestSet {
+        destination: Register,
+        value: Register,
+        invert: bool,
+    },
+    Call {
+        function: Register,
+        arguments: u8,
+        return_values: u8,
+    },
+    TailCall {
+        function: Register,
+        arguments: u8,
+    },
+    Return(Register, u8),
+    // FORLOOP, jumps back to the body while the counter hasn't passed the limit
+    IterateNumericForLoop {
+        // internal_counter, limit, step, external_counter
+        control: Vec<Register>,
+        skip: i32,
+    },
+    // FORPREP, skips the loop if it doesn't run at all. it's read as a jump to FORLOOP
+    // like in 5.3, since that's what the ast for loops are built from
+    InitNumericForLoop {
+        // internal_counter, limit, step, external_counter
+        // the name "control" refers to just the counter
+        control: Vec<Register>,
+        skip: i32,
+    },
+    // TFORPREP, jumps to TFORCALL
+    InitGenericForLoop {
+        skip: i32,
+    },
+    // TFORCALL, always followed by TFORLOOP
+    GenericForCall {
+        // ex. `next` in `for i, v in next, {}, 5`
+        generator: Register,
+        // ex. `{}` in `for i, v in next, {}, 5`
+        state: Register,
+        // internal control variable
+        // initial value ex. `5` in `for i, v in next, {}, 5`
+        internal_control: Register,
+        // variables returned by generator call, starting with the external control
+        vars: Vec<Register>,
+    },
+    // TFORLOOP, jumps back to the body if the external control isn't nil
+    IterateGenericForLoop {
+        internal_control: Register,
+        external_control: Register,
+        skip: i32,
+    },
+    SetList {
+        table: Register,
+        number_of_elements: u8,
+        // the elements are stored from `offset + 1`
+        offset: u32,
+    },
+    Closure {
+        destination: Register,
+        function: Function,
+    },
+    VarArg(Register, u8),
+    // adjusts the stack for a vararg function with this many parameters
+    PrepVarArg(u8),
+    // the argument of the previous instruction, already folded into it
+    ExtraArgument(u32),
+}
+
+impl Instruction {
+    pub fn parse_list<'a>(input: &'a [u8], header: &Header) -> IResult<&'a [u8], Vec<Self>> {
+        let (input, code_length) = header.parse_int(input)?;
+        let (input, code) = count(
+            |input| RawInstruction::parse(input, header),
+            code_length as usize,
+        )(input)?;
+
+        let instructions = code
+            .iter()
+            .enumerate()
+            .map(|(index, instruction)| Self::from_raw(instruction, code.get(index + 1)))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| Err::Failure(Error::from_error_kind(input, ErrorKind::Switch)))?;
+
+        Ok((input, instructions))
+    }
+
+    fn immediate(value: u8, is_float: bool) -> Operand {
+        let value = value as i64 - OFFSET_SC;
+        if is_float {
+            Operand::Float(value as f64)
+        } else {
+            Operand::Integer(value)
+        }
+    }
+
+    fn arithmetic(
+        operation_code: OperationCode,
+        destination: Register,
+        lhs: Operand,
+        rhs: Operand,
+    ) -> Option<Self> {
+        let instruction = match operation_code {
+            OperationCode::Add | OperationCode::AddImmediate | OperationCode::AddConstant => {
+                Self::Add {
+                    destination,
+                    lhs,
+                    rhs,
+                }
+            }
+            OperationCode::Subtract | OperationCode::SubtractConstant => Self::Sub {
+                destination,
+                lhs,
+                rhs,
+            },
+            OperationCode::Multiply | OperationCode::MultiplyConstant => Self::Mul {
+                destination,
+                lhs,
+                rhs,
+            },
+            OperationCode::Divide | OperationCode::DivideConstant => Self::Div {
+                destination,
+                lhs,
+                rhs,
+            },
+            OperationCode::Modulo | OperationCode::ModuloConstant => Self::Mod {
+                destination,
+                lhs,
+                rhs,
+            },
+            OperationCode::Power | OperationCode::PowerConstant => Self::Pow {
+                destination,
+                lhs,
+                rhs,
+            },
+            OperationCode::FloorDivide | OperationCode::FloorDivideConstant => Self::IDiv {
+                destination,
+                lhs,
+                rhs,
+            },
+            OperationCode::BitwiseAnd | OperationCode::BitwiseAndConstant => Self::BitwiseAnd {
+                destination,
+                lhs,
+                rhs,
+            },
+            OperationCode::BitwiseOr | OperationCode::BitwiseOrConstant => Self::BitwiseOr {
+                destination,
+                lhs,
+                rhs,
+            },
+            OperationCode::BitwiseXor | OperationCode::BitwiseXorConstant => Self::BitwiseXor {
+                destination,
+                lhs,
+                rhs,
+            },
+            OperationCode::ShiftLeft | OperationCode::ShiftLeftImmediate => Self::ShiftLeft {
+                destination,
+                lhs,
+                rhs,
+            },
+            OperationCode::ShiftRight | OperationCode::ShiftRightImmediate => Self::ShiftRight {
+                destination,
+                lhs,
+                rhs,
+            },
+            _ => return None,
+        };
+
+        Some(instruction)
+    }
+
+    fn from_raw(instruction: &RawInstruction, next: Option<&RawInstruction>) -> Option<Self> {
+        // LOADKX, NEWTABLE and SETLIST can take their argument from the next instruction
+        let extra_argument = match next {
+            Some(&RawInstruction(OperationCode::ExtraArgument, Layout::AX { a_x })) => Some(a_x),
+            _ => None,
+        };
+        // arithmetic instructions with a constant or an immediate are followed by a
+        // metamethod fallback, which tells us if the operands were swapped, and the
+        // operator and immediate of the source if they were rewritten
+        let fallback = match next {
+            Some(&RawInstruction(
+                OperationCode::MetaMethodImmediate | OperationCode::MetaMethodConstant,
+                Layout::BC { b, c, k, .. },
+            )) => Some((b, c, k)),
+            _ => None,
+        };
+        let flipped = fallback.is_some_and(|(.., k)| k);
+
+        let instruction = match *instruction {
+            RawInstruction(OperationCode::Move, Layout::BC { a, b, .. }) => Self::Move {
+                destination: Register(a),
+                source: Register(b),
+            },
+            RawInstruction(OperationCode::LoadInteger, Layout::BSx { a, b_sx }) => {
+                Self::LoadInteger {
+                    destination: Register(a),
+                    value: b_sx.into(),
+                }
+            }
+            RawInstruction(OperationCode::LoadFloat, Layout::BSx { a, b_sx }) => Self::LoadFloat {
+                destination: Register(a),
+                value: b_sx.into(),
+            },
+            RawInstruction(OperationCode::LoadConstant, Layout::BX { a, b_x }) => {
+                Self::LoadConstant {
+                    destination: Register(a),
+                    source: Constant(b_x),
+                }
+            }
+            RawInstruction(OperationCode::LoadConstantExtended, Layout::BX { a, .. }) => {
+                Self::LoadConstant {
+                    destination: Register(a),
+                    source: Constant(extra_argument?),
+                }
+            }
+            RawInstruction(
+                operation_code @ (OperationCode::LoadFalse
+                | OperationCode::LoadFalseSkip
+                | OperationCode::LoadTrue),
+                Layout::BC { a, .. },
+            ) => Self::LoadBoolean {
+                destination: Register(a),
+                value: operation_code == OperationCode::LoadTrue,
+                skip_next: operation_code == OperationCode::LoadFalseSkip,
+            },
+            RawInstruction(OperationCode::LoadNil, Layout::BC { a, b, .. }) => {
+                Self::LoadNil((a..=a + b).map(Register).collect())
+            }
+            RawInstruction(OperationCode::GetUpvalue, Layout::BC { a, b, .. }) => {
+                Self::GetUpvalue {
+                    destination: Register(a),
+                    upvalue: Upvalue(b),
+                }
+            }
+            RawInstruction(OperationCode::SetUpvalue, Layout::BC { a, b, .. }) => {
+                Self::SetUpvalue {
+                    destination: Upvalue(b),
+                    source: Register(a),
+                }
+            }
+            RawInstruction(OperationCode::GetTableUpvalue, Layout::BC { a, b, c, .. }) => {
+                Self::GetTableUpvalue {
+                    destination: Register(a),
+                    upvalue: Upvalue(b),
+                    key: Constant(c.into()).into(),
+                }
+            }
+            RawInstruction(OperationCode::SetTableUpvalue, Layout::BC { a, b, c, k }) => {
+                Self::SetTableUpvalue {
+                    upvalue: Upvalue(a),
+                    key: Constant(b.into()).into(),
+                    value: Operand::register_or_constant(c, k),
+                }
+            }
+            RawInstruction(
+                operation_code @ (OperationCode::GetIndex
+                | OperationCode::GetIndexInteger
+                | OperationCode::GetField),
+                Layout::BC { a, b, c, .. },
+            ) => Self::GetIndex {
+                destination: Register(a),
+                object: Register(b),
+                key: match operation_code {
+                    OperationCode::GetIndex => Register(c).into(),
+                    OperationCode::GetIndexInteger => Operand::Integer(c.into()),
+                    _ => Constant(c.into()).into(),
+                },
+            },
+            RawInstruction(
+                operation_code @ (OperationCode::SetIndex
+                | OperationCode::SetIndexInteger
+                | OperationCode::SetField),
+                Layout::BC { a, b, c, k },
+            ) => Self::SetIndex {
+                object: Register(a),
+                key: match operation_code {
+                    OperationCode::SetIndex => Register(b).into(),
+                    OperationCode::SetIndexInteger => Operand::Integer(b.into()),
+                    _ => Constant(b.into()).into(),
+                },
+                value: Operand::register_or_constant(c, k),
+            },
+            RawInstruction(OperationCode::NewTable, Layout::BC { a, b, c, k }) => Self::NewTable {
+                destination: Register(a),
+                array_size: if k {
+                    extra_argument? * 256 + c as u32
+                } else {
+                    c.into()
+                },
+                hash_size: if b > 0 { 1 << (b - 1) } else { 0 },
+            },
+            RawInstruction(OperationCode::PrepMethodCall, Layout::BC { a, b, c, k }) => {
+                Self::PrepMethodCall {
+                    destination: Register(a),
+                    self_arg: Register(a + 1),
+                    object: Register(b),
+                    method: Operand::register_or_constant(c, k),
+                }
+            }
+            // `x - 1` is compiled as `x + -1` and `x << 1` as `x >> -1`
+            RawInstruction(
+                operation_code @ (OperationCode::AddImmediate | OperationCode::ShiftRightImmediate),
+                Layout::BC { a, b, c, .. },
+            ) => {
+                let destination = Register(a);
+                let lhs = Register(b).into();
+                match fallback {
+                    Some((immediate, TM_SUB, _))
+                        if operation_code == OperationCode::AddImmediate =>
+                    {
+                        Self::Sub {
+                            destination,
+                            lhs,
+                            rhs: Self::immediate(immediate, false),
+                        }
+                    }
+                    Some((immediate, TM_SHL, _))
+                        if operation_code == OperationCode::ShiftRightImmediate =>
+                    {
+                        Self::ShiftLeft {
+                            destination,
+                            lhs,
+                            rhs: Self::immediate(immediate, false),
+                        }
+                    }
+                    _ => {
+                        let rhs = Self::immediate(c, false);
+                        let (lhs, rhs) = if flipped { (rhs, lhs) } else { (lhs, rhs) };
+                        Self::arithmetic(operation_code, destination, lhs, rhs)?
+                    }
+                }
+            }
+            RawInstruction(OperationCode::ShiftLeftImmediate, Layout::BC { a, b, c, .. }) => {
+                Self::ShiftLeft {
+                    destination: Register(a),
+                    lhs: Self::immediate(c, false),
+                    rhs: Register(b).into(),
+                }
+            }
+            RawInstruction(
+                operation_code @ (OperationCode::AddConstant
+                | OperationCode::SubtractConstant
+                | OperationCode::MultiplyConstant
+                | OperationCode::ModuloConstant
+                | OperationCode::PowerConstant
+                | OperationCode::DivideConstant
+                | OperationCode::FloorDivideConstant
+                | OperationCode::BitwiseAndConstant
+                | OperationCode::BitwiseOrConstant
+                | OperationCode::BitwiseXorConstant),
+                Layout::BC { a, b, c, .. },
+            ) => {
+                let (lhs, rhs) = (Register(b).into(), Constant(c.into()).into());
+                let (lhs, rhs) = if flipped { (rhs, lhs) } else { (lhs, rhs) };
+                Self::arithmetic(operation_code, Register(a), lhs, rhs)?
+            }
+            RawInstruction(
+                operation_code @ (OperationCode::Add
+                | OperationCode::Subtract
+                | OperationCode::Multiply
+                | OperationCode::Modulo
+                | OperationCode::Power
+                | OperationCode::Divide
+                | OperationCode::FloorDivide
+                | OperationCode::BitwiseAnd
+                | OperationCode::BitwiseOr
+                | OperationCode::BitwiseXor
+                | OperationCode::ShiftLeft
+                | OperationCode::ShiftRight),
+                Layout::BC { a, b, c, .. },
+            ) => Self::arithmetic(
+                operation_code,
+                Register(a),
+                Register(b).into(),
+                Register(c).into(),
+            )?,
+            RawInstruction(
+                OperationCode::MetaMethod
+                | OperationCode::MetaMethodImmediate
+                | OperationCode::MetaMethodConstant,
+                Layout::BC { .. },
+            ) => Self::MetaMethod,
+            RawInstruction(OperationCode::Minus, Layout::BC { a, b, .. }) => Self::Minus {
+                destination: Register(a),
+                operand: Register(b),
+            },
+            RawInstruction(OperationCode::BitwiseNot, Layout::BC { a, b, .. }) => {
+                Self::BitwiseNot {
+                    destination: Register(a),
+                    operand: Register(b),
+                }
+            }
+            RawInstruction(OperationCode::Not, Layout::BC { a, b, .. }) => Self::Not {
+                destination: Register(a),
+                operand: Register(b),
+            },
+            RawInstruction(OperationCode::Length, Layout::BC { a, b, .. }) => Self::Length {
+                destination: Register(a),
+                operand: Register(b),
+            },
+            RawInstruction(OperationCode::Concatenate, Layout::BC { a, b, .. }) => {
+                Self::Concatenate {
+                    destination: Register(a),
+                    operands: (a..a + b).map(Register).collect(),
+                }
+            }
+            RawInstruction(OperationCode::Close, Layout::BC { a, .. }) => Self::Close(Register(a)),
+            RawInstruction(OperationCode::ToBeClosed, Layout::BC { a, .. }) => {
+                Self::ToBeClosed(Register(a))
+            }
+            RawInstruction(OperationCode::Jump, Layout::SJ { s_j }) => Self::Jump { skip: s_j },
+            RawInstruction(
+                operation_code @ (OperationCode::Equal
+                | OperationCode::EqualConstant
+                | OperationCode::EqualImmediate),
+                Layout::BC { a, b, c, k },
+            ) => Self::Equal {
+                lhs: Register(a).into(),
+                rhs: match operation_code {
+                    OperationCode::Equal => Register(b).into(),
+                    OperationCode::EqualConstant => Constant(b.into()).into(),
+                    _ => Self::immediate(b, c != 0),
+                },
+                invert: !k,
+            },
+            RawInstruction(OperationCode::LessThan, Layout::BC { a, b, k, .. }) => Self::LessThan {
+                lhs: Register(a).into(),
+                rhs: Register(b).into(),
+                invert: !k,
+            },
+            RawInstruction(OperationCode::LessThanOrEqual, Layout::BC { a, b, k, .. }) => {
+                Self::LessThanOrEqual {
+                    lhs: Register(a).into(),
+                    rhs: Register(b).into(),
+                    invert: !k,
+                }
+            }
+            RawInstruction(OperationCode::LessThanImmediate, Layout::BC { a, b, c, k }) => {
+                Self::LessThan {
+                    lhs: Register(a).into(),
+                    rhs: Self::immediate(b, c != 0),
+                    invert: !k,
+                }
+            }
+            RawInstruction(OperationCode::LessThanOrEqualImmediate, Layout::BC { a, b, c, k }) => {
+                Self::LessThanOrEqual {
+                    lhs: Register(a).into(),
+                    rhs: Self::immediate(b, c != 0),
+                    invert: !k,
+                }
+            }
+            // `x > 1` is the same as `1 < x`
+            RawInstruction(OperationCode::GreaterThanImmediate, Layout::BC { a, b, c, k }) => {
+                Self::LessThan {
+                    lhs: Self::immediate(b, c != 0),
+                    rhs: Register(a).into(),
+                    invert: !k,
+                }
+            }
+            RawInstruction(
+                OperationCode::GreaterThanOrEqualImmediate,
+                Layout::BC { a, b, c, k },
+            ) => Self::LessThanOrEqual {
+                lhs: Self::immediate(b, c != 0),
+                rhs: Register(a).into(),
+                invert: !k,
+            },
+            RawInstruction(OperationCode::Test, Layout::BC { a, k, .. }) => Self::Test {
+                value: Register(a),
+                invert: !k,
+            },
+            RawInstruction(OperationCode::TestSet, Layout::BC { a, b, k, .. }) => Self::TestSet {
+                destination: Register(a),
+                value: Register(b),
+                invert: !k,
+            },
+            RawInstruction(OperationCode::Call, Layout::BC { a, b, c, .. }) => Self::Call {
+                function: Register(a),
+                arguments: b,
+                return_values: c,
+            },
+            RawInstruction(OperationCode::TailCall, Layout::BC { a, b, .. }) => Self::TailCall {
+                function: Register(a),
+                arguments: b,
+            },
+            RawInstruction(OperationCode::Return, Layout::BC { a, b, .. }) => {
+                Self::Return(Register(a), b)
+            }
+            RawInstruction(OperationCode::ReturnNone, Layout::BC { a, .. }) => {
+                Self::Return(Register(a), 1)
+            }
+            RawInstruction(OperationCode::ReturnOne, Layout::BC { a, .. }) => {
+                Self::Return(Register(a), 2)
+            }
+            RawInstruction(OperationCode::IterateNumericForLoop, Layout::BX { a, b_x }) => {
+                Self::IterateNumericForLoop {
+                    control: (a..=a + 3).map(Register).collect(),
+                    skip: -(b_x as i32),
+                }
+            }
+            RawInstruction(OperationCode::InitNumericForLoop, Layout::BX { a, b_x }) => {
+                Self::InitNumericForLoop {
+                    control: (a..=a + 3).map(Register).collect(),
+                    skip: b_x as i32,
+                }
+            }
+            RawInstruction(OperationCode::InitGenericForLoop, Layout::BX { b_x, .. }) => {
+                Self::InitGenericForLoop { skip: b_x as i32 }
+            }
+            // the register after the internal control holds the closing value
+            RawInstruction(OperationCode::GenericForCall, Layout::BC { a, c, .. }) => {
+                // must have at least external control variable
+                if c == 0 {
+                    return None;
+                }
+                Self::GenericForCall {
+                    generator: Register(a),
+                    state: Register(a + 1),
+                    internal_control: Register(a + 2),
+                    vars: (a + 4..a + 4 + c).map(Register).collect(),
+                }
+            }
+            RawInstruction(OperationCode::IterateGenericForLoop, Layout::BX { a, b_x }) => {
+                Self::IterateGenericForLoop {
+                    internal_control: Register(a + 2),
+                    external_control: Register(a + 4),
+                    skip: -(b_x as i32),
+                }
+            }
+            RawInstruction(OperationCode::SetList, Layout::BC { a, b, c, k }) => Self::SetList {
+                table: Register(a),
+                number_of_elements: b,
+                offset: if k {
+                    extra_argument? * 256 + c as u32
+                } else {
+                    c.into()
+                },
+            },
+            RawInstruction(OperationCode::Closure, Layout::BX { a, b_x }) => Self::Closure {
+                destination: Register(a),
+                function: Function(b_x),
+            },
+            RawInstruction(OperationCode::VarArg, Layout::BC { a, c, .. }) => {
+                Self::VarArg(Register(a), c)
+            }
+            RawInstruction(OperationCode::PrepVarArg, Layout::BC { a, .. }) => Self::PrepVarArg(a),
+            RawInstruction(OperationCode::ExtraArgument, Layout::AX { a_x }) => {
+                Self::ExtraArgument(a_x)
+            }
+            _ => return None,
+        };
+
+        Some(instruction)
+    }
+}
@@ -0,0 +1,197 @@
+use crate::instruction::layout::LayoutDiscriminants;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationCode {
+    Move,
+    LoadInteger,
+    LoadFloat,
+    LoadConstant,
+    LoadConstantExtended,
+    LoadFalse,
+    LoadFalseSkip,
+    LoadTrue,
+    LoadNil,
+    GetUpvalue,
+    SetUpvalue,
+    GetTableUpvalue,
+    GetIndex,
+    GetIndexInteger,
+    GetField,
+    SetTableUpvalue,
+    SetIndex,
+    SetIndexInteger,
+    SetField,
+    NewTable,
+    PrepMethodCall,
+    AddImmediate,
+    AddConstant,
+    SubtractConstant,
+    MultiplyConstant,
+    ModuloConstant,
+    PowerConstant,
+    DivideConstant,
+    FloorDivideConstant,
+    BitwiseAndConstant,
+    BitwiseOrConstant,
+    BitwiseXorConstant,
+    ShiftRightImmediate,
+    ShiftLeftImmediate,
+    Add,
+    Subtract,
+    Multiply,
+    Modulo,
+    Power,
+    Divide,
+    FloorDivide,
+    BitwiseAnd,
+    BitwiseOr,
+    BitwiseXor,
+    ShiftLeft,
+    ShiftRight,
+    MetaMethod,
+    MetaMethodImmediate,
+    MetaMethodConstant,
+    Minus,
+    BitwiseNot,
+    Not,
+    Length,
+    Concatenate,
+    Close,
+    ToBeClosed,
+    Jump,
+    Equal,
+    LessThan,
+    LessThanOrEqual,
+    EqualConstant,
+    EqualImmediate,
+    LessThanImmediate,
+    LessThanOrEqualImmediate,
+    GreaterThanImmediate,
+    GreaterThanOrEqualImmediate,
+    Test,
+    TestSet,
+    Call,
+    TailCall,
+    Return,
+    ReturnNone,
+    ReturnOne,
+    IterateNumericForLoop,
+    InitNumericForLoop,
+    InitGenericForLoop,
+    GenericForCall,
+    IterateGenericForLoop,
+    SetList,
+    Closure,
+    VarArg,
+    PrepVarArg,
+    ExtraArgument,
+}
+
+// in the order of `lopcodes.h`
+const OPERATION_CODES: [OperationCode; 83] = [
+    OperationCode::Move,
+    OperationCode::LoadInteger,
+    OperationCode::LoadFloat,
+    OperationCode::LoadConstant,
+    OperationCode::LoadConstantExtended,
+    OperationCode::LoadFalse,
+    OperationCode::LoadFalseSkip,
+    OperationCode::LoadTrue,
+    OperationCode::LoadNil,
+    OperationCode::GetUpvalue,
+    OperationCode::SetUpvalue,
+    OperationCode::GetTableUpvalue,
+    OperationCode::GetIndex,
+    OperationCode::GetIndexInteger,
+    OperationCode::GetField,
+    OperationCode::SetTableUpvalue,
+    OperationCode::SetIndex,
+    OperationCode::SetIndexInteger,
+    OperationCode::SetField,
+    OperationCode::NewTable,
+    OperationCode::PrepMethodCall,
+    OperationCode::AddImmediate,
+    OperationCode::AddConstant,
+    OperationCode::SubtractConstant,
+    OperationCode::MultiplyConstant,
+    OperationCode::ModuloConstant,
+    OperationCode::PowerConstant,
+    OperationCode::DivideConstant,
+    OperationCode::FloorDivideConstant,
+    OperationCode::BitwiseAndConstant,
+    OperationCode::BitwiseOrConstant,
+    OperationCode::BitwiseXorConstant,
+    OperationCode::ShiftRightImmediate,
+    OperationCode::ShiftLeftImmediate,
+    OperationCode::Add,
+    OperationCode::Subtract,
+    OperationCode::Multiply,
+    OperationCode::Modulo,
+    OperationCode::Power,
+    OperationCode::Divide,
+    OperationCode::FloorDivide,
+    OperationCode::BitwiseAnd,
+    OperationCode::BitwiseOr,
+    OperationCode::BitwiseXor,
+    OperationCode::ShiftLeft,
+    OperationCode::ShiftRight,
+    OperationCode::MetaMethod,
+    OperationCode::MetaMethodImmediate,
+    OperationCode::MetaMethodConstant,
+    OperationCode::Minus,
+    OperationCode::BitwiseNot,
+    OperationCode::Not,
+    OperationCode::Length,
+    OperationCode::Concatenate,
+    OperationCode::Close,
+    OperationCode::ToBeClosed,
+    OperationCode::Jump,
+    OperationCode::Equal,
+    OperationCode::LessThan,
+    OperationCode::LessThanOrEqual,
+    OperationCode::EqualConstant,
+    OperationCode::EqualImmediate,
+    OperationCode::LessThanImmediate,
+    OperationCode::LessThanOrEqualImmediate,
+    OperationCode::GreaterThanImmediate,
+    OperationCode::GreaterThanOrEqualImmediate,
+    OperationCode::Test,
+    OperationCode::TestSet,
+    OperationCode::Call,
+    OperationCode::TailCall,
+    OperationCode::Return,
+    OperationCode::ReturnNone,
+    OperationCode::ReturnOne,
+    OperationCode::IterateNumericForLoop,
+    OperationCode::InitNumericForLoop,
+    OperationCode::InitGenericForLoop,
+    OperationCode::GenericForCall,
+    OperationCode::IterateGenericForLoop,
+    OperationCode::SetList,
+    OperationCode::Closure,
+    OperationCode::VarArg,
+    OperationCode::PrepVarArg,
+    OperationCode::ExtraArgument,
+];
+
+impl OperationCode {
+    pub fn from_instruction(instruction: u32) -> Option<Self> {
+        OPERATION_CODES.get((instruction & 0x7F) as usize).copied()
+    }
+
+    pub fn instruction_layout(&self) -> LayoutDiscriminants {
+        match self {
+            Self::LoadConstant
+            | Self::LoadConstantExtended
+            | Self::Closure
+            | Self::IterateNumericForLoop
+            | Self::InitNumericForLoop
+            | Self::InitGenericForLoop
+            | Self::IterateGenericForLoop => LayoutDiscriminants::BX,
+            Self::LoadInteger | Self::LoadFloat => LayoutDiscriminants::BSx,
+            Self::Jump => LayoutDiscriminants::SJ,
+            Self::ExtraArgument => LayoutDiscriminants::AX,
+            _ => LayoutDiscriminants::BC,
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use nom::{multi::count, number::complete::i8, IResult};
+
+use crate::chunk::Header;
+
+// marks an instruction whose line is stored in the absolute line info
+const ABSOLUTE_LINE_INFO: i8 = -0x80;
+
+#[derive(Debug)]
+pub struct Position {
+    pub instruction: usize,
+    pub source: u32,
+}
+
+impl Position {
+    // lines are stored as deltas from the previous instruction's line, starting at
+    // the line the function is defined on, with an absolute line every so often
+    pub fn parse<'a>(
+        input: &'a [u8],
+        header: &Header,
+        line_defined: u32,
+    ) -> IResult<&'a [u8], Vec<Self>> {
+        let (input, deltas_length) = header.parse_int(input)?;
+        let (input, deltas) = count(i8, deltas_length as usize)(input)?;
+        let (input, absolute_length) = header.parse_int(input)?;
+        let (input, absolute_lines) = count(
+            |input| {
+                let (input, instruction) = header.parse_int(input)?;
+                let (input, line) = header.parse_int(input)?;
+                Ok((input, (instruction as usize, line)))
+            },
+            absolute_length as usize,
+        )(input)?;
+
+        let mut line = line_defined;
+        let positions = deltas
+            .iter()
+            .enumerate()
+            .map(|(instruction, &delta)| {
+                line = if delta == ABSOLUTE_LINE_INFO {
+                    absolute_lines
+                        .iter()
+                        .find(|&&(absolute_instruction, _)| absolute_instruction == instruction)
+                        .map_or(line, |&(_, line)| line)
+                } else {
+                    line.wrapping_add_signed(delta.into())
+                };
+                Self {
+                    instruction,
+                    source: line,
+                }
+            })
+            .collect();
+
+        Ok((input, positions))
+    }
+}
@@ -0,0 +1,33 @@
+use std::ops::Range;
+
+use nom::{multi::count, IResult};
+
+use crate::{chunk::Header, value::parse_string};
+
+#[derive(Debug)]
+pub struct Local<'a> {
+    pub name: &'a [u8],
+    pub range: Range<u32>,
+}
+
+impl<'a> Local<'a> {
+    pub fn parse_list(input: &'a [u8], header: &Header) -> IResult<&'a [u8], Vec<Self>> {
+        let (input, length) = header.parse_int(input)?;
+
+        count(|input| Self::parse(input, header), length as usize)(input)
+    }
+
+    fn parse(input: &'a [u8], header: &Header) -> IResult<&'a [u8], Self> {
+        let (input, name) = parse_string(input, header)?;
+        let (input, start) = header.parse_int(input)?;
+        let (input, end) = header.parse_int(input)?;
+
+        Ok((
+            input,
+            Self {
+                name,
+                range: (start..end),
+            },
+        ))
+    }
+}
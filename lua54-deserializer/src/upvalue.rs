@@ -0,0 +1,60 @@
+use nom::{
+    error::{Error, ErrorKind, ParseError},
+    multi::count,
+    number::complete::le_u8,
+    Err, IResult,
+};
+
+use crate::chunk::Header;
+
+// the kind of the variable an upvalue refers to, see `vd.kind` in `lparser.h`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpvalueKind {
+    Regular,
+    Const,
+    ToBeClosed,
+    CompileTimeConstant,
+}
+
+// where a closure captures an upvalue from when it's created
+#[derive(Debug, Clone, Copy)]
+pub struct UpvalueDescriptor {
+    // a register of the enclosing function, otherwise one of its upvalues
+    pub in_stack: bool,
+    pub index: u8,
+    pub kind: UpvalueKind,
+}
+
+impl UpvalueDescriptor {
+    pub fn parse_list<'a>(input: &'a [u8], header: &Header) -> IResult<&'a [u8], Vec<Self>> {
+        let (input, length) = header.parse_int(input)?;
+
+        count(Self::parse, length as usize)(input)
+    }
+
+    fn parse(input: &[u8]) -> IResult<&[u8], Self> {
+        let (input, in_stack) = le_u8(input)?;
+        let (input, index) = le_u8(input)?;
+        let (input, kind) = match le_u8(input)? {
+            (input, 0) => (input, UpvalueKind::Regular),
+            (input, 1) => (input, UpvalueKind::Const),
+            (input, 2) => (input, UpvalueKind::ToBeClosed),
+            (input, 3) => (input, UpvalueKind::CompileTimeConstant),
+            _ => {
+                return Err(Err::Failure(Error::from_error_kind(
+                    input,
+                    ErrorKind::Switch,
+                )))
+            }
+        };
+
+        Ok((
+            input,
+            Self {
+                in_stack: in_stack != 0,
+                index,
+                kind,
+            },
+        ))
+    }
+}
@@ -0,0 +1,67 @@
+use enum_as_inner::EnumAsInner;
+use nom::{
+    bytes::complete::take,
+    error::{Error, ErrorKind, ParseError},
+    multi::count,
+    number::complete::le_u8,
+    Err, IResult,
+};
+
+use crate::chunk::Header;
+
+#[derive(Debug, EnumAsInner)]
+pub enum Value<'a> {
+    Nil,
+    Boolean(bool),
+    Integer(i64),
+    Float(f64),
+    String(&'a [u8]),
+}
+
+impl<'a> Value<'a> {
+    pub fn parse(input: &'a [u8], header: &Header) -> IResult<&'a [u8], Self> {
+        let (input, kind) = le_u8(input)?;
+
+        // the variant tags of `lobject.h`, booleans are two tags without a payload
+        match kind {
+            0 => Ok((input, Self::Nil)),
+            1 => Ok((input, Self::Boolean(false))),
+            17 => Ok((input, Self::Boolean(true))),
+            3 => {
+                let (input, value) = header.parse_integer(input)?;
+
+                Ok((input, Self::Integer(value)))
+            }
+            19 => {
+                let (input, value) = header.parse_number(input)?;
+
+                Ok((input, Self::Float(value)))
+            }
+            // short and long strings
+            4 | 20 => {
+                let (input, value) = parse_string(input, header)?;
+
+                Ok((input, Self::String(value)))
+            }
+            _ => Err(Err::Failure(Error::from_error_kind(
+                input,
+                ErrorKind::Switch,
+            ))),
+        }
+    }
+}
+
+// a null string is read as empty, the length counts a null terminator that isn't stored
+pub fn parse_string<'a>(input: &'a [u8], header: &Header) -> IResult<&'a [u8], &'a [u8]> {
+    let (input, string_length) = header.parse_size(input)?;
+
+    take(string_length.saturating_sub(1))(input)
+}
+
+pub fn parse_strings<'a>(input: &'a [u8], header: &Header) -> IResult<&'a [u8], Vec<&'a [u8]>> {
+    let (input, string_count) = header.parse_int(input)?;
+    let (input, strings) =
+        count(|input| parse_string(input, header), string_count as usize)(input)?;
+
+    Ok((input, strings))
+}
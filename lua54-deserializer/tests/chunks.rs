@@ -0,0 +1,155 @@
+use lua54_deserializer::{deserialize, error::DeserializeError, UpvalueKind, Value};
+use nom::error::ErrorKind;
+
+const LUAC_DATA: &[u8] = b"\x19\x93\r\n\x1a\n";
+
+// `int` and `size_t` values, most significant group first
+fn size(mut value: u64) -> Vec<u8> {
+    let mut groups = vec![(value & 0x7F) as u8 | 0x80];
+    value >>= 7;
+    while value != 0 {
+        groups.push((value & 0x7F) as u8);
+        value >>= 7;
+    }
+    groups.reverse();
+    groups
+}
+
+fn string(string: &[u8]) -> Vec<u8> {
+    let mut out = size(string.len() as u64 + 1);
+    out.extend(string);
+    out
+}
+
+// a little-endian header with 8-byte integers and numbers
+fn header() -> Vec<u8> {
+    let mut out = b"\x1BLua\x54\x00".to_vec();
+    out.extend(LUAC_DATA);
+    out.extend([4, 8, 8]);
+    out.extend(0x5678i64.to_le_bytes());
+    out.extend(370.5f64.to_le_bytes());
+    out
+}
+
+// a function that returns, with the given constants and upvalues and no debug info
+fn function(line_defined: u64, constants: &[u8], upvalues: &[[u8; 3]]) -> Vec<u8> {
+    let mut out = string(b"@test.lua");
+    out.extend(size(line_defined));
+    out.extend(size(line_defined));
+    out.extend([0, 1, 2]);
+    // `RETURN0`
+    out.extend(size(1));
+    out.extend(71u32.to_le_bytes());
+    out.extend(constants);
+    out.extend(size(upvalues.len() as u64));
+    out.extend(upvalues.concat());
+    // closures, line info, absolute line info, locals and upvalue names
+    out.extend([size(0), size(0), size(0), size(0), size(0)].concat());
+    out
+}
+
+fn chunk(line_defined: u64, constants: &[u8], upvalues: &[[u8; 3]]) -> Vec<u8> {
+    let mut out = header();
+    out.push(upvalues.len() as u8);
+    out.extend(function(line_defined, constants, upvalues));
+    out
+}
+
+#[test]
+fn parses_constants() {
+    let long_string = vec![b'x'; 300];
+    let mut constants = size(6);
+    constants.extend([0, 1, 17, 3]);
+    constants.extend((-2i64).to_le_bytes());
+    constants.push(19);
+    constants.extend(0.5f64.to_le_bytes());
+    constants.push(20);
+    constants.extend(string(&long_string));
+    let bytecode = chunk(0, &constants, &[[1, 0, 0]]);
+    let chunk = deserialize(&bytecode).unwrap();
+    assert!(matches!(
+        chunk.function.constants[..],
+        [
+            Value::Nil,
+            Value::Boolean(false),
+            Value::Boolean(true),
+            Value::Integer(-2),
+            Value::Float(value),
+            Value::String(string),
+        ] if value == 0.5 && string == &long_string[..]
+    ));
+}
+
+#[test]
+fn parses_sizes() {
+    // one, two, three and five groups
+    for line_defined in [0x7F, 0x80, 0x3FFF, 0x4000, u32::MAX as u64] {
+        let bytecode = chunk(line_defined, &size(0), &[]);
+        let chunk = deserialize(&bytecode).unwrap();
+        assert_eq!(chunk.function.line_defined as u64, line_defined);
+    }
+}
+
+#[test]
+fn rejects_ints_that_dont_fit() {
+    let bytecode = chunk(1 << 32, &size(0), &[]);
+    let offset = header().len() + 1 + string(b"@test.lua").len() + size(1 << 32).len();
+    assert_eq!(
+        deserialize(&bytecode).unwrap_err(),
+        DeserializeError::Malformed {
+            offset,
+            kind: ErrorKind::TooLarge,
+        }
+    );
+}
+
+#[test]
+fn rejects_sizes_that_overflow() {
+    // 10 groups of 7 bits don't fit in 64
+    let mut bytecode = header();
+    bytecode.push(0);
+    bytecode.extend([0x7F; 9]);
+    bytecode.push(0xFF);
+    assert_eq!(
+        deserialize(&bytecode).unwrap_err(),
+        DeserializeError::Malformed {
+            offset: header().len() + 1 + 10,
+            kind: ErrorKind::TooLarge,
+        }
+    );
+}
+
+#[test]
+fn parses_upvalue_kinds() {
+    let bytecode = chunk(0, &size(0), &[[1, 0, 0], [1, 1, 1], [1, 2, 2], [0, 0, 3]]);
+    let chunk = deserialize(&bytecode).unwrap();
+    let upvalues = &chunk.function.upvalues;
+    assert_eq!(
+        upvalues
+            .iter()
+            .map(|upvalue| upvalue.kind)
+            .collect::<Vec<_>>(),
+        [
+            UpvalueKind::Regular,
+            UpvalueKind::Const,
+            UpvalueKind::ToBeClosed,
+            UpvalueKind::CompileTimeConstant,
+        ]
+    );
+    assert!(upvalues[2].in_stack && upvalues[2].index == 2);
+    assert!(!upvalues[3].in_stack);
+}
+
+#[test]
+fn rejects_unknown_upvalue_kinds() {
+    let bytecode = chunk(0, &size(0), &[[1, 0, 4]]);
+    // the kind is followed by the five empty lists
+    let offset = bytecode.len() - 6;
+    assert_eq!(
+        deserialize(&bytecode).unwrap_err(),
+        DeserializeError::Malformed {
+            offset,
+            kind: ErrorKind::Switch,
+        }
+    );
+}
@@ -0,0 +1,46 @@
+#![feature(let_chains)]
+
+use lifter::Lifter;
+use parking_lot::Mutex;
+use pipeline::{isolate, Lifted};
+use triomphe::Arc;
+
+pub use lua54_deserializer::DeserializeError;
+pub use pipeline::install_panic_hook;
+
+mod lifter;
+
+pub type DecompileError = pipeline::DecompileError<DeserializeError>;
+
+pub fn decompile_bytecode(bytecode: &[u8]) -> Result<String, DecompileError> {
+    let chunk = lua54_deserializer::deserialize(bytecode).map_err(DecompileError::Deserialize)?;
+    let mut lifted = Vec::new();
+    // `load` sets the first upvalue of the main function to the globals table
+    let environment = (0..chunk.function.upvalues.len())
+        .map(|index| match chunk.function.upvalue_names.get(index) {
+            Some(name) => *name == b"_ENV",
+            None => index == 0,
+        })
+        .collect::<Vec<_>>();
+    let (function, upvalues) = isolate(false, || {
+        Lifter::lift(&chunk.function, &environment, &mut lifted)
+    })
+    .map_err(DecompileError::Lift)?;
+    let main = Arc::<Mutex<_>>::default();
+    lifted.push((main.clone(), function, upvalues));
+
+    let lifted = lifted
+        .into_iter()
+        .rev()
+        .map(|(ast_function, function, upvalues_in)| Lifted {
+            ast_function,
+            function,
+            upvalues_in,
+            data: (),
+        })
+        .collect();
+    let (upvalues, _) = pipeline::decompile_functions(lifted, &Default::default(), |_, _| {
+        "failed to decompile".to_string()
+    });
+    Ok(pipeline::link(main, upvalues, false).to_string())
+}
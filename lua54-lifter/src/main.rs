@@ -0,0 +1,26 @@
+use std::path::Path;
+
+use clap::Parser;
+
+#[cfg(feature = "dhat-heap")]
+#[global_allocator]
+static ALLOC: dhat::Alloc = dhat::Alloc;
+
+#[derive(Parser, Debug)]
+#[clap(about, version, author)]
+struct Args {
+    #[clap(short, long)]
+    file: String,
+}
+
+fn main() -> anyhow::Result<()> {
+    #[cfg(feature = "dhat-heap")]
+    let _profiler = dhat::Profiler::new_heap();
+
+    let args = Args::parse();
+    pipeline::cli::decompile_file(
+        Path::new(&args.file),
+        "dec.54.lua",
+        lua54_lifter::decompile_bytecode,
+    )
+}
@@ -0,0 +1,266 @@
+use lua54_lifter::decompile_bytecode;
+
+const LUAC_DATA: &[u8] = b"\x19\x93\r\n\x1a\n";
+
+const OP_CODES: &str = "\
+    MOVE LOADI LOADF LOADK LOADKX LOADFALSE LFALSESKIP LOADTRUE LOADNIL GETUPVAL SETUPVAL \
+    GETTABUP GETTABLE GETI GETFIELD SETTABUP SETTABLE SETI SETFIELD NEWTABLE SELF ADDI ADDK \
+    SUBK MULK MODK POWK DIVK IDIVK BANDK BORK BXORK SHRI SHLI ADD SUB MUL MOD POW DIV IDIV \
+    BAND BOR BXOR SHL SHR MMBIN MMBINI MMBINK UNM BNOT NOT LEN CONCAT CLOSE TBC JMP EQ LT \
+    LE EQK EQI LTI LEI GTI GEI TEST TESTSET CALL TAILCALL RETURN RETURN0 RETURN1 FORLOOP \
+    FORPREP TFORPREP TFORCALL TFORLOOP SETLIST CLOSURE VARARG VARARGPREP EXTRAARG";
+
+fn op_code(name: &str) -> u32 {
+    OP_CODES
+        .split_whitespace()
+        .position(|op_code| op_code == name)
+        .unwrap() as u32
+}
+
+fn abck(name: &str, a: u32, b: u32, c: u32, k: bool) -> u32 {
+    op_code(name) | a << 7 | (k as u32) << 15 | b << 16 | c << 24
+}
+
+fn abc(name: &str, a: u32, b: u32, c: u32) -> u32 {
+    abck(name, a, b, c, false)
+}
+
+fn abx(name: &str, a: u32, bx: u32) -> u32 {
+    op_code(name) | a << 7 | bx << 15
+}
+
+fn sj(name: &str, j: i32) -> u32 {
+    op_code(name) | ((j + 0xFFFFFF) as u32) << 7
+}
+
+// signed `B` and `C` operands
+fn s(value: i32) -> u32 {
+    (value + 127) as u32
+}
+
+fn size(mut value: u32) -> Vec<u8> {
+    let mut groups = vec![(value & 0x7F) as u8 | 0x80];
+    value >>= 7;
+    while value != 0 {
+        groups.push((value & 0x7F) as u8);
+        value >>= 7;
+    }
+    groups.reverse();
+    groups
+}
+
+fn string(string: Option<&[u8]>) -> Vec<u8> {
+    match string {
+        Some(string) => [size(string.len() as u32 + 1), string.to_vec()].concat(),
+        None => size(0),
+    }
+}
+
+// a function with string constants and the given debug info
+fn function(
+    source: Option<&[u8]>,
+    code: &[u32],
+    constants: &[&[u8]],
+    upvalues: &[[u8; 3]],
+    closures: &[Vec<u8>],
+    locals: &[(&[u8], u32, u32)],
+    upvalue_names: &[&[u8]],
+) -> Vec<u8> {
+    let mut out = string(source);
+    out.extend([size(0), size(0)].concat());
+    out.extend([0, 1, 12]);
+    out.extend(size(code.len() as u32));
+    for &instruction in code {
+        out.extend(instruction.to_le_bytes());
+    }
+    out.extend(size(constants.len() as u32));
+    for &constant in constants {
+        out.push(4);
+        out.extend(string(Some(constant)));
+    }
+    out.extend(size(upvalues.len() as u32));
+    out.extend(upvalues.concat());
+    out.extend(size(closures.len() as u32));
+    out.extend(closures.concat());
+    // no line info
+    out.extend([size(0), size(0)].concat());
+    out.extend(size(locals.len() as u32));
+    for &(name, start, end) in locals {
+        out.extend(string(Some(name)));
+        out.extend(size(start));
+        out.extend(size(end));
+    }
+    out.extend(size(upvalue_names.len() as u32));
+    for &name in upvalue_names {
+        out.extend(string(Some(name)));
+    }
+    out
+}
+
+// local x <close> = f()
+// local c <const> = g()
+// local function h() return c end
+// print(x, h, c - 1, 1 + c, c << 2, c > 3)
+fn attributes(debug: bool) -> Vec<u8> {
+    let (upvalue_names, child_upvalue_names): (&[&[u8]], &[&[u8]]) = if debug {
+        (&[b"_ENV"], &[b"c"])
+    } else {
+        (&[], &[])
+    };
+    let locals: &[(&[u8], u32, u32)] = if debug {
+        &[(b"x", 3, 23), (b"c", 6, 23), (b"h", 7, 23)]
+    } else {
+        &[]
+    };
+    // the child captures `c` as a `<const>` local
+    let child = function(
+        None,
+        &[
+            abc("GETUPVAL", 0, 0, 0),
+            abc("RETURN1", 0, 0, 0),
+            abc("RETURN0", 0, 0, 0),
+        ],
+        &[],
+        &[[1, 1, 1]],
+        &[],
+        &[],
+        child_upvalue_names,
+    );
+    let code = [
+        abc("VARARGPREP", 0, 0, 0),
+        abc("GETTABUP", 0, 0, 0),
+        abc("CALL", 0, 1, 2),
+        abc("TBC", 0, 0, 0),
+        abc("GETTABUP", 1, 0, 1),
+        abc("CALL", 1, 1, 2),
+        abx("CLOSURE", 2, 0),
+        abc("GETTABUP", 3, 0, 2),
+        abc("MOVE", 4, 0, 0),
+        abc("MOVE", 5, 2, 0),
+        abc("ADDI", 6, 1, s(-1)),
+        abc("MMBINI", 1, s(1), 7),
+        abc("ADDI", 7, 1, s(1)),
+        abck("MMBINI", 1, s(1), 6, true),
+        abc("SHRI", 8, 1, s(-2)),
+        abc("MMBINI", 1, s(2), 16),
+        abck("GTI", 1, s(3), 0, true),
+        sj("JMP", 1),
+        abc("LFALSESKIP", 9, 0, 0),
+        abc("LOADTRUE", 9, 0, 0),
+        abc("CALL", 3, 7, 1),
+        abc("CLOSE", 0, 0, 0),
+        abck("RETURN", 3, 1, 1, true),
+    ];
+    chunk(function(
+        Some(b"@test.lua"),
+        &code,
+        &[b"f", b"g", b"print"],
+        &[[1, 0, 0]],
+        &[child],
+        locals,
+        upvalue_names,
+    ))
+}
+
+// a chunk with `main` as its main function, which has one upvalue
+fn chunk(main: Vec<u8>) -> Vec<u8> {
+    let mut bytecode = b"\x1BLua\x54\x00".to_vec();
+    bytecode.extend(LUAC_DATA);
+    bytecode.extend([4, 8, 8]);
+    bytecode.extend(0x5678i64.to_le_bytes());
+    bytecode.extend(370.5f64.to_le_bytes());
+    bytecode.push(1);
+    bytecode.extend(main);
+    bytecode
+}
+
+#[test]
+fn lifts_attributes() {
+    assert_eq!(
+        decompile_bytecode(&attributes(true)).unwrap(),
+        "local x <close> = f()\n\
+        local c <const> = g()\n\
+        local v1 = c\n\
+        local function h()\n\
+        \t-- upvalues: (ref) c\n\
+        \treturn c\n\
+        end\n\
+        print(x, h, v1 - 1, 1 + v1, v1 << 2, v1 > 3)"
+    );
+}
+
+#[test]
+fn lifts_close_without_debug_info() {
+    // `<close>` is implied by `TBC`, `<const>` is only in the debug info
+    assert_eq!(
+        decompile_bytecode(&attributes(false)).unwrap(),
+        "local v1 <close> = f()\n\
+        local v_u_2 = g()\n\
+        local function v3()\n\
+        \t-- upvalues: (ref) v_u_2\n\
+        \treturn v_u_2\n\
+        end\n\
+        print(v1, v3, v_u_2 - 1, 1 + v_u_2, v_u_2 << 2, v_u_2 > 3)"
+    );
+}
+
+#[test]
+fn keeps_names_that_shadow_unused_locals() {
+    // local x = f()
+    // local x = g(x)
+    // print(x)
+    let code = [
+        abc("VARARGPREP", 0, 0, 0),
+        abc("GETTABUP", 0, 0, 0),
+        abc("CALL", 0, 1, 2),
+        abc("GETTABUP", 1, 0, 1),
+        abc("MOVE", 2, 0, 0),
+        abc("CALL", 1, 2, 2),
+        abc("GETTABUP", 2, 0, 2),
+        abc("MOVE", 3, 1, 0),
+        abc("CALL", 2, 2, 1),
+        abck("RETURN", 2, 1, 1, true),
+    ];
+    let main = function(
+        Some(b"@test.lua"),
+        &code,
+        &[b"f", b"g", b"print"],
+        &[[1, 0, 0]],
+        &[],
+        &[(b"x", 3, 10), (b"x", 6, 10)],
+        &[b"_ENV"],
+    );
+    assert_eq!(
+        decompile_bytecode(&chunk(main)).unwrap(),
+        "local x = f()\nlocal x = g(x)\nprint(x)"
+    );
+}
+
+#[test]
+fn renames_locals_that_shadow_used_globals() {
+    // local x = f()
+    // print(x, _ENV.x)
+    let code = [
+        abc("VARARGPREP", 0, 0, 0),
+        abc("GETTABUP", 0, 0, 0),
+        abc("CALL", 0, 1, 2),
+        abc("GETTABUP", 1, 0, 1),
+        abc("MOVE", 2, 0, 0),
+        abc("GETTABUP", 3, 0, 2),
+        abc("CALL", 1, 3, 1),
+        abck("RETURN", 1, 1, 1, true),
+    ];
+    let main = function(
+        Some(b"@test.lua"),
+        &code,
+        &[b"f", b"print", b"x"],
+        &[[1, 0, 0]],
+        &[],
+        &[(b"x", 3, 8)],
+        &[b"_ENV"],
+    );
+    assert_eq!(
+        decompile_bytecode(&chunk(main)).unwrap(),
+        "local x_2 = f()\nprint(x_2, x)"
+    );
+}
@@ -0,0 +1,137 @@
+use nom::{
+    bytes::complete::{tag, take},
+    error::{Error, ErrorKind, ParseError},
+    number::{
+        self,
+        complete::{le_u8, u16, u32},
+    },
+    Err, IResult,
+};
+
+// see `BCDUMP_F_*` in `lj_bcdump.h`
+const FLAG_BIG_ENDIAN: u32 = 0x01;
+const FLAG_STRIP: u32 = 0x02;
+const FLAG_FFI: u32 = 0x04;
+const FLAG_FR2: u32 = 0x08;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Version {
+    // LuaJIT 2.0
+    V1,
+    // LuaJIT 2.1, which adds ISTYPE, ISNUM, TGETR and TSETR
+    V2,
+}
+
+#[derive(Debug)]
+pub struct Header<'a> {
+    pub version: Version,
+    pub big_endian: bool,
+    // no debug info, so no line info or names of locals and upvalues
+    pub stripped: bool,
+    // uses 64-bit integer or complex cdata constants
+    pub ffi: bool,
+    // two-slot frames of 64-bit builds with GC64, call arguments start at `A + 2`
+    pub fr2: bool,
+    pub name: &'a [u8],
+}
+
+impl<'a> Header<'a> {
+    pub fn parse(input: &'a [u8]) -> IResult<&'a [u8], Self> {
+        let (input, _) = tag("\x1BLJ")(input)?;
+        let (input, version) = match le_u8(input)? {
+            (input, 1) => (input, Version::V1),
+            (input, 2) => (input, Version::V2),
+            _ => {
+                return Err(Err::Failure(Error::from_error_kind(
+                    input,
+                    ErrorKind::Switch,
+                )))
+            }
+        };
+        let (input, flags) = parse_uleb128(input)?;
+        if flags & !(FLAG_BIG_ENDIAN | FLAG_STRIP | FLAG_FFI | FLAG_FR2) != 0 {
+            return Err(Err::Failure(Error::from_error_kind(
+                input,
+                ErrorKind::Verify,
+            )));
+        }
+        let stripped = flags & FLAG_STRIP != 0;
+        let (input, name) = if stripped {
+            (input, &b""[..])
+        } else {
+            let (input, length) = parse_uleb128(input)?;
+            take(length)(input)?
+        };
+
+        Ok((
+            input,
+            Self {
+                version,
+                big_endian: flags & FLAG_BIG_ENDIAN != 0,
+                stripped,
+                ffi: flags & FLAG_FFI != 0,
+                fr2: flags & FLAG_FR2 != 0,
+                name,
+            },
+        ))
+    }
+
+    fn endianness(&self) -> number::Endianness {
+        if self.big_endian {
+            number::Endianness::Big
+        } else {
+            number::Endianness::Little
+        }
+    }
+
+    // instructions, upvalue descriptors and line info are stored in the byte order
+    // of the machine that wrote them, everything else is ULEB128
+    pub fn parse_u16<'b>(&self, input: &'b [u8]) -> IResult<&'b [u8], u16> {
+        u16(self.endianness())(input)
+    }
+
+    pub fn parse_u32<'b>(&self, input: &'b [u8]) -> IResult<&'b [u8], u32> {
+        u32(self.endianness())(input)
+    }
+}
+
+pub fn parse_uleb128(input: &[u8]) -> IResult<&[u8], u32> {
+    let mut value = 0u32;
+    let mut shift = 0;
+    let mut input = input;
+    loop {
+        let (rest, byte) = le_u8(input)?;
+        input = rest;
+        if shift < 32 {
+            value |= ((byte & 0x7F) as u32) << shift;
+        }
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return Ok((input, value));
+        }
+    }
+}
+
+// the low bit of the first byte is a flag, see `lj_buf_ruleb128_33`
+pub fn parse_uleb128_33(input: &[u8]) -> IResult<&[u8], (u32, bool)> {
+    let (mut input, first) = le_u8(input)?;
+    let flag = first & 1 != 0;
+    let mut value = (first >> 1) as u32;
+    if value >= 0x40 {
+        value &= 0x3F;
+        let mut shift = 6;
+        loop {
+            let (rest, byte) = le_u8(input)?;
+            input = rest;
+            if shift < 32 {
+                value |= ((byte & 0x7F) as u32) << shift;
+            }
+            shift += 7;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+    }
+
+    Ok((input, (value, flag)))
+}
@@ -0,0 +1,53 @@
+use nom::{
+    error::{Error, ErrorKind, ParseError},
+    Err, IResult,
+};
+
+pub use header::{Header, Version};
+
+use crate::function::Function;
+
+pub mod header;
+
+#[derive(Debug)]
+pub struct Chunk<'a> {
+    pub header: Header<'a>,
+    pub function: Function<'a>,
+}
+
+impl<'a> Chunk<'a> {
+    pub fn parse(input: &'a [u8]) -> IResult<&'a [u8], Self> {
+        let (input, header) = Header::parse(input)?;
+        // prototypes are stored children first, each one taking the closures
+        // it creates off the top of the stack. the main function comes last.
+        let mut stack = Vec::new();
+        let mut input = input;
+        loop {
+            let (rest, length) = header::parse_uleb128(input)?;
+            input = rest;
+            if length == 0 {
+                break;
+            }
+            let (rest, function) = Function::parse(input, &header, &mut stack)?;
+            if input.len() - rest.len() != length as usize {
+                return Err(Err::Failure(Error::from_error_kind(
+                    input,
+                    ErrorKind::LengthValue,
+                )));
+            }
+            input = rest;
+            stack.push(function);
+        }
+        let function = match (stack.pop(), stack.is_empty()) {
+            (Some(function), true) => function,
+            _ => {
+                return Err(Err::Failure(Error::from_error_kind(
+                    input,
+                    ErrorKind::Verify,
+                )))
+            }
+        };
+
+        Ok((input, Self { header, function }))
+    }
+}
@@ -0,0 +1,157 @@
+use nom::{
+    bytes::complete::{take, take_until},
+    error::{Error, ErrorKind, ParseError},
+    multi::count,
+    number::complete::le_u8,
+    Err, IResult,
+};
+
+use crate::{
+    chunk::{header::parse_uleb128, Header},
+    instruction::Instruction,
+    local::Local,
+    upvalue::UpvalueDescriptor,
+    value::{self, Value},
+};
+
+// see `PROTO_*` in `lj_obj.h`
+const FLAG_VARARG: u8 = 0x02;
+
+#[derive(Debug)]
+pub struct Function<'a> {
+    pub name: &'a [u8],
+    pub line_defined: u32,
+    pub number_of_lines: u32,
+    pub is_variadic: bool,
+    pub frame_size: u8,
+    pub code: Vec<Instruction>,
+    // indexed by the `D` argument, the reverse of how they're stored
+    pub constants: Vec<Value<'a>>,
+    pub numbers: Vec<f64>,
+    pub upvalues: Vec<UpvalueDescriptor>,
+    pub closures: Vec<Function<'a>>,
+    // the line of each instruction, empty if stripped
+    pub lines: Vec<u32>,
+    pub locals: Vec<Local<'a>>,
+    pub upvalue_names: Vec<&'a [u8]>,
+    pub number_of_parameters: u8,
+}
+
+impl<'a> Function<'a> {
+    // `stack` holds the functions read so far that haven't been taken as a closure yet
+    pub fn parse(
+        input: &'a [u8],
+        header: &Header<'a>,
+        stack: &mut Vec<Function<'a>>,
+    ) -> IResult<&'a [u8], Self> {
+        let (input, flags) = le_u8(input)?;
+        let (input, number_of_parameters) = le_u8(input)?;
+        let (input, frame_size) = le_u8(input)?;
+        let (input, upvalues_length) = le_u8(input)?;
+        let (input, constants_length) = parse_uleb128(input)?;
+        let (input, numbers_length) = parse_uleb128(input)?;
+        let (input, code_length) = parse_uleb128(input)?;
+        let (input, debug_length) = if header.stripped {
+            (input, 0)
+        } else {
+            parse_uleb128(input)?
+        };
+        let (input, (line_defined, number_of_lines)) = if debug_length != 0 {
+            let (input, line_defined) = parse_uleb128(input)?;
+            let (input, number_of_lines) = parse_uleb128(input)?;
+            (input, (line_defined, number_of_lines))
+        } else {
+            (input, (0, 0))
+        };
+        let (input, code) = Instruction::parse_list(input, header, code_length)?;
+        let (input, upvalues) = count(
+            |input| UpvalueDescriptor::parse(input, header),
+            upvalues_length as usize,
+        )(input)?;
+        let mut closures = Vec::new();
+        let (input, mut constants) = count(
+            |input| Value::parse(input, stack, &mut closures),
+            constants_length as usize,
+        )(input)?;
+        constants.reverse();
+        let (input, numbers) = count(value::parse_number, numbers_length as usize)(input)?;
+
+        let (input, debug_info) = take(debug_length)(input)?;
+        let (lines, upvalue_names, locals) = if debug_length != 0 {
+            let (_, debug_info) = Self::parse_debug_info(
+                debug_info,
+                header,
+                code.len() - 1,
+                upvalues_length as usize,
+                line_defined,
+                number_of_lines,
+            )?;
+            debug_info
+        } else {
+            Default::default()
+        };
+
+        Ok((
+            input,
+            Self {
+                name: header.name,
+                line_defined,
+                number_of_lines,
+                is_variadic: flags & FLAG_VARARG != 0,
+                frame_size,
+                code,
+                constants,
+                numbers,
+                upvalues,
+                closures,
+                lines,
+                locals,
+                upvalue_names,
+                number_of_parameters,
+            },
+        ))
+    }
+
+    // line info is stored relative to `line_defined` in as few bytes as it takes
+    #[allow(clippy::type_complexity)]
+    fn parse_debug_info(
+        input: &'a [u8],
+        header: &Header,
+        code_length: usize,
+        upvalues_length: usize,
+        line_defined: u32,
+        number_of_lines: u32,
+    ) -> IResult<&'a [u8], (Vec<u32>, Vec<&'a [u8]>, Vec<Local<'a>>)> {
+        let (input, lines) = match number_of_lines {
+            0..=0xFF => count(le_u8, code_length)(input)
+                .map(|(input, lines)| (input, lines.into_iter().map(u32::from).collect())),
+            0x100..=0xFFFF => count(|input| header.parse_u16(input), code_length)(input)
+                .map(|(input, lines)| (input, lines.into_iter().map(u32::from).collect())),
+            _ => count(|input| header.parse_u32(input), code_length)(input),
+        }?;
+        // the header has the line the function is defined on
+        let lines = std::iter::once(line_defined)
+            .chain(lines.into_iter().map(|line: u32| line_defined + line))
+            .collect();
+        let (input, upvalue_names) = count(
+            |input| {
+                let (input, name) = take_until(&b"\0"[..])(input)?;
+                let (input, _) = take(1usize)(input)?;
+                Ok((input, name))
+            },
+            upvalues_length,
+        )(input)?;
+        let (input, locals) = Local::parse_list(input)?;
+        if locals
+            .iter()
+            .any(|local| local.range.end as usize > code_length + 1)
+        {
+            return Err(Err::Failure(Error::from_error_kind(
+                input,
+                ErrorKind::Verify,
+            )));
+        }
+
+        Ok((input, (lines, upvalue_names, locals)))
+    }
+}
@@ -0,0 +1,60 @@
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Register(pub u8);
+
+impl From<u8> for Register {
+    fn from(value: u8) -> Self {
+        Self(value)
+    }
+}
+
+// an index into the GC constants, strings, tables, cdata and prototypes
+#[derive(Debug, Copy, Clone)]
+pub struct Constant(pub u32);
+
+// an index into the number constants
+#[derive(Debug, Copy, Clone)]
+pub struct Number(pub u32);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Primitive {
+    Nil,
+    False,
+    True,
+}
+
+// most instructions have variants that take a constant in place of a register
+#[derive(Debug, Copy, Clone)]
+pub enum Operand {
+    Register(Register),
+    Constant(Constant),
+    Number(Number),
+    Integer(i32),
+    Primitive(Primitive),
+}
+
+impl From<Register> for Operand {
+    fn from(value: Register) -> Self {
+        Self::Register(value)
+    }
+}
+
+impl From<Constant> for Operand {
+    fn from(value: Constant) -> Self {
+        Self::Constant(value)
+    }
+}
+
+impl From<Number> for Operand {
+    fn from(value: Number) -> Self {
+        Self::Number(value)
+    }
+}
+
+impl From<Primitive> for Operand {
+    fn from(value: Primitive) -> Self {
+        Self::Primitive(value)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Upvalue(pub u16);
@@ -0,0 +1,643 @@
+use nom::{
+    error::{Error, ErrorKind, ParseError},
+    multi::count,
+    Err, IResult,
+};
+
+use crate::chunk::Header;
+
+use argument::{Constant, Number, Operand, Primitive, Register, Upvalue};
+use operation_code::OperationCode;
+
+pub mod argument;
+mod operation_code;
+
+// subtracted from the `D` argument of jumps
+const JUMP_BIAS: i32 = 0x8000;
+
+#[derive(Debug)]
+struct RawInstruction {
+    operation_code: OperationCode,
+    a: u8,
+    b: u8,
+    c: u8,
+    d: u16,
+}
+
+impl RawInstruction {
+    pub fn parse<'a>(input: &'a [u8], header: &Header) -> IResult<&'a [u8], Self> {
+        let (input, instruction) = header.parse_u32(input)?;
+        let operation_code = OperationCode::from_instruction(instruction, header.version)
+            .ok_or_else(|| Err::Failure(Error::from_error_kind(input, ErrorKind::Switch)))?;
+
+        Ok((
+            input,
+            Self {
+                operation_code,
+                a: (instruction >> 8) as u8,
+                c: (instruction >> 16) as u8,
+                b: (instruction >> 24) as u8,
+                d: (instruction >> 16) as u16,
+            },
+        ))
+    }
+
+    fn skip(&self) -> i32 {
+        self.d as i32 - JUMP_BIAS
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Instruction {
+    // FUNCF and FUNCV, which aren't stored. this keeps the pcs of debug info in line.
+    FunctionHeader,
+    Move {
+        destination: Register,
+        source: Register,
+    },
+    // KSTR, KCDATA, KSHORT, KNUM and KPRI
+    LoadConstant {
+        destination: Register,
+        source: Operand,
+    },
+    LoadNil(Vec<Register>),
+    GetUpvalue {
+        destination: Register,
+        upvalue: Upvalue,
+    },
+    SetUpvalue {
+        destination: Upvalue,
+        source: Operand,
+    },
+    // upvalues from this register up are closed, then it jumps
+    Close {
+        start: Register,
+        skip: i32,
+    },
+    Closure {
+        destination: Register,
+        function: Constant,
+    },
+    GetGlobal {
+        destination: Register,
+        name: Constant,
+    },
+    SetGlobal {
+        name: Constant,
+        value: Register,
+    },
+    GetIndex {
+        destination: Register,
+        object: Register,
+        key: Operand,
+    },
+    SetIndex {
+        object: Register,
+        key: Operand,
+        value: Register,
+    },
+    NewTable {
+        destination: Register,
+        array_size: u16,
+        hash_size: u16,
+    },
+    DuplicateTable {
+        destination: Register,
+        table: Constant,
+    },
+    // TSETM, the values from the register after the table up to the top are
+    // set from the index in the low 32 bits of the number
+    SetList {
+        table: Register,
+        index: Number,
+    },
+    Add {
+        destination: Register,
+        lhs: Operand,
+        rhs: Operand,
+    },
+    Sub {
+        destination: Register,
+        lhs: Operand,
+        rhs: Operand,
+    },
+    Mul {
+        destination: Register,
+        lhs: Operand,
+        rhs: Operand,
+    },
+    Div {
+        destination: Register,
+        lhs: Operand,
+        rhs: Operand,
+    },
+    Mod {
+        destination: Register,
+        lhs: Operand,
+        rhs: Operand,
+    },
+    Pow {
+        destination: Register,
+        lhs: Operand,
+        rhs: Operand,
+    },
+    Minus {
+        destination: Register,
+        operand: Register,
+    },
+    Not {
+        destination: Register,
+        operand: Register,
+    },
+    Length {
+        destination: Register,
+        operand: Register,
+    },
+    Concatenate {
+        destination: Register,
+        operands: Vec<Register>,
+    },
+    Jump {
+        skip: i32,
+    },
+    // LOOP, only used by the JIT to find loops
+    Loop {
+        skip: i32,
+    },
+    // ISTYPE and ISNUM, type assertions emitted for the JIT
+    TypeCheck,
+    // comparisons and tests take the jump that follows them if the condition holds
+    Equal {
+        lhs: Operand,
+        rhs: Operand,
+        invert: bool,
+    },
+    LessThan {
+        lhs: Operand,
+        rhs: Operand,
+        invert: bool,
+    },
+    LessThanOrEqual {
+        lhs: Operand,
+        rhs: Operand,
+        invert: bool,
+    },
+    Test {
+        value: Register,
+        invert: bool,
+    },
+    TestSet {
+        destination: Register,
+        value: Register,
+        invert: bool,
+    },
+    // the last argument is the top of the stack if `variadic`
+    Call {
+        function: Register,
+        arguments: Vec<Register>,
+        variadic: bool,
+        // 0 if the results are left on the stack
+        return_values: u8,
+    },
+    TailCall {
+        function: Register,
+        arguments: Vec<Register>,
+        variadic: bool,
+    },
+    // 0 if the values are left on the stack
+    VarArg(Register, u8),
+    Return {
+        values: Vec<Register>,
+        variadic: bool,
+    },
+    // the jump to FORL is taken if the loop isn't entered, here it points to the
+    // FORL rather than past it, like the FORPREP of Lua 5.4
+    InitNumericForLoop {
+        control: [Register; 4],
+        skip: i32,
+    },
+    IterateNumericForLoop {
+        control: [Register; 4],
+        skip: i32,
+    },
+    // the JMP or ISNEXT to the ITERC or ITERN of a generic for loop
+    InitGenericForLoop {
+        generator: Register,
+        state: Register,
+        internal_control: Register,
+        skip: i32,
+    },
+    GenericForCall {
+        generator: Register,
+        state: Register,
+        internal_control: Register,
+        vars: Vec<Register>,
+    },
+    IterateGenericForLoop {
+        internal_control: Register,
+        external_control: Register,
+        skip: i32,
+    },
+}
+
+impl Instruction {
+    fn from_raw(instruction: &RawInstruction, header: &Header) -> Self {
+        let &RawInstruction {
+            operation_code,
+            a,
+            b,
+            c,
+            d,
+        } = instruction;
+        // the second slot of a frame holds the frame link in GC64 builds
+        let first_argument = a + 1 + header.fr2 as u8;
+        let register = |value: u16| Register(value as u8);
+        let primitive = |value: u16| match value {
+            0 => Primitive::Nil,
+            1 => Primitive::False,
+            _ => Primitive::True,
+        };
+
+        match operation_code {
+            OperationCode::IsLessThan
+            | OperationCode::IsGreaterThanOrEqual
+            | OperationCode::IsLessThanOrEqual
+            | OperationCode::IsGreaterThan => {
+                let (lhs, rhs) = (Register(a).into(), register(d).into());
+                // `a >= b` is `not (a < b)` since comparisons with NaN are false
+                let invert = matches!(
+                    operation_code,
+                    OperationCode::IsGreaterThanOrEqual | OperationCode::IsGreaterThan
+                );
+                if matches!(
+                    operation_code,
+                    OperationCode::IsLessThan | OperationCode::IsGreaterThanOrEqual
+                ) {
+                    Self::LessThan { lhs, rhs, invert }
+                } else {
+                    Self::LessThanOrEqual { lhs, rhs, invert }
+                }
+            }
+            OperationCode::IsEqualVariable
+            | OperationCode::IsNotEqualVariable
+            | OperationCode::IsEqualString
+            | OperationCode::IsNotEqualString
+            | OperationCode::IsEqualNumber
+            | OperationCode::IsNotEqualNumber
+            | OperationCode::IsEqualPrimitive
+            | OperationCode::IsNotEqualPrimitive => {
+                let rhs = match operation_code {
+                    OperationCode::IsEqualVariable | OperationCode::IsNotEqualVariable => {
+                        register(d).into()
+                    }
+                    OperationCode::IsEqualString | OperationCode::IsNotEqualString => {
+                        Constant(d.into()).into()
+                    }
+                    OperationCode::IsEqualNumber | OperationCode::IsNotEqualNumber => {
+                        Number(d.into()).into()
+                    }
+                    _ => primitive(d).into(),
+                };
+                Self::Equal {
+                    lhs: Register(a).into(),
+                    rhs,
+                    invert: matches!(
+                        operation_code,
+                        OperationCode::IsNotEqualVariable
+                            | OperationCode::IsNotEqualString
+                            | OperationCode::IsNotEqualNumber
+                            | OperationCode::IsNotEqualPrimitive
+                    ),
+                }
+            }
+            OperationCode::IsTrueCopy | OperationCode::IsFalseCopy => Self::TestSet {
+                destination: Register(a),
+                value: register(d),
+                invert: operation_code == OperationCode::IsFalseCopy,
+            },
+            OperationCode::IsTrue | OperationCode::IsFalse => Self::Test {
+                value: register(d),
+                invert: operation_code == OperationCode::IsFalse,
+            },
+            OperationCode::IsType | OperationCode::IsNumber => Self::TypeCheck,
+            OperationCode::Move => Self::Move {
+                destination: Register(a),
+                source: register(d),
+            },
+            OperationCode::Not => Self::Not {
+                destination: Register(a),
+                operand: register(d),
+            },
+            OperationCode::Minus => Self::Minus {
+                destination: Register(a),
+                operand: register(d),
+            },
+            OperationCode::Length => Self::Length {
+                destination: Register(a),
+                operand: register(d),
+            },
+            OperationCode::AddVariableNumber
+            | OperationCode::SubtractVariableNumber
+            | OperationCode::MultiplyVariableNumber
+            | OperationCode::DivideVariableNumber
+            | OperationCode::ModuloVariableNumber
+            | OperationCode::AddNumberVariable
+            | OperationCode::SubtractNumberVariable
+            | OperationCode::MultiplyNumberVariable
+            | OperationCode::DivideNumberVariable
+            | OperationCode::ModuloNumberVariable
+            | OperationCode::AddVariableVariable
+            | OperationCode::SubtractVariableVariable
+            | OperationCode::MultiplyVariableVariable
+            | OperationCode::DivideVariableVariable
+            | OperationCode::ModuloVariableVariable
+            | OperationCode::Power => Self::arithmetic(operation_code, Register(a), b, c),
+            OperationCode::Concatenate => Self::Concatenate {
+                destination: Register(a),
+                operands: (b..=c).map(Register).collect(),
+            },
+            OperationCode::LoadString | OperationCode::LoadCData => Self::LoadConstant {
+                destination: Register(a),
+                source: Constant(d.into()).into(),
+            },
+            OperationCode::LoadShort => Self::LoadConstant {
+                destination: Register(a),
+                source: Operand::Integer(d as i16 as i32),
+            },
+            OperationCode::LoadNumber => Self::LoadConstant {
+                destination: Register(a),
+                source: Number(d.into()).into(),
+            },
+            OperationCode::LoadPrimitive => Self::LoadConstant {
+                destination: Register(a),
+                source: primitive(d).into(),
+            },
+            OperationCode::LoadNil => Self::LoadNil((a as u16..=d).map(register).collect()),
+            OperationCode::GetUpvalue => Self::GetUpvalue {
+                destination: Register(a),
+                upvalue: Upvalue(d),
+            },
+            OperationCode::SetUpvalueVariable
+            | OperationCode::SetUpvalueString
+            | OperationCode::SetUpvalueNumber
+            | OperationCode::SetUpvaluePrimitive => Self::SetUpvalue {
+                destination: Upvalue(a.into()),
+                source: match operation_code {
+                    OperationCode::SetUpvalueVariable => register(d).into(),
+                    OperationCode::SetUpvalueString => Constant(d.into()).into(),
+                    OperationCode::SetUpvalueNumber => Number(d.into()).into(),
+                    _ => primitive(d).into(),
+                },
+            },
+            OperationCode::CloseUpvalues => Self::Close {
+                start: Register(a),
+                skip: instruction.skip(),
+            },
+            OperationCode::NewFunction => Self::Closure {
+                destination: Register(a),
+                function: Constant(d.into()),
+            },
+            // the hash size is stored as a power of two
+            OperationCode::NewTable => Self::NewTable {
+                destination: Register(a),
+                array_size: d & 0x7FF,
+                hash_size: match d >> 11 {
+                    0 => 0,
+                    log => 1 << (log - 1),
+                },
+            },
+            OperationCode::DuplicateTable => Self::DuplicateTable {
+                destination: Register(a),
+                table: Constant(d.into()),
+            },
+            OperationCode::GetGlobal => Self::GetGlobal {
+                destination: Register(a),
+                name: Constant(d.into()),
+            },
+            OperationCode::SetGlobal => Self::SetGlobal {
+                name: Constant(d.into()),
+                value: Register(a),
+            },
+            OperationCode::GetIndexVariable
+            | OperationCode::GetIndexString
+            | OperationCode::GetIndexByte
+            | OperationCode::GetIndexRaw => Self::GetIndex {
+                destination: Register(a),
+                object: Register(b),
+                key: Self::key(operation_code, c),
+            },
+            OperationCode::SetIndexVariable
+            | OperationCode::SetIndexString
+            | OperationCode::SetIndexByte
+            | OperationCode::SetIndexRaw => Self::SetIndex {
+                object: Register(b),
+                key: Self::key(operation_code, c),
+                value: Register(a),
+            },
+            OperationCode::SetIndexMultiple => Self::SetList {
+                table: Register(a - 1),
+                index: Number(d.into()),
+            },
+            OperationCode::Call | OperationCode::CallMultiple => Self::Call {
+                function: Register(a),
+                arguments: Self::arguments(operation_code, first_argument, c),
+                variadic: operation_code == OperationCode::CallMultiple,
+                return_values: b,
+            },
+            OperationCode::TailCall | OperationCode::TailCallMultiple => Self::TailCall {
+                function: Register(a),
+                arguments: Self::arguments(operation_code, first_argument, d as u8),
+                variadic: operation_code == OperationCode::TailCallMultiple,
+            },
+            OperationCode::IteratorCall | OperationCode::IteratorNext => Self::GenericForCall {
+                generator: Register(a - 3),
+                state: Register(a - 2),
+                internal_control: Register(a - 1),
+                vars: (a..a + b - 1).map(Register).collect(),
+            },
+            OperationCode::VarArg => Self::VarArg(Register(a), b),
+            OperationCode::ReturnMultiple => Self::Return {
+                values: (a as u16..a as u16 + d).map(register).collect(),
+                variadic: true,
+            },
+            OperationCode::Return | OperationCode::ReturnNone | OperationCode::ReturnOne => {
+                Self::Return {
+                    values: (a as u16..a as u16 + d - 1).map(register).collect(),
+                    variadic: false,
+                }
+            }
+            OperationCode::ForInit | OperationCode::ForInitJit => Self::InitNumericForLoop {
+                control: [
+                    Register(a),
+                    Register(a + 1),
+                    Register(a + 2),
+                    Register(a + 3),
+                ],
+                skip: instruction.skip() - 1,
+            },
+            OperationCode::ForLoop
+            | OperationCode::ForLoopInterpreter
+            | OperationCode::ForLoopJit => Self::IterateNumericForLoop {
+                control: [
+                    Register(a),
+                    Register(a + 1),
+                    Register(a + 2),
+                    Register(a + 3),
+                ],
+                skip: instruction.skip(),
+            },
+            OperationCode::IteratorLoop
+            | OperationCode::IteratorLoopInterpreter
+            | OperationCode::IteratorLoopJit => Self::IterateGenericForLoop {
+                internal_control: Register(a - 1),
+                external_control: Register(a),
+                skip: instruction.skip(),
+            },
+            OperationCode::Loop | OperationCode::LoopInterpreter | OperationCode::LoopJit => {
+                Self::Loop {
+                    skip: instruction.skip(),
+                }
+            }
+            // the target is checked once the whole function is read
+            OperationCode::Jump | OperationCode::IsNext => Self::Jump {
+                skip: instruction.skip(),
+            },
+            OperationCode::FunctionFixed
+            | OperationCode::FunctionFixedInterpreter
+            | OperationCode::FunctionFixedJit
+            | OperationCode::FunctionVarArg
+            | OperationCode::FunctionVarArgInterpreter
+            | OperationCode::FunctionVarArgJit
+            | OperationCode::FunctionC
+            | OperationCode::FunctionCWrapped => Self::FunctionHeader,
+        }
+    }
+
+    // the instructions start after the function header, which isn't stored
+    pub fn parse_list<'a>(
+        input: &'a [u8],
+        header: &Header,
+        code_length: u32,
+    ) -> IResult<&'a [u8], Vec<Self>> {
+        let (input, code) = count(
+            |input| RawInstruction::parse(input, header),
+            code_length.saturating_sub(1) as usize,
+        )(input)?;
+
+        let mut instructions = std::iter::once(Self::FunctionHeader)
+            .chain(
+                code.iter()
+                    .map(|instruction| Self::from_raw(instruction, header)),
+            )
+            .collect::<Vec<_>>();
+
+        // a generic for loop starts with a jump to its iterator call
+        for (index, instruction) in code.iter().enumerate() {
+            if !matches!(
+                instruction.operation_code,
+                OperationCode::Jump | OperationCode::IsNext
+            ) {
+                continue;
+            }
+            let skip = instruction.skip();
+            let Some(target) = (index + 1).checked_add_signed(skip as isize) else {
+                continue;
+            };
+            let Some(call) = code.get(target) else {
+                continue;
+            };
+            if matches!(
+                call.operation_code,
+                OperationCode::IteratorCall | OperationCode::IteratorNext
+            ) {
+                instructions[index + 1] = Self::InitGenericForLoop {
+                    generator: Register(call.a - 3),
+                    state: Register(call.a - 2),
+                    internal_control: Register(call.a - 1),
+                    skip,
+                };
+            }
+        }
+
+        Ok((input, instructions))
+    }
+
+    fn arguments(operation_code: OperationCode, first_argument: u8, count: u8) -> Vec<Register> {
+        // the fixed arguments of the variadic variants aren't counted with the function
+        let count = match operation_code {
+            OperationCode::CallMultiple | OperationCode::TailCallMultiple => count,
+            _ => count - 1,
+        };
+        (first_argument..first_argument + count)
+            .map(Register)
+            .collect()
+    }
+
+    fn key(operation_code: OperationCode, c: u8) -> Operand {
+        match operation_code {
+            OperationCode::GetIndexString | OperationCode::SetIndexString => {
+                Constant(c.into()).into()
+            }
+            OperationCode::GetIndexByte | OperationCode::SetIndexByte => Operand::Integer(c.into()),
+            _ => Register(c).into(),
+        }
+    }
+
+    fn arithmetic(operation_code: OperationCode, destination: Register, b: u8, c: u8) -> Self {
+        let (lhs, rhs): (Operand, Operand) = match operation_code {
+            OperationCode::AddVariableNumber
+            | OperationCode::SubtractVariableNumber
+            | OperationCode::MultiplyVariableNumber
+            | OperationCode::DivideVariableNumber
+            | OperationCode::ModuloVariableNumber => (Register(b).into(), Number(c.into()).into()),
+            OperationCode::AddNumberVariable
+            | OperationCode::SubtractNumberVariable
+            | OperationCode::MultiplyNumberVariable
+            | OperationCode::DivideNumberVariable
+            | OperationCode::ModuloNumberVariable => (Number(c.into()).into(), Register(b).into()),
+            _ => (Register(b).into(), Register(c).into()),
+        };
+        match operation_code {
+            OperationCode::AddVariableNumber
+            | OperationCode::AddNumberVariable
+            | OperationCode::AddVariableVariable => Self::Add {
+                destination,
+                lhs,
+                rhs,
+            },
+            OperationCode::SubtractVariableNumber
+            | OperationCode::SubtractNumberVariable
+            | OperationCode::SubtractVariableVariable => Self::Sub {
+                destination,
+                lhs,
+                rhs,
+            },
+            OperationCode::MultiplyVariableNumber
+            | OperationCode::MultiplyNumberVariable
+            | OperationCode::MultiplyVariableVariable => Self::Mul {
+                destination,
+                lhs,
+                rhs,
+            },
+            OperationCode::DivideVariableNumber
+            | OperationCode::DivideNumberVariable
+            | OperationCode::DivideVariableVariable => Self::Div {
+                destination,
+                lhs,
+                rhs,
+            },
+            OperationCode::ModuloVariableNumber
+            | OperationCode::ModuloNumberVariable
+            | OperationCode::ModuloVariableVariable => Self::Mod {
+                destination,
+                lhs,
+                rhs,
+            },
+            _ => Self::Pow {
+                destination,
+                lhs,
+                rhs,
+            },
+        }
+    }
+}
@@ -0,0 +1,312 @@
+use crate::chunk::Version;
+
+// the JIT variants (`IFORL`, `JFORL`, ...) are written out as their interpreter
+// variant, but are read as such anyway
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationCode {
+    IsLessThan,
+    IsGreaterThanOrEqual,
+    IsLessThanOrEqual,
+    IsGreaterThan,
+    IsEqualVariable,
+    IsNotEqualVariable,
+    IsEqualString,
+    IsNotEqualString,
+    IsEqualNumber,
+    IsNotEqualNumber,
+    IsEqualPrimitive,
+    IsNotEqualPrimitive,
+    IsTrueCopy,
+    IsFalseCopy,
+    IsTrue,
+    IsFalse,
+    IsType,
+    IsNumber,
+    Move,
+    Not,
+    Minus,
+    Length,
+    AddVariableNumber,
+    SubtractVariableNumber,
+    MultiplyVariableNumber,
+    DivideVariableNumber,
+    ModuloVariableNumber,
+    AddNumberVariable,
+    SubtractNumberVariable,
+    MultiplyNumberVariable,
+    DivideNumberVariable,
+    ModuloNumberVariable,
+    AddVariableVariable,
+    SubtractVariableVariable,
+    MultiplyVariableVariable,
+    DivideVariableVariable,
+    ModuloVariableVariable,
+    Power,
+    Concatenate,
+    LoadString,
+    LoadCData,
+    LoadShort,
+    LoadNumber,
+    LoadPrimitive,
+    LoadNil,
+    GetUpvalue,
+    SetUpvalueVariable,
+    SetUpvalueString,
+    SetUpvalueNumber,
+    SetUpvaluePrimitive,
+    CloseUpvalues,
+    NewFunction,
+    NewTable,
+    DuplicateTable,
+    GetGlobal,
+    SetGlobal,
+    GetIndexVariable,
+    GetIndexString,
+    GetIndexByte,
+    GetIndexRaw,
+    SetIndexVariable,
+    SetIndexString,
+    SetIndexByte,
+    SetIndexMultiple,
+    SetIndexRaw,
+    CallMultiple,
+    Call,
+    TailCallMultiple,
+    TailCall,
+    IteratorCall,
+    IteratorNext,
+    VarArg,
+    IsNext,
+    ReturnMultiple,
+    Return,
+    ReturnNone,
+    ReturnOne,
+    ForInit,
+    ForInitJit,
+    ForLoop,
+    ForLoopInterpreter,
+    ForLoopJit,
+    IteratorLoop,
+    IteratorLoopInterpreter,
+    IteratorLoopJit,
+    Loop,
+    LoopInterpreter,
+    LoopJit,
+    Jump,
+    FunctionFixed,
+    FunctionFixedInterpreter,
+    FunctionFixedJit,
+    FunctionVarArg,
+    FunctionVarArgInterpreter,
+    FunctionVarArgJit,
+    FunctionC,
+    FunctionCWrapped,
+}
+
+// in the order of `lj_bc.h` of LuaJIT 2.0
+const OPERATION_CODES_V1: [OperationCode; 93] = [
+    OperationCode::IsLessThan,
+    OperationCode::IsGreaterThanOrEqual,
+    OperationCode::IsLessThanOrEqual,
+    OperationCode::IsGreaterThan,
+    OperationCode::IsEqualVariable,
+    OperationCode::IsNotEqualVariable,
+    OperationCode::IsEqualString,
+    OperationCode::IsNotEqualString,
+    OperationCode::IsEqualNumber,
+    OperationCode::IsNotEqualNumber,
+    OperationCode::IsEqualPrimitive,
+    OperationCode::IsNotEqualPrimitive,
+    OperationCode::IsTrueCopy,
+    OperationCode::IsFalseCopy,
+    OperationCode::IsTrue,
+    OperationCode::IsFalse,
+    OperationCode::Move,
+    OperationCode::Not,
+    OperationCode::Minus,
+    OperationCode::Length,
+    OperationCode::AddVariableNumber,
+    OperationCode::SubtractVariableNumber,
+    OperationCode::MultiplyVariableNumber,
+    OperationCode::DivideVariableNumber,
+    OperationCode::ModuloVariableNumber,
+    OperationCode::AddNumberVariable,
+    OperationCode::SubtractNumberVariable,
+    OperationCode::MultiplyNumberVariable,
+    OperationCode::DivideNumberVariable,
+    OperationCode::ModuloNumberVariable,
+    OperationCode::AddVariableVariable,
+    OperationCode::SubtractVariableVariable,
+    OperationCode::MultiplyVariableVariable,
+    OperationCode::DivideVariableVariable,
+    OperationCode::ModuloVariableVariable,
+    OperationCode::Power,
+    OperationCode::Concatenate,
+    OperationCode::LoadString,
+    OperationCode::LoadCData,
+    OperationCode::LoadShort,
+    OperationCode::LoadNumber,
+    OperationCode::LoadPrimitive,
+    OperationCode::LoadNil,
+    OperationCode::GetUpvalue,
+    OperationCode::SetUpvalueVariable,
+    OperationCode::SetUpvalueString,
+    OperationCode::SetUpvalueNumber,
+    OperationCode::SetUpvaluePrimitive,
+    OperationCode::CloseUpvalues,
+    OperationCode::NewFunction,
+    OperationCode::NewTable,
+    OperationCode::DuplicateTable,
+    OperationCode::GetGlobal,
+    OperationCode::SetGlobal,
+    OperationCode::GetIndexVariable,
+    OperationCode::GetIndexString,
+    OperationCode::GetIndexByte,
+    OperationCode::SetIndexVariable,
+    OperationCode::SetIndexString,
+    OperationCode::SetIndexByte,
+    OperationCode::SetIndexMultiple,
+    OperationCode::CallMultiple,
+    OperationCode::Call,
+    OperationCode::TailCallMultiple,
+    OperationCode::TailCall,
+    OperationCode::IteratorCall,
+    OperationCode::IteratorNext,
+    OperationCode::VarArg,
+    OperationCode::IsNext,
+    OperationCode::ReturnMultiple,
+    OperationCode::Return,
+    OperationCode::ReturnNone,
+    OperationCode::ReturnOne,
+    OperationCode::ForInit,
+    OperationCode::ForInitJit,
+    OperationCode::ForLoop,
+    OperationCode::ForLoopInterpreter,
+    OperationCode::ForLoopJit,
+    OperationCode::IteratorLoop,
+    OperationCode::IteratorLoopInterpreter,
+    OperationCode::IteratorLoopJit,
+    OperationCode::Loop,
+    OperationCode::LoopInterpreter,
+    OperationCode::LoopJit,
+    OperationCode::Jump,
+    OperationCode::FunctionFixed,
+    OperationCode::FunctionFixedInterpreter,
+    OperationCode::FunctionFixedJit,
+    OperationCode::FunctionVarArg,
+    OperationCode::FunctionVarArgInterpreter,
+    OperationCode::FunctionVarArgJit,
+    OperationCode::FunctionC,
+    OperationCode::FunctionCWrapped,
+];
+
+// in the order of `lj_bc.h` of LuaJIT 2.1
+const OPERATION_CODES_V2: [OperationCode; 97] = [
+    OperationCode::IsLessThan,
+    OperationCode::IsGreaterThanOrEqual,
+    OperationCode::IsLessThanOrEqual,
+    OperationCode::IsGreaterThan,
+    OperationCode::IsEqualVariable,
+    OperationCode::IsNotEqualVariable,
+    OperationCode::IsEqualString,
+    OperationCode::IsNotEqualString,
+    OperationCode::IsEqualNumber,
+    OperationCode::IsNotEqualNumber,
+    OperationCode::IsEqualPrimitive,
+    OperationCode::IsNotEqualPrimitive,
+    OperationCode::IsTrueCopy,
+    OperationCode::IsFalseCopy,
+    OperationCode::IsTrue,
+    OperationCode::IsFalse,
+    OperationCode::IsType,
+    OperationCode::IsNumber,
+    OperationCode::Move,
+    OperationCode::Not,
+    OperationCode::Minus,
+    OperationCode::Length,
+    OperationCode::AddVariableNumber,
+    OperationCode::SubtractVariableNumber,
+    OperationCode::MultiplyVariableNumber,
+    OperationCode::DivideVariableNumber,
+    OperationCode::ModuloVariableNumber,
+    OperationCode::AddNumberVariable,
+    OperationCode::SubtractNumberVariable,
+    OperationCode::MultiplyNumberVariable,
+    OperationCode::DivideNumberVariable,
+    OperationCode::ModuloNumberVariable,
+    OperationCode::AddVariableVariable,
+    OperationCode::SubtractVariableVariable,
+    OperationCode::MultiplyVariableVariable,
+    OperationCode::DivideVariableVariable,
+    OperationCode::ModuloVariableVariable,
+    OperationCode::Power,
+    OperationCode::Concatenate,
+    OperationCode::LoadString,
+    OperationCode::LoadCData,
+    OperationCode::LoadShort,
+    OperationCode::LoadNumber,
+    OperationCode::LoadPrimitive,
+    OperationCode::LoadNil,
+    OperationCode::GetUpvalue,
+    OperationCode::SetUpvalueVariable,
+    OperationCode::SetUpvalueString,
+    OperationCode::SetUpvalueNumber,
+    OperationCode::SetUpvaluePrimitive,
+    OperationCode::CloseUpvalues,
+    OperationCode::NewFunction,
+    OperationCode::NewTable,
+    OperationCode::DuplicateTable,
+    OperationCode::GetGlobal,
+    OperationCode::SetGlobal,
+    OperationCode::GetIndexVariable,
+    OperationCode::GetIndexString,
+    OperationCode::GetIndexByte,
+    OperationCode::GetIndexRaw,
+    OperationCode::SetIndexVariable,
+    OperationCode::SetIndexString,
+    OperationCode::SetIndexByte,
+    OperationCode::SetIndexMultiple,
+    OperationCode::SetIndexRaw,
+    OperationCode::CallMultiple,
+    OperationCode::Call,
+    OperationCode::TailCallMultiple,
+    OperationCode::TailCall,
+    OperationCode::IteratorCall,
+    OperationCode::IteratorNext,
+    OperationCode::VarArg,
+    OperationCode::IsNext,
+    OperationCode::ReturnMultiple,
+    OperationCode::Return,
+    OperationCode::ReturnNone,
+    OperationCode::ReturnOne,
+    OperationCode::ForInit,
+    OperationCode::ForInitJit,
+    OperationCode::ForLoop,
+    OperationCode::ForLoopInterpreter,
+    OperationCode::ForLoopJit,
+    OperationCode::IteratorLoop,
+    OperationCode::IteratorLoopInterpreter,
+    OperationCode::IteratorLoopJit,
+    OperationCode::Loop,
+    OperationCode::LoopInterpreter,
+    OperationCode::LoopJit,
+    OperationCode::Jump,
+    OperationCode::FunctionFixed,
+    OperationCode::FunctionFixedInterpreter,
+    OperationCode::FunctionFixedJit,
+    OperationCode::FunctionVarArg,
+    OperationCode::FunctionVarArgInterpreter,
+    OperationCode::FunctionVarArgJit,
+    OperationCode::FunctionC,
+    OperationCode::FunctionCWrapped,
+];
+
+impl OperationCode {
+    pub fn from_instruction(instruction: u32, version: Version) -> Option<Self> {
+        let operation_codes: &[Self] = match version {
+            Version::V1 => &OPERATION_CODES_V1,
+            Version::V2 => &OPERATION_CODES_V2,
+        };
+        operation_codes.get((instruction & 0xFF) as usize).copied()
+    }
+}
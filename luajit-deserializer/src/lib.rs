@@ -0,0 +1,20 @@
+pub use error::DeserializeError;
+pub use function::Function;
+pub use instruction::{argument, Instruction};
+pub use upvalue::UpvalueDescriptor;
+pub use value::Value;
+
+pub mod chunk;
+pub mod error;
+pub mod function;
+pub mod instruction;
+pub mod local;
+pub mod upvalue;
+pub mod value;
+
+pub fn deserialize(bytecode: &[u8]) -> Result<chunk::Chunk<'_>, DeserializeError> {
+    match chunk::Chunk::parse(bytecode) {
+        Ok((_, chunk)) => Ok(chunk),
+        Err(err) => Err(DeserializeError::from_parse_error(bytecode, err)),
+    }
+}
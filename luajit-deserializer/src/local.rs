@@ -0,0 +1,50 @@
+use std::ops::Range;
+
+use nom::{bytes::complete::take_until, number::complete::le_u8, IResult};
+
+use crate::chunk::header::parse_uleb128;
+
+// the names of the hidden locals of for loops, see `VARNAME_*` in `lj_debug.h`
+const INTERNAL_NAMES: [&[u8]; 6] = [
+    b"(for index)",
+    b"(for limit)",
+    b"(for step)",
+    b"(for generator)",
+    b"(for state)",
+    b"(for control)",
+];
+
+#[derive(Debug)]
+pub struct Local<'a> {
+    pub name: &'a [u8],
+    pub range: Range<u32>,
+}
+
+impl<'a> Local<'a> {
+    // the list ends with a zero byte, each start is relative to the previous one
+    pub fn parse_list(input: &'a [u8]) -> IResult<&'a [u8], Vec<Self>> {
+        let mut locals = Vec::new();
+        let mut input = input;
+        let mut last_start = 0u32;
+        loop {
+            let (rest, kind) = le_u8(input)?;
+            let (rest, name) = match kind {
+                0 => return Ok((rest, locals)),
+                1..=6 => (rest, INTERNAL_NAMES[kind as usize - 1]),
+                // the name is a null terminated string
+                _ => {
+                    let (rest, name) = take_until(&b"\0"[..])(input)?;
+                    (&rest[1..], name)
+                }
+            };
+            let (rest, start) = parse_uleb128(rest)?;
+            let (rest, length) = parse_uleb128(rest)?;
+            input = rest;
+            last_start += start;
+            locals.push(Self {
+                name,
+                range: last_start..last_start + length,
+            });
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use nom::IResult;
+
+use crate::chunk::Header;
+
+// see `PROTO_UV_*` in `lj_obj.h`
+const LOCAL: u16 = 0x8000;
+const IMMUTABLE: u16 = 0x4000;
+
+// where a closure captures an upvalue from when it's created
+#[derive(Debug, Clone, Copy)]
+pub struct UpvalueDescriptor {
+    // a register of the enclosing function, otherwise one of its upvalues
+    pub in_stack: bool,
+    // the local is never written to after it's captured
+    pub immutable: bool,
+    pub index: u16,
+}
+
+impl UpvalueDescriptor {
+    pub fn parse<'a>(input: &'a [u8], header: &Header) -> IResult<&'a [u8], Self> {
+        let (input, value) = header.parse_u16(input)?;
+        let in_stack = value & LOCAL != 0;
+
+        Ok((
+            input,
+            Self {
+                in_stack,
+                immutable: value & IMMUTABLE != 0,
+                index: if in_stack {
+                    value & 0xFF
+                } else {
+                    value & !(LOCAL | IMMUTABLE)
+                },
+            },
+        ))
+    }
+}
@@ -0,0 +1,151 @@
+use enum_as_inner::EnumAsInner;
+use nom::{
+    bytes::complete::take,
+    error::{Error, ErrorKind, ParseError},
+    multi::count,
+    Err, IResult,
+};
+
+use crate::{
+    chunk::header::{parse_uleb128, parse_uleb128_33},
+    function::Function,
+};
+
+// a constant of the GC constant table, see `BCDUMP_KGC_*` in `lj_bcdump.h`
+#[derive(Debug, EnumAsInner)]
+pub enum Value<'a> {
+    // an index into the closures of the function
+    Function(usize),
+    Table(Table<'a>),
+    Int64(i64),
+    UInt64(u64),
+    Complex(f64, f64),
+    String(&'a [u8]),
+}
+
+// a template duplicated by TDUP, see `BCDUMP_KTAB_*`
+#[derive(Debug, Clone, Copy)]
+pub enum TableValue<'a> {
+    Nil,
+    Boolean(bool),
+    Integer(i32),
+    Number(f64),
+    String(&'a [u8]),
+}
+
+#[derive(Debug)]
+pub struct Table<'a> {
+    pub array: Vec<TableValue<'a>>,
+    pub hash: Vec<(TableValue<'a>, TableValue<'a>)>,
+}
+
+impl<'a> Value<'a> {
+    // children are taken off `stack` in the order they're referenced, and
+    // pushed to `closures`
+    pub fn parse(
+        input: &'a [u8],
+        stack: &mut Vec<Function<'a>>,
+        closures: &mut Vec<Function<'a>>,
+    ) -> IResult<&'a [u8], Self> {
+        let (input, kind) = parse_uleb128(input)?;
+
+        match kind {
+            0 => match stack.pop() {
+                Some(function) => {
+                    closures.push(function);
+                    Ok((input, Self::Function(closures.len() - 1)))
+                }
+                None => Err(Err::Failure(Error::from_error_kind(
+                    input,
+                    ErrorKind::Verify,
+                ))),
+            },
+            1 => {
+                let (input, array_length) = parse_uleb128(input)?;
+                let (input, hash_length) = parse_uleb128(input)?;
+                let (input, array) = count(TableValue::parse, array_length as usize)(input)?;
+                let (input, hash) = count(
+                    |input| {
+                        let (input, key) = TableValue::parse(input)?;
+                        let (input, value) = TableValue::parse(input)?;
+                        Ok((input, (key, value)))
+                    },
+                    hash_length as usize,
+                )(input)?;
+
+                Ok((input, Self::Table(Table { array, hash })))
+            }
+            2 => {
+                let (input, value) = parse_u64(input)?;
+
+                Ok((input, Self::Int64(value as i64)))
+            }
+            3 => {
+                let (input, value) = parse_u64(input)?;
+
+                Ok((input, Self::UInt64(value)))
+            }
+            4 => {
+                let (input, real) = parse_u64(input)?;
+                let (input, imaginary) = parse_u64(input)?;
+
+                Ok((
+                    input,
+                    Self::Complex(f64::from_bits(real), f64::from_bits(imaginary)),
+                ))
+            }
+            _ => {
+                let (input, value) = take(kind - 5)(input)?;
+
+                Ok((input, Self::String(value)))
+            }
+        }
+    }
+}
+
+impl<'a> TableValue<'a> {
+    fn parse(input: &'a [u8]) -> IResult<&'a [u8], Self> {
+        let (input, kind) = parse_uleb128(input)?;
+
+        match kind {
+            0 => Ok((input, Self::Nil)),
+            1 => Ok((input, Self::Boolean(false))),
+            2 => Ok((input, Self::Boolean(true))),
+            3 => {
+                let (input, value) = parse_uleb128(input)?;
+
+                Ok((input, Self::Integer(value as i32)))
+            }
+            4 => {
+                let (input, value) = parse_u64(input)?;
+
+                Ok((input, Self::Number(f64::from_bits(value))))
+            }
+            _ => {
+                let (input, value) = take(kind - 5)(input)?;
+
+                Ok((input, Self::String(value)))
+            }
+        }
+    }
+}
+
+// numbers are either an integer or the bits of a double, see `bcread_knum`
+pub fn parse_number(input: &[u8]) -> IResult<&[u8], f64> {
+    let (input, (low, is_number)) = parse_uleb128_33(input)?;
+    if is_number {
+        let (input, high) = parse_uleb128(input)?;
+
+        Ok((input, f64::from_bits((high as u64) << 32 | low as u64)))
+    } else {
+        Ok((input, low as i32 as f64))
+    }
+}
+
+// 64-bit values are stored as their low and high halves
+fn parse_u64(input: &[u8]) -> IResult<&[u8], u64> {
+    let (input, low) = parse_uleb128(input)?;
+    let (input, high) = parse_uleb128(input)?;
+
+    Ok((input, (high as u64) << 32 | low as u64))
+}
@@ -0,0 +1,210 @@
+use luajit_deserializer::{
+    argument::Register, chunk::Version, deserialize, error::DeserializeError, value::TableValue,
+    Instruction, Value,
+};
+use nom::error::ErrorKind;
+
+// LuaJIT 2.1 opcodes
+const GGET: u32 = 54;
+const KSTR: u32 = 39;
+const CALL: u32 = 66;
+const RET0: u32 = 75;
+
+const FLAG_STRIP: u32 = 0x02;
+const FLAG_FR2: u32 = 0x08;
+
+fn uleb128(mut value: u64) -> Vec<u8> {
+    let mut out = Vec::new();
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return out;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn string(string: &[u8]) -> Vec<u8> {
+    [uleb128(5 + string.len() as u64), string.to_vec()].concat()
+}
+
+// 64-bit values are stored as their low and high halves
+fn halves(value: u64) -> Vec<u8> {
+    [uleb128(value & 0xFFFFFFFF), uleb128(value >> 32)].concat()
+}
+
+fn header(version: u8, flags: u32) -> Vec<u8> {
+    let mut out = b"\x1BLJ".to_vec();
+    out.push(version);
+    out.extend(uleb128(flags.into()));
+    if flags & FLAG_STRIP == 0 {
+        out.extend(uleb128(9));
+        out.extend(b"@test.lua");
+    }
+    out
+}
+
+// `print("hi")`, with the given GC constants before the ones it uses, and numbers.
+// `debug_info` is the lines, upvalue names and locals, if not stripped.
+fn function(constants: &[Vec<u8>], numbers: &[Vec<u8>], debug_info: Option<&[u8]>) -> Vec<u8> {
+    let code = [
+        GGET | (constants.len() as u32 + 1) << 16,
+        KSTR | 2 << 8 | (constants.len() as u32) << 16,
+        CALL | 2 << 16 | 1 << 24,
+        RET0 | 1 << 16,
+    ];
+    let mut body = vec![0x02, 0, 3, 0];
+    body.extend(uleb128(constants.len() as u64 + 2));
+    body.extend(uleb128(numbers.len() as u64));
+    body.extend(uleb128(code.len() as u64 + 1));
+    if let Some(debug_info) = debug_info {
+        body.extend(uleb128(debug_info.len() as u64));
+        // the first line and the number of lines
+        body.extend(uleb128(1));
+        body.extend(uleb128(0));
+    }
+    for instruction in code {
+        body.extend(instruction.to_le_bytes());
+    }
+    // the constants are stored in reverse
+    body.extend(string(b"print"));
+    body.extend(string(b"hi"));
+    for constant in constants.iter().rev() {
+        body.extend(constant);
+    }
+    body.extend(numbers.concat());
+    body.extend(debug_info.unwrap_or_default());
+    [uleb128(body.len() as u64), body].concat()
+}
+
+fn chunk(flags: u32, constants: &[Vec<u8>], numbers: &[Vec<u8>]) -> Vec<u8> {
+    let debug_info = (flags & FLAG_STRIP == 0).then_some(&[1, 1, 1, 1, 0][..]);
+    [
+        header(2, flags),
+        function(constants, numbers, debug_info),
+        vec![0],
+    ]
+    .concat()
+}
+
+fn call_arguments(code: &[Instruction]) -> &[Register] {
+    match &code[3] {
+        Instruction::Call { arguments, .. } => arguments,
+        instruction => panic!("expected a call, got {:?}", instruction),
+    }
+}
+
+#[test]
+fn parses_headers() {
+    let chunk = chunk(0, &[], &[]);
+    let chunk = deserialize(&chunk).unwrap();
+    assert_eq!(chunk.header.version, Version::V2);
+    assert!(!chunk.header.stripped && !chunk.header.fr2);
+    assert_eq!(chunk.header.name, b"@test.lua");
+    assert_eq!(chunk.function.name, b"@test.lua");
+    assert_eq!(chunk.function.lines, [1, 2, 2, 2, 2]);
+    assert_eq!(call_arguments(&chunk.function.code), [Register(1)]);
+}
+
+#[test]
+fn parses_stripped_headers() {
+    let chunk = chunk(FLAG_STRIP, &[], &[]);
+    let chunk = deserialize(&chunk).unwrap();
+    assert!(chunk.header.stripped);
+    assert!(chunk.header.name.is_empty());
+    assert!(chunk.function.lines.is_empty() && chunk.function.locals.is_empty());
+}
+
+#[test]
+fn parses_fr2_headers() {
+    // call arguments start at `A + 2` in two-slot frames
+    let chunk = chunk(FLAG_STRIP | FLAG_FR2, &[], &[]);
+    let chunk = deserialize(&chunk).unwrap();
+    assert!(chunk.header.fr2);
+    assert_eq!(call_arguments(&chunk.function.code), [Register(2)]);
+}
+
+#[test]
+fn rejects_unknown_flags() {
+    let bytecode = chunk(0x10, &[], &[]);
+    assert_eq!(
+        deserialize(&bytecode).unwrap_err(),
+        DeserializeError::Malformed {
+            offset: 5,
+            kind: ErrorKind::Verify,
+        }
+    );
+}
+
+#[test]
+fn rejects_unknown_versions() {
+    let mut bytecode = chunk(0, &[], &[]);
+    bytecode[3] = 3;
+    assert_eq!(
+        deserialize(&bytecode).unwrap_err(),
+        DeserializeError::Malformed {
+            offset: 3,
+            kind: ErrorKind::Switch,
+        }
+    );
+}
+
+#[test]
+fn parses_gc_constants() {
+    // `{ false, x = 0.5 }`
+    let table = [
+        uleb128(1),
+        uleb128(1),
+        uleb128(1),
+        uleb128(1),
+        string(b"x"),
+        uleb128(4),
+        halves(0.5f64.to_bits()),
+    ]
+    .concat();
+    let constants = [
+        [uleb128(2), halves(-5i64 as u64)].concat(),
+        [uleb128(3), halves(u64::MAX)].concat(),
+        // 2i
+        [uleb128(4), halves(0f64.to_bits()), halves(2f64.to_bits())].concat(),
+        table,
+    ];
+    let bytecode = chunk(FLAG_STRIP, &constants, &[]);
+    let chunk = deserialize(&bytecode).unwrap();
+    let constants = &chunk.function.constants;
+    assert!(matches!(constants[0], Value::Int64(-5)));
+    assert!(matches!(constants[1], Value::UInt64(u64::MAX)));
+    assert!(
+        matches!(constants[2], Value::Complex(real, imaginary) if real == 0.0 && imaginary == 2.0)
+    );
+    let Value::Table(table) = &constants[3] else {
+        panic!("expected a table, got {:?}", constants[3]);
+    };
+    assert!(matches!(table.array[..], [TableValue::Boolean(false)]));
+    assert!(matches!(
+        table.hash[..],
+        [(TableValue::String(b"x"), TableValue::Number(value))] if value == 0.5
+    ));
+    assert!(matches!(constants[4], Value::String(b"hi")));
+    assert!(matches!(constants[5], Value::String(b"print")));
+}
+
+#[test]
+fn parses_numbers() {
+    // integers have the low bit of the first byte clear, the bits of doubles have it set
+    let double = 0.1f64.to_bits();
+    let numbers = [
+        uleb128(2 << 1),
+        uleb128(((-3i32 as u32) as u64) << 1),
+        [
+            uleb128((double & 0xFFFFFFFF) << 1 | 1),
+            uleb128(double >> 32),
+        ]
+        .concat(),
+    ];
+    let bytecode = chunk(FLAG_STRIP, &[], &numbers);
+    let chunk = deserialize(&bytecode).unwrap();
+    assert_eq!(chunk.function.numbers, [2.0, -3.0, 0.1]);
+}
@@ -0,0 +1,936 @@
+use by_address::ByAddress;
+use cfg::block::{BlockEdge, BranchType};
+
+use itertools::Itertools;
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+
+use ast::{name_locals::name_written_locals, RcLocal, Statement};
+use cfg::function::Function;
+
+use luajit_deserializer::{
+    argument::{Constant, Number, Operand, Primitive, Register, Upvalue},
+    value::TableValue,
+    Function as BytecodeFunction, Instruction, Value,
+};
+
+use petgraph::{stable_graph::NodeIndex, visit::EdgeRef, Direction};
+
+use triomphe::Arc;
+
+// the functions lifted so far, with the upvalues they were lifted with
+type LiftedFunctions = Vec<(Arc<Mutex<ast::Function>>, Function, Vec<RcLocal>)>;
+
+pub struct Lifter<'a, 'b> {
+    bytecode: &'a BytecodeFunction<'a>,
+    nodes: FxHashMap<usize, NodeIndex>,
+    insert_between: FxHashMap<NodeIndex, (NodeIndex, Statement)>,
+    locals: FxHashMap<Register, RcLocal>,
+    function: Function,
+    upvalues: Vec<RcLocal>,
+    // debug names of locals by the pc they're live from and their register
+    local_names: FxHashMap<(usize, Register), String>,
+    lifted_functions: &'b mut LiftedFunctions,
+}
+
+impl<'a, 'b> Lifter<'a, 'b> {
+    // the register of a local isn't recorded, it's the number of locals
+    // that are still live when it's declared
+    fn collect_local_names(&mut self) {
+        let locals = &self.bytecode.locals;
+        for (index, local) in locals.iter().enumerate() {
+            let register = locals[..index]
+                .iter()
+                .filter(|outer| outer.range.contains(&local.range.start))
+                .count();
+            self.local_names.insert(
+                (local.range.start as usize, Register(register as u8)),
+                String::from_utf8_lossy(local.name).into_owned(),
+            );
+        }
+    }
+
+    fn allocate_locals(&mut self) {
+        self.upvalues.reserve(self.bytecode.upvalues.len());
+        for i in 0..self.bytecode.upvalues.len() {
+            let name = self
+                .bytecode
+                .upvalue_names
+                .get(i)
+                .map(|name| String::from_utf8_lossy(name).into_owned());
+            self.upvalues.push(RcLocal::new(ast::Local::new(name)));
+        }
+
+        self.locals.reserve(self.bytecode.frame_size as usize);
+        for i in 0..self.bytecode.frame_size {
+            let local = if i < self.bytecode.number_of_parameters {
+                let name = self.local_names.get(&(0, Register(i))).cloned();
+                RcLocal::new(ast::Local::new(name))
+            } else {
+                RcLocal::default()
+            };
+            if i < self.bytecode.number_of_parameters {
+                self.function.parameters.push(local.clone());
+            }
+            self.locals.insert(Register(i), local);
+        }
+        self.function.is_variadic = self.bytecode.is_variadic;
+    }
+
+    fn jump_target(pc: usize, skip: i32) -> usize {
+        (pc + 1)
+            .checked_add_signed(skip.try_into().unwrap())
+            .unwrap()
+    }
+
+    // TODO: support jumps to invalid destinations
+    fn create_block_map(&mut self) {
+        self.nodes.insert(0, self.function.new_block());
+        for (insn_index, insn) in self.bytecode.code.iter().enumerate() {
+            match *insn {
+                Instruction::Equal { .. }
+                | Instruction::LessThan { .. }
+                | Instruction::LessThanOrEqual { .. }
+                | Instruction::Test { .. }
+                | Instruction::TestSet { .. } => {
+                    self.nodes
+                        .entry(insn_index + 1)
+                        .or_insert_with(|| self.function.new_block());
+                    self.nodes
+                        .entry(insn_index + 2)
+                        .or_insert_with(|| self.function.new_block());
+                }
+                Instruction::Jump { skip }
+                | Instruction::Close { skip, .. }
+                | Instruction::InitGenericForLoop { skip, .. }
+                | Instruction::IterateNumericForLoop { skip, .. }
+                | Instruction::InitNumericForLoop { skip, .. }
+                | Instruction::IterateGenericForLoop { skip, .. } => {
+                    self.nodes
+                        .entry(Self::jump_target(insn_index, skip))
+                        .or_insert_with(|| self.function.new_block());
+                    self.nodes
+                        .entry(insn_index + 1)
+                        .or_insert_with(|| self.function.new_block());
+                }
+                Instruction::Return { .. } | Instruction::TailCall { .. } => {
+                    self.nodes
+                        .entry(insn_index + 1)
+                        .or_insert_with(|| self.function.new_block());
+                }
+                _ => {}
+            }
+        }
+        // a jump past the last instruction can't happen, but a block for it can
+        // be made by the instruction before it
+        self.nodes
+            .retain(|&index, _| index < self.bytecode.code.len());
+    }
+
+    fn code_ranges(&self) -> Vec<(usize, usize)> {
+        let mut nodes = self.nodes.keys().cloned().collect::<Vec<_>>();
+        nodes.sort_unstable();
+        let ends = nodes
+            .iter()
+            .skip(1)
+            .map(|&s| s - 1)
+            .chain(std::iter::once(self.bytecode.code.len() - 1));
+        nodes.iter().cloned().zip(ends).collect()
+    }
+
+    fn constant(&self, constant: Constant) -> ast::RValue {
+        match &self.bytecode.constants[constant.0 as usize] {
+            Value::String(value) => ast::Literal::String(value.to_vec()).into(),
+            &Value::Int64(value) => ast::Literal::Int64(value).into(),
+            &Value::UInt64(value) => ast::Literal::UInt64(value).into(),
+            &Value::Complex(real, imaginary) => {
+                let imaginary = ast::Literal::Imaginary(imaginary).into();
+                if real == 0.0 {
+                    imaginary
+                } else {
+                    ast::Binary::new(
+                        ast::Literal::Number(real).into(),
+                        imaginary,
+                        ast::BinaryOperation::Add,
+                    )
+                    .into()
+                }
+            }
+            Value::Table(_) | Value::Function(_) => unreachable!(),
+        }
+    }
+
+    fn number(&self, number: Number) -> ast::Literal {
+        ast::Literal::Number(self.bytecode.numbers[number.0 as usize])
+    }
+
+    fn operand(&self, value: Operand) -> ast::RValue {
+        match value {
+            Operand::Register(register) => self.locals[&register].clone().into(),
+            Operand::Constant(constant) => self.constant(constant),
+            Operand::Number(number) => self.number(number).into(),
+            Operand::Integer(value) => ast::Literal::Number(value.into()).into(),
+            Operand::Primitive(Primitive::Nil) => ast::Literal::Nil.into(),
+            Operand::Primitive(Primitive::False) => ast::Literal::Boolean(false).into(),
+            Operand::Primitive(Primitive::True) => ast::Literal::Boolean(true).into(),
+        }
+    }
+
+    fn table_value(value: TableValue) -> ast::RValue {
+        match value {
+            TableValue::Nil => ast::Literal::Nil,
+            TableValue::Boolean(value) => ast::Literal::Boolean(value),
+            TableValue::Integer(value) => ast::Literal::Number(value.into()),
+            TableValue::Number(value) => ast::Literal::Number(value),
+            TableValue::String(value) => ast::Literal::String(value.to_vec()),
+        }
+        .into()
+    }
+
+    // the template of TDUP. keys with a nil value are filled in by the
+    // instructions that follow, and slot 0 of the array part is `[0]`
+    fn table_template(&self, constant: Constant) -> ast::Table {
+        let table = self.bytecode.constants[constant.0 as usize]
+            .as_table()
+            .unwrap();
+        let array_length = table
+            .array
+            .iter()
+            .rposition(|value| !matches!(value, TableValue::Nil))
+            .map_or(0, |index| index + 1);
+        let zero = table.array.first().and_then(|&value| {
+            (!matches!(value, TableValue::Nil)).then(|| {
+                (
+                    Some(ast::Literal::Number(0.0).into()),
+                    Self::table_value(value),
+                )
+            })
+        });
+        let array = table
+            .array
+            .iter()
+            .take(array_length)
+            .skip(1)
+            .map(|&value| (None, Self::table_value(value)));
+        let hash = table
+            .hash
+            .iter()
+            .filter(|(_, value)| !matches!(value, TableValue::Nil))
+            .map(|&(key, value)| (Some(Self::table_value(key)), Self::table_value(value)));
+        ast::Table(array.chain(zero).chain(hash).collect())
+    }
+
+    fn close(&self, start: Register) -> ast::Close {
+        let locals = (start.0..self.bytecode.frame_size)
+            .map(|i| self.locals[&Register(i)].clone())
+            .collect();
+        ast::Close { locals }
+    }
+
+    fn arguments(
+        &self,
+        arguments: &[Register],
+        variadic: bool,
+        top: &mut Option<(ast::RValue, u8)>,
+    ) -> Vec<ast::RValue> {
+        let mut values = arguments
+            .iter()
+            .map(|r| self.locals[r].clone().into())
+            .collect::<Vec<_>>();
+        if variadic {
+            let (tail, end) = top.take().unwrap();
+            let start = arguments.last().map_or(end, |r| r.0 + 1);
+            values.extend((start..end).map(|r| self.locals[&Register(r)].clone().into()));
+            values.push(tail);
+        }
+        values
+    }
+
+    // TODO: rename to one of: lift_instructions, lift_range, lift_instruction_range, lift_block?
+    fn lift_instruction(&mut self, start: usize, end: usize, statements: &mut Vec<Statement>) {
+        if end > start {
+            statements.reserve(end - start + 1);
+        }
+        let mut top: Option<(ast::RValue, u8)> = None;
+        // the results of ITERC, taken by the ITERL that follows it
+        let mut generic_for_call = None;
+        let mut iter = self.bytecode.code[start..=end].iter();
+        while let Some(instruction) = iter.next() {
+            let first_statement = statements.len();
+            match instruction {
+                Instruction::Move {
+                    destination,
+                    source,
+                } => {
+                    statements.push(
+                        ast::Assign::new(
+                            vec![self.locals[destination].clone().into()],
+                            vec![self.locals[source].clone().into()],
+                        )
+                        .into(),
+                    );
+                }
+                &Instruction::LoadConstant {
+                    destination,
+                    source,
+                } => {
+                    statements.push(
+                        ast::Assign::new(
+                            vec![self.locals[&destination].clone().into()],
+                            vec![self.operand(source)],
+                        )
+                        .into(),
+                    );
+                }
+                Instruction::LoadNil(registers) => {
+                    for register in registers {
+                        statements.push(
+                            ast::Assign::new(
+                                vec![self.locals[register].clone().into()],
+                                vec![ast::Literal::Nil.into()],
+                            )
+                            .into(),
+                        );
+                    }
+                }
+                Instruction::GetGlobal { destination, name } => {
+                    let name = self.bytecode.constants[name.0 as usize]
+                        .as_string()
+                        .unwrap()
+                        .to_vec();
+                    statements.push(
+                        ast::Assign::new(
+                            vec![self.locals[destination].clone().into()],
+                            vec![ast::Global::new(name).into()],
+                        )
+                        .into(),
+                    );
+                }
+                Instruction::SetGlobal { name, value } => {
+                    let name = self.bytecode.constants[name.0 as usize]
+                        .as_string()
+                        .unwrap()
+                        .to_vec();
+                    statements.push(
+                        ast::Assign::new(
+                            vec![ast::Global::new(name).into()],
+                            vec![self.locals[value].clone().into()],
+                        )
+                        .into(),
+                    );
+                }
+                &Instruction::GetIndex {
+                    destination,
+                    object,
+                    key,
+                } => {
+                    statements.push(
+                        ast::Assign::new(
+                            vec![self.locals[&destination].clone().into()],
+                            vec![ast::Index::new(
+                                self.locals[&object].clone().into(),
+                                self.operand(key),
+                            )
+                            .into()],
+                        )
+                        .into(),
+                    );
+                }
+                &Instruction::SetIndex { object, key, value } => {
+                    statements.push(
+                        ast::Assign::new(
+                            vec![ast::Index::new(
+                                self.locals[&object].clone().into(),
+                                self.operand(key),
+                            )
+                            .into()],
+                            vec![self.locals[&value].clone().into()],
+                        )
+                        .into(),
+                    );
+                }
+                &Instruction::Test { value, invert } => {
+                    let value = self.locals[&value].clone().into();
+                    let condition = if invert {
+                        ast::Unary::new(value, ast::UnaryOperation::Not).into()
+                    } else {
+                        value
+                    };
+                    statements.push(
+                        ast::If::new(condition, ast::Block::default(), ast::Block::default())
+                            .into(),
+                    )
+                }
+                Instruction::Not {
+                    destination,
+                    operand,
+                }
+                | Instruction::Minus {
+                    destination,
+                    operand,
+                }
+                | Instruction::Length {
+                    destination,
+                    operand,
+                } => {
+                    statements.push(
+                        ast::Assign::new(
+                            vec![self.locals[destination].clone().into()],
+                            vec![ast::Unary::new(
+                                self.locals[operand].clone().into(),
+                                match instruction {
+                                    Instruction::Not { .. } => ast::UnaryOperation::Not,
+                                    Instruction::Minus { .. } => ast::UnaryOperation::Negate,
+                                    Instruction::Length { .. } => ast::UnaryOperation::Length,
+                                    _ => unreachable!(),
+                                },
+                            )
+                            .into()],
+                        )
+                        .into(),
+                    );
+                }
+                Instruction::Return { values, variadic } => {
+                    let values = self.arguments(values, *variadic, &mut top);
+                    statements.push(ast::Return::new(values).into());
+                }
+                &Instruction::Close { start, .. } => {
+                    statements.push(self.close(start).into());
+                }
+                Instruction::FunctionHeader
+                | Instruction::TypeCheck
+                | Instruction::Jump { .. }
+                | Instruction::Loop { .. } => {}
+                &Instruction::Add {
+                    destination,
+                    lhs,
+                    rhs,
+                }
+                | &Instruction::Sub {
+                    destination,
+                    lhs,
+                    rhs,
+                }
+                | &Instruction::Mul {
+                    destination,
+                    lhs,
+                    rhs,
+                }
+                | &Instruction::Div {
+                    destination,
+                    lhs,
+                    rhs,
+                }
+                | &Instruction::Mod {
+                    destination,
+                    lhs,
+                    rhs,
+                }
+                | &Instruction::Pow {
+                    destination,
+                    lhs,
+                    rhs,
+                } => {
+                    statements.push(
+                        ast::Assign::new(
+                            vec![self.locals[&destination].clone().into()],
+                            vec![ast::Binary::new(
+                                self.operand(lhs),
+                                self.operand(rhs),
+                                match instruction {
+                                    Instruction::Add { .. } => ast::BinaryOperation::Add,
+                                    Instruction::Sub { .. } => ast::BinaryOperation::Sub,
+                                    Instruction::Mul { .. } => ast::BinaryOperation::Mul,
+                                    Instruction::Div { .. } => ast::BinaryOperation::Div,
+                                    Instruction::Mod { .. } => ast::BinaryOperation::Mod,
+                                    Instruction::Pow { .. } => ast::BinaryOperation::Pow,
+                                    _ => unreachable!(),
+                                },
+                            )
+                            .into()],
+                        )
+                        .into(),
+                    );
+                }
+                Instruction::Concatenate {
+                    destination,
+                    operands,
+                } => {
+                    assert!(operands.len() >= 2);
+                    let mut operands = operands.iter().rev();
+
+                    let right = operands.next().unwrap();
+                    let left = operands.next().unwrap();
+                    let mut concat = ast::Binary::new(
+                        self.locals[left].clone().into(),
+                        self.locals[right].clone().into(),
+                        ast::BinaryOperation::Concat,
+                    );
+                    for r in operands {
+                        concat = ast::Binary::new(
+                            self.locals[r].clone().into(),
+                            concat.into(),
+                            ast::BinaryOperation::Concat,
+                        );
+                    }
+                    statements.push(
+                        ast::Assign::new(
+                            vec![self.locals[destination].clone().into()],
+                            vec![concat.into()],
+                        )
+                        .into(),
+                    );
+                }
+                &Instruction::LessThan { lhs, rhs, invert }
+                | &Instruction::LessThanOrEqual { lhs, rhs, invert }
+                | &Instruction::Equal { lhs, rhs, invert } => {
+                    let value = ast::Binary::new(
+                        self.operand(lhs),
+                        self.operand(rhs),
+                        match instruction {
+                            Instruction::LessThan { .. } => ast::BinaryOperation::LessThan,
+                            Instruction::LessThanOrEqual { .. } => {
+                                ast::BinaryOperation::LessThanOrEqual
+                            }
+                            Instruction::Equal { .. } => ast::BinaryOperation::Equal,
+                            _ => unreachable!(),
+                        },
+                    )
+                    .into();
+                    let condition = if invert {
+                        ast::Unary::new(value, ast::UnaryOperation::Not).into()
+                    } else {
+                        value
+                    };
+                    statements.push(
+                        ast::If::new(condition, ast::Block::default(), ast::Block::default())
+                            .into(),
+                    )
+                }
+                Instruction::TestSet {
+                    destination,
+                    value,
+                    invert,
+                } => {
+                    let value: ast::RValue = self.locals[value].clone().into();
+                    statements.push(
+                        ast::If::new(
+                            if *invert {
+                                ast::Unary::new(value.clone(), ast::UnaryOperation::Not).into()
+                            } else {
+                                value.clone()
+                            },
+                            ast::Block::default(),
+                            ast::Block::default(),
+                        )
+                        .into(),
+                    );
+
+                    let assign = ast::Assign::new(
+                        vec![self.locals[destination].clone().into()],
+                        vec![value],
+                    );
+
+                    self.function
+                        .block_mut(self.nodes[&(end + 1)])
+                        .unwrap()
+                        .push(assign.into());
+                }
+                Instruction::TailCall {
+                    function,
+                    arguments,
+                    variadic,
+                } => {
+                    let arguments = self.arguments(arguments, *variadic, &mut top);
+                    let call = ast::Call::new(self.locals[function].clone().into(), arguments);
+                    statements.push(ast::Return::new(vec![call.into()]).into());
+                }
+                Instruction::Call {
+                    function,
+                    arguments,
+                    variadic,
+                    return_values,
+                } => {
+                    let arguments = self.arguments(arguments, *variadic, &mut top);
+                    let call = ast::Call::new(self.locals[function].clone().into(), arguments);
+
+                    match return_values {
+                        0 => top = Some((call.into(), function.0)),
+                        1 => statements.push(call.into()),
+                        _ => {
+                            statements.push(
+                                ast::Assign::new(
+                                    (function.0..function.0 + return_values - 1)
+                                        .map(|r| self.locals[&Register(r)].clone().into())
+                                        .collect_vec(),
+                                    vec![ast::RValue::Select(call.into())],
+                                )
+                                .into(),
+                            );
+                        }
+                    }
+                }
+                Instruction::GetUpvalue {
+                    destination,
+                    upvalue,
+                } => {
+                    statements.push(
+                        ast::Assign::new(
+                            vec![self.locals[destination].clone().into()],
+                            vec![self.upvalue(upvalue).into()],
+                        )
+                        .into(),
+                    );
+                }
+                &Instruction::SetUpvalue {
+                    ref destination,
+                    source,
+                } => {
+                    statements.push(
+                        ast::Assign::new(
+                            vec![self.upvalue(destination).into()],
+                            vec![self.operand(source)],
+                        )
+                        .into(),
+                    );
+                }
+                &Instruction::VarArg(destination, b) => {
+                    let vararg = ast::VarArg {};
+                    if b != 0 {
+                        statements.push(
+                            ast::Assign::new(
+                                (destination.0..destination.0 + b - 1)
+                                    .map(|r| self.locals[&Register(r)].clone().into())
+                                    .collect(),
+                                vec![ast::RValue::Select(vararg.into())],
+                            )
+                            .into(),
+                        );
+                    } else {
+                        top = Some((vararg.into(), destination.0));
+                    }
+                }
+                Instruction::Closure {
+                    destination,
+                    function,
+                } => {
+                    let closure = &self.bytecode.closures[*self.bytecode.constants
+                        [function.0 as usize]
+                        .as_function()
+                        .unwrap()];
+
+                    // a local that's never written to again is captured by value
+                    let upvalues_passed = closure
+                        .upvalues
+                        .iter()
+                        .map(|upvalue| {
+                            if upvalue.in_stack {
+                                let local = self.locals[&Register(upvalue.index as u8)].clone();
+                                if upvalue.immutable {
+                                    ast::Upvalue::Copy(local)
+                                } else {
+                                    ast::Upvalue::Ref(local)
+                                }
+                            } else {
+                                ast::Upvalue::Ref(self.upvalues[upvalue.index as usize].clone())
+                            }
+                        })
+                        .collect();
+
+                    let ast_function = Arc::<Mutex<_>>::default();
+
+                    let (function, upvalues) = Lifter::lift(closure, self.lifted_functions);
+                    self.lifted_functions
+                        .push((ast_function.clone(), function, upvalues));
+
+                    statements.push(
+                        ast::Assign::new(
+                            vec![self.locals[destination].clone().into()],
+                            vec![ast::Closure {
+                                function: ByAddress(ast_function),
+                                upvalues: upvalues_passed,
+                            }
+                            .into()],
+                        )
+                        .into(),
+                    );
+                }
+                Instruction::NewTable { destination, .. } => {
+                    statements.push(
+                        ast::Assign::new(
+                            vec![self.locals[destination].clone().into()],
+                            vec![ast::Table::default().into()],
+                        )
+                        .into(),
+                    );
+                }
+                &Instruction::DuplicateTable { destination, table } => {
+                    statements.push(
+                        ast::Assign::new(
+                            vec![self.locals[&destination].clone().into()],
+                            vec![self.table_template(table).into()],
+                        )
+                        .into(),
+                    );
+                }
+                &Instruction::SetList { table, index } => {
+                    let index = self.bytecode.numbers[index.0 as usize].to_bits() as u32;
+                    let (tail, end) = top.take().unwrap();
+                    statements.push(
+                        ast::SetList::new(
+                            self.locals[&table].clone(),
+                            index as usize,
+                            (table.0 + 1..end)
+                                .map(|r| self.locals[&Register(r)].clone().into())
+                                .collect(),
+                            Some(tail),
+                        )
+                        .into(),
+                    );
+                }
+                Instruction::InitNumericForLoop { control, .. } => {
+                    let (internal_counter, limit, step) = (
+                        self.locals[&control[0]].clone(),
+                        self.locals[&control[1]].clone(),
+                        self.locals[&control[2]].clone(),
+                    );
+                    statements.push(ast::NumForInit::new(internal_counter, limit, step).into());
+                }
+                &Instruction::IterateNumericForLoop { ref control, skip } => {
+                    let (internal_counter, limit, step, external_counter) = (
+                        self.locals[&control[0]].clone(),
+                        self.locals[&control[1]].clone(),
+                        self.locals[&control[2]].clone(),
+                        self.locals[&control[3]].clone(),
+                    );
+                    statements.push(
+                        ast::NumForNext::new(internal_counter.clone(), limit.into(), step.into())
+                            .into(),
+                    );
+
+                    let body_node = self.nodes[&Self::jump_target(end, skip)];
+                    assert!(self
+                        .insert_between
+                        .insert(
+                            self.nodes[&start],
+                            (
+                                body_node,
+                                ast::Assign::new(
+                                    vec![external_counter.into()],
+                                    vec![internal_counter.into()],
+                                )
+                                .into()
+                            )
+                        )
+                        .is_none());
+                }
+                &Instruction::InitGenericForLoop {
+                    generator,
+                    state,
+                    internal_control,
+                    ..
+                } => {
+                    statements.push(
+                        ast::GenericForInit::new(
+                            self.locals[&generator].clone(),
+                            self.locals[&state].clone(),
+                            self.locals[&internal_control].clone(),
+                        )
+                        .into(),
+                    );
+                }
+                // lifted with the ITERL that follows, which checks the first result
+                Instruction::GenericForCall {
+                    generator,
+                    state,
+                    vars,
+                    ..
+                } => {
+                    generic_for_call = Some((*generator, *state, vars));
+                }
+                Instruction::IterateGenericForLoop { .. } => {
+                    let (generator, state, vars) = generic_for_call.take().unwrap();
+                    statements.push(
+                        ast::GenericForNext::new(
+                            vars.iter().map(|r| self.locals[r].clone()).collect(),
+                            self.locals[&generator].clone().into(),
+                            self.locals[&state].clone(),
+                        )
+                        .into(),
+                    );
+                }
+            }
+
+            let next_pc = end + 1 - iter.len();
+            self.name_written_locals(statements, first_statement, next_pc);
+
+            if matches!(
+                instruction,
+                Instruction::Return { .. } | Instruction::TailCall { .. }
+            ) {
+                break;
+            }
+        }
+    }
+
+    fn upvalue(&self, upvalue: &Upvalue) -> RcLocal {
+        self.upvalues[upvalue.0 as usize].clone()
+    }
+
+    // debug info names a local from the pc right after the instructions that initialize it
+    fn name_written_locals(
+        &self,
+        statements: &mut Vec<Statement>,
+        first_statement: usize,
+        next_pc: usize,
+    ) {
+        if self.local_names.is_empty() {
+            return;
+        }
+
+        name_written_locals(statements, first_statement, |local| {
+            let (&register, _) = self
+                .locals
+                .iter()
+                .find(|(_, register_local)| *register_local == local)?;
+            let name = self.local_names.get(&(next_pc, register))?;
+            Some(RcLocal::new(ast::Local::new(Some(name.clone()))))
+        });
+    }
+
+    fn lift_blocks(&mut self) {
+        let ranges = self.code_ranges();
+        for (start, end) in ranges {
+            // the body of a for loop can be lifted after the loop instruction
+            // inserts into it, see IterateNumericForLoop and TestSet
+            let mut statements =
+                std::mem::take(self.function.block_mut(self.nodes[&start]).unwrap());
+            self.lift_instruction(start, end, &mut statements);
+            *self.function.block_mut(self.nodes[&start]).unwrap() = statements;
+
+            match self.bytecode.code[end] {
+                Instruction::Equal { .. }
+                | Instruction::LessThan { .. }
+                | Instruction::LessThanOrEqual { .. }
+                | Instruction::Test { .. }
+                | Instruction::TestSet { .. } => {
+                    self.function.set_edges(
+                        self.nodes[&start],
+                        vec![
+                            (self.nodes[&(end + 1)], BlockEdge::new(BranchType::Then)),
+                            (self.nodes[&(end + 2)], BlockEdge::new(BranchType::Else)),
+                        ],
+                    );
+                }
+                Instruction::IterateNumericForLoop { skip, .. }
+                | Instruction::IterateGenericForLoop { skip, .. } => {
+                    self.function.set_edges(
+                        self.nodes[&start],
+                        vec![
+                            (
+                                self.nodes[&Self::jump_target(end, skip)],
+                                BlockEdge::new(BranchType::Then),
+                            ),
+                            (self.nodes[&(end + 1)], BlockEdge::new(BranchType::Else)),
+                        ],
+                    );
+                }
+                Instruction::Jump { skip }
+                | Instruction::Close { skip, .. }
+                | Instruction::InitGenericForLoop { skip, .. }
+                | Instruction::InitNumericForLoop { skip, .. } => {
+                    self.function.set_edges(
+                        self.nodes[&start],
+                        vec![(
+                            self.nodes[&Self::jump_target(end, skip)],
+                            BlockEdge::new(BranchType::Unconditional),
+                        )],
+                    );
+                }
+                Instruction::Return { .. } | Instruction::TailCall { .. } => {}
+                _ => {
+                    if end + 1 != self.bytecode.code.len() {
+                        self.function.set_edges(
+                            self.nodes[&start],
+                            vec![(
+                                self.nodes[&(end + 1)],
+                                BlockEdge::new(BranchType::Unconditional),
+                            )],
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn lift(
+        bytecode: &'a BytecodeFunction,
+        lifted_functions: &'b mut LiftedFunctions,
+    ) -> (Function, Vec<RcLocal>) {
+        let mut context = Self {
+            bytecode,
+            nodes: FxHashMap::default(),
+            insert_between: FxHashMap::default(),
+            locals: FxHashMap::default(),
+            function: Function::new(0),
+            upvalues: Vec::new(),
+            local_names: FxHashMap::default(),
+            lifted_functions,
+        };
+
+        context.create_block_map();
+        context.collect_local_names();
+        context.allocate_locals();
+        context.lift_blocks();
+
+        let stack_init_node = context.function.new_block();
+        let stack_init_block = context.function.block_mut(stack_init_node).unwrap();
+        stack_init_block.reserve(context.locals.len());
+        for (_, local) in context.locals {
+            if !context.function.parameters.contains(&local) {
+                let stack_init_block = context.function.block_mut(stack_init_node).unwrap();
+                stack_init_block.push(
+                    ast::Assign::new(vec![local.into()], vec![ast::Literal::Nil.into()]).into(),
+                )
+            }
+        }
+        context.function.set_edges(
+            stack_init_node,
+            vec![(context.nodes[&0], BlockEdge::new(BranchType::Unconditional))],
+        );
+        context.function.set_entry(stack_init_node);
+
+        for (node, (successor, stat)) in context.insert_between {
+            if context.function.predecessor_blocks(successor).count() == 1 {
+                context
+                    .function
+                    .block_mut(successor)
+                    .unwrap()
+                    .insert(0, stat);
+            } else {
+                let between_node = context.function.new_block();
+                context.function.block_mut(between_node).unwrap().push(stat);
+                context.function.set_edges(
+                    between_node,
+                    vec![(successor, BlockEdge::new(BranchType::Unconditional))],
+                );
+                for edge in context
+                    .function
+                    .graph()
+                    .edges_directed(node, Direction::Outgoing)
+                    .filter(|e| e.target() == successor)
+                    .map(|e| e.id())
+                    .collect::<Vec<_>>()
+                {
+                    let edge = context.function.graph_mut().remove_edge(edge).unwrap();
+                    context
+                        .function
+                        .graph_mut()
+                        .add_edge(node, between_node, edge);
+                }
+            }
+        }
+
+        (context.function, context.upvalues)
+    }
+}
@@ -0,0 +1,104 @@
+use luajit_lifter::decompile_bytecode;
+
+// LuaJIT 2.1 opcodes
+const KSTR: u32 = 39;
+const KCDATA: u32 = 40;
+const GGET: u32 = 54;
+const GSET: u32 = 55;
+const CALL: u32 = 66;
+const RET0: u32 = 75;
+
+const FLAG_STRIP: u32 = 0x02;
+const FLAG_FFI: u32 = 0x04;
+const FLAG_FR2: u32 = 0x08;
+
+fn ad(op_code: u32, a: u32, d: u32) -> u32 {
+    op_code | a << 8 | d << 16
+}
+
+fn uleb128(mut value: u64) -> Vec<u8> {
+    let mut out = Vec::new();
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return out;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn string(string: &[u8]) -> Vec<u8> {
+    [uleb128(5 + string.len() as u64), string.to_vec()].concat()
+}
+
+// 64-bit values are stored as their low and high halves
+fn halves(value: u64) -> Vec<u8> {
+    [uleb128(value & 0xFFFFFFFF), uleb128(value >> 32)].concat()
+}
+
+// a stripped chunk of one function, `constants` are in the order `D` indexes them
+fn chunk(flags: u32, frame_size: u8, code: &[u32], constants: &[Vec<u8>]) -> Vec<u8> {
+    let mut body = vec![0x02, 0, frame_size, 0];
+    body.extend(uleb128(constants.len() as u64));
+    body.extend(uleb128(0));
+    body.extend(uleb128(code.len() as u64 + 1));
+    for &instruction in code {
+        body.extend(instruction.to_le_bytes());
+    }
+    for constant in constants.iter().rev() {
+        body.extend(constant);
+    }
+    let mut out = b"\x1BLJ\x02".to_vec();
+    out.extend(uleb128((FLAG_STRIP | flags).into()));
+    out.extend(uleb128(body.len() as u64));
+    out.extend(body);
+    out.push(0);
+    out
+}
+
+#[test]
+fn lifts_cdata_constants() {
+    let code = [
+        ad(KCDATA, 0, 0),
+        ad(GSET, 0, 4),
+        ad(KCDATA, 0, 1),
+        ad(GSET, 0, 5),
+        ad(KCDATA, 0, 2),
+        ad(GSET, 0, 6),
+        ad(KCDATA, 0, 3),
+        ad(GSET, 0, 7),
+        ad(RET0, 0, 1),
+    ];
+    let constants = [
+        [uleb128(2), halves(-5i64 as u64)].concat(),
+        [uleb128(3), halves(u64::MAX)].concat(),
+        [uleb128(4), halves(0f64.to_bits()), halves(2f64.to_bits())].concat(),
+        [uleb128(4), halves(1f64.to_bits()), halves(0.5f64.to_bits())].concat(),
+        string(b"a"),
+        string(b"b"),
+        string(b"c"),
+        string(b"d"),
+    ];
+    assert_eq!(
+        decompile_bytecode(&chunk(FLAG_FFI, 1, &code, &constants)).unwrap(),
+        "a = -5LL\nb = 18446744073709551615ULL\nc = 2i\nd = 1 + 0.5i"
+    );
+}
+
+#[test]
+fn lifts_fr2_calls() {
+    // the arguments start at `A + 2`, `A + 1` is the frame link
+    let code = [
+        ad(GGET, 0, 0),
+        ad(KSTR, 2, 1),
+        CALL | 2 << 16 | 1 << 24,
+        ad(RET0, 0, 1),
+    ];
+    let constants = [string(b"print"), string(b"hi")];
+    assert_eq!(
+        decompile_bytecode(&chunk(FLAG_FR2, 3, &code, &constants)).unwrap(),
+        "print(\"hi\")"
+    );
+}
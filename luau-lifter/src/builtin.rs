@@ -0,0 +1,136 @@
+pub(crate) const BUILTIN_VECTOR: u8 = 54;
+
+// indexed by LuauBuiltinFunction, 0 is LBF_NONE
+const BUILTINS: [&str; 89] = [
+    "",
+    "assert",
+    "math.abs",
+    "math.acos",
+    "math.asin",
+    "math.atan2",
+    "math.atan",
+    "math.ceil",
+    "math.cosh",
+    "math.cos",
+    "math.deg",
+    "math.exp",
+    "math.floor",
+    "math.fmod",
+    "math.frexp",
+    "math.ldexp",
+    "math.log10",
+    "math.log",
+    "math.max",
+    "math.min",
+    "math.modf",
+    "math.pow",
+    "math.rad",
+    "math.sinh",
+    "math.sin",
+    "math.sqrt",
+    "math.tanh",
+    "math.tan",
+    "bit32.arshift",
+    "bit32.band",
+    "bit32.bnot",
+    "bit32.bor",
+    "bit32.bxor",
+    "bit32.btest",
+    "bit32.extract",
+    "bit32.lrotate",
+    "bit32.lshift",
+    "bit32.replace",
+    "bit32.rrotate",
+    "bit32.rshift",
+    "type",
+    "string.byte",
+    "string.char",
+    "string.len",
+    "typeof",
+    "string.sub",
+    "math.clamp",
+    "math.sign",
+    "math.round",
+    "rawset",
+    "rawget",
+    "rawequal",
+    "table.insert",
+    "table.unpack",
+    // depends on the host, see `VectorConstructor`
+    "Vector3.new",
+    "bit32.countlz",
+    "bit32.countrz",
+    "select",
+    "rawlen",
+    // bit32.extract with a constant field and width
+    "bit32.extract",
+    "getmetatable",
+    "setmetatable",
+    "tonumber",
+    "tostring",
+    "bit32.byteswap",
+    "buffer.readi8",
+    "buffer.readu8",
+    "buffer.writeu8",
+    "buffer.readi16",
+    "buffer.readu16",
+    "buffer.writeu16",
+    "buffer.readi32",
+    "buffer.readu32",
+    "buffer.writeu32",
+    "buffer.readf32",
+    "buffer.writef32",
+    "buffer.readf64",
+    "buffer.writef64",
+    "vector.magnitude",
+    "vector.normalize",
+    "vector.cross",
+    "vector.dot",
+    "vector.floor",
+    "vector.ceil",
+    "vector.abs",
+    "vector.sign",
+    "vector.clamp",
+    "vector.min",
+    "vector.max",
+];
+
+// the path of the builtin, e.g. `math.floor`
+pub(crate) fn builtin(id: u8) -> Option<&'static str> {
+    BUILTINS
+        .get(id as usize)
+        .copied()
+        .filter(|path| !path.is_empty())
+}
+
+pub(crate) fn builtin_expression(path: &str) -> ast::RValue {
+    let mut components = path.split('.');
+    let mut expression = ast::Global::new(components.next().unwrap().into()).into();
+    for component in components {
+        expression = ast::Index::new(
+            expression,
+            ast::Literal::String(component.as_bytes().to_vec()).into(),
+        )
+        .into();
+    }
+    expression
+}
+
+// the name of a local holding the result, e.g. `floor`. `None` if the name of the builtin
+// would shadow a global and its result has no better name.
+pub(crate) fn builtin_result_name(path: &str) -> Option<&str> {
+    match path {
+        "tostring" => Some("str"),
+        "tonumber" => Some("num"),
+        "rawlen" => Some("len"),
+        "rawget" => Some("value"),
+        "rawequal" => Some("equal"),
+        "getmetatable" => Some("metatable"),
+        "table.unpack" => None,
+        _ => match path.rsplit_once('.') {
+            Some((_, "new" | "create")) => Some("vector"),
+            Some((_, name)) => Some(name),
+            None => None,
+        },
+    }
+}
@@ -1,6 +1,14 @@
-use nom::{bytes::complete::take, number::complete::le_u8, IResult};
+use nom::{bytes::complete::take, number::complete::le_u8};
 
-use super::chunk::Chunk;
+use crate::op_code_map::OpCodeMap;
+
+use super::{
+    chunk::Chunk,
+    error::{Error, ParseResult, Reason},
+};
+
+pub(crate) const VERSION_MIN: u8 = 3;
+pub(crate) const VERSION_MAX: u8 = 7;
 
 #[derive(Debug)]
 pub enum Bytecode {
@@ -9,7 +17,8 @@ pub enum Bytecode {
 }
 
 impl Bytecode {
-    pub fn parse(input: &[u8], encode_key: u8) -> IResult<&[u8], Bytecode> {
+    pub(crate) fn parse<'a>(input: &'a [u8], op_code_map: &OpCodeMap) -> ParseResult<'a, Bytecode> {
+        let start = input;
         let (input, status_code) = le_u8(input)?;
         match status_code {
             0 => {
@@ -19,11 +28,14 @@ impl Bytecode {
                     Bytecode::Error(String::from_utf8_lossy(error_msg).to_string()),
                 ))
             }
-            4..=6 => {
-                let (input, chunk) = Chunk::parse(input, encode_key, status_code)?;
+            VERSION_MIN..=VERSION_MAX => {
+                let (input, chunk) = Chunk::parse(input, op_code_map, status_code)?;
                 Ok((input, Bytecode::Chunk(chunk)))
             }
-            _ => panic!("Unsupported bytecode version: {}", status_code),
+            _ => Err(Error::failure(
+                start,
+                Reason::UnsupportedVersion(status_code),
+            )),
         }
     }
 }
@@ -1,40 +1,90 @@
-use super::{function::Function, list::parse_list, parse_string};
-use nom::character::complete::char;
-use nom::multi::many_till;
-use nom::number::complete::le_u8;
-use nom::IResult;
+use super::{
+    error::{Error, ParseResult, Reason},
+    function::Function,
+    list::parse_list,
+    parse_string,
+};
+use crate::op_code_map::OpCodeMap;
+use nom::{error::ErrorKind, number::complete::le_u8};
 use nom_leb128::leb128_usize;
+use rustc_hash::FxHashMap;
 
 #[derive(Debug)]
 pub struct Chunk {
+    pub version: u8,
+    pub types_version: u8,
     pub string_table: Vec<Vec<u8>>,
+    // tagged userdata type index -> string table index, 1-based
+    pub userdata_types: FxHashMap<u8, usize>,
     pub functions: Vec<Function>,
     pub main: usize,
 }
 
 impl Chunk {
-    pub(crate) fn parse(input: &[u8], encode_key: u8, version: u8) -> IResult<&[u8], Self> {
+    pub(crate) fn parse<'a>(
+        input: &'a [u8],
+        op_code_map: &OpCodeMap,
+        version: u8,
+    ) -> ParseResult<'a, Self> {
+        let start = input;
         let (input, types_version) = if version >= 4 {
             le_u8(input)?
         } else {
             (input, 0)
         };
         if types_version > 3 {
-            panic!("unsupported types version");
+            return Err(Error::failure(
+                start,
+                Reason::UnsupportedTypesVersion(types_version),
+            ));
         }
         let (input, string_table) = parse_list(input, parse_string)?;
-        let input = if types_version == 3 {
-            many_till(leb128_usize, char('\0'))(input)?.0
-        } else {
-            input
-        };
-        let (input, functions) = parse_list(input, |i| Function::parse(i, encode_key))?;
+        let mut userdata_types = FxHashMap::default();
+        let mut input = input;
+        if types_version == 3 {
+            loop {
+                let index;
+                (input, index) = le_u8(input)?;
+                if index == 0 {
+                    break;
+                }
+                let name;
+                (input, name) = leb128_usize(input)?;
+                userdata_types.insert(index - 1, name);
+            }
+        }
+        let (mut input, function_count) = leb128_usize(input)?;
+        let mut functions = Vec::new();
+        for function_id in 0..function_count {
+            let function;
+            (input, function) = Function::parse(input, op_code_map, version, types_version)
+                .map_err(|e| e.map(|e| e.in_function(function_id)))?;
+            functions.push(function);
+        }
+        let main_start = input;
         let (input, main) = leb128_usize(input)?;
+        // the lifter indexes the function list with these
+        if main >= functions.len() {
+            return Err(Error::failure(main_start, Reason::Nom(ErrorKind::Verify)));
+        }
+        if let Some(function_id) = functions.iter().position(|function| {
+            function
+                .functions
+                .iter()
+                .any(|&child| child >= functions.len())
+        }) {
+            return Err(nom::Err::Failure(
+                Error::new(main_start, Reason::Nom(ErrorKind::Verify)).in_function(function_id),
+            ));
+        }
 
         Ok((
             input,
             Self {
+                version,
+                types_version,
                 string_table,
+                userdata_types,
                 functions,
                 main,
             },
@@ -1,18 +1,22 @@
-use super::list::parse_list;
+use super::{
+    error::{Error, ParseResult, Reason},
+    list::parse_list,
+};
 use nom::{
-    number::complete::{le_f32, le_f64, le_u32, le_u8},
-    IResult,
+    number::complete::{le_f32, le_f64, le_i32, le_u32, le_u8},
+    sequence::pair,
 };
 use nom_leb128::leb128_usize;
 
-const CONSTANT_NIL: u8 = 0;
-const CONSTANT_BOOLEAN: u8 = 1;
-const CONSTANT_NUMBER: u8 = 2;
-const CONSTANT_STRING: u8 = 3;
-const CONSTANT_IMPORT: u8 = 4;
-const CONSTANT_TABLE: u8 = 5;
-const CONSTANT_CLOSURE: u8 = 6;
-const CONSTANT_VECTOR: u8 = 7;
+pub(crate) const CONSTANT_NIL: u8 = 0;
+pub(crate) const CONSTANT_BOOLEAN: u8 = 1;
+pub(crate) const CONSTANT_NUMBER: u8 = 2;
+pub(crate) const CONSTANT_STRING: u8 = 3;
+pub(crate) const CONSTANT_IMPORT: u8 = 4;
+pub(crate) const CONSTANT_TABLE: u8 = 5;
+pub(crate) const CONSTANT_CLOSURE: u8 = 6;
+pub(crate) const CONSTANT_VECTOR: u8 = 7;
+pub(crate) const CONSTANT_TABLE_WITH_CONSTANTS: u8 = 8;
 
 #[derive(Debug)]
 pub enum Constant {
@@ -22,12 +26,15 @@ pub enum Constant {
     String(usize),
     Import(usize),
     Table(Vec<usize>),
+    // keys paired with the constant the field is initialized to, if any
+    TableWithConstants(Vec<(usize, Option<usize>)>),
     Closure(usize),
     Vector(f32, f32, f32, f32),
 }
 
 impl Constant {
-    pub(crate) fn parse(input: &[u8]) -> IResult<&[u8], Self> {
+    pub(crate) fn parse(input: &[u8], version: u8) -> ParseResult<'_, Self> {
+        let start = input;
         let (input, tag) = le_u8(input)?;
         match tag {
             CONSTANT_NIL => Ok((input, Constant::Nil)),
@@ -51,6 +58,14 @@ impl Constant {
                 let (input, keys) = parse_list(input, leb128_usize)?;
                 Ok((input, Constant::Table(keys)))
             }
+            CONSTANT_TABLE_WITH_CONSTANTS if version >= 7 => {
+                let (input, fields) = parse_list(input, |input| pair(leb128_usize, le_i32)(input))?;
+                let fields = fields
+                    .into_iter()
+                    .map(|(key, value)| (key, usize::try_from(value).ok()))
+                    .collect();
+                Ok((input, Constant::TableWithConstants(fields)))
+            }
             CONSTANT_CLOSURE => {
                 let (input, f_id) = leb128_usize(input)?;
                 Ok((input, Constant::Closure(f_id)))
@@ -62,7 +77,7 @@ impl Constant {
                 let (input, w) = le_f32(input)?;
                 Ok((input, Constant::Vector(x, y, z, w)))
             }
-            _ => panic!("{}", tag),
+            _ => Err(Error::failure(start, Reason::UnknownConstantTag(tag))),
         }
     }
 }
@@ -0,0 +1,184 @@
+use std::fmt;
+
+use nom::error::{ErrorKind, ParseError};
+
+use super::bytecode;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeserializeError {
+    UnsupportedVersion {
+        offset: usize,
+        version: u8,
+    },
+    UnsupportedTypesVersion {
+        offset: usize,
+        version: u8,
+    },
+    UnknownConstantTag {
+        offset: usize,
+        function: Option<usize>,
+        tag: u8,
+    },
+    InvalidOpCode {
+        offset: usize,
+        function: Option<usize>,
+        pc: usize,
+        // the raw opcode, before it went through the opcode map
+        op_code: u8,
+    },
+    MissingAux {
+        offset: usize,
+        function: Option<usize>,
+        pc: usize,
+    },
+    UnexpectedEof {
+        offset: usize,
+        function: Option<usize>,
+    },
+    Malformed {
+        offset: usize,
+        function: Option<usize>,
+        kind: ErrorKind,
+    },
+}
+
+impl DeserializeError {
+    pub fn offset(&self) -> usize {
+        match *self {
+            Self::UnsupportedVersion { offset, .. }
+            | Self::UnsupportedTypesVersion { offset, .. }
+            | Self::UnknownConstantTag { offset, .. }
+            | Self::InvalidOpCode { offset, .. }
+            | Self::MissingAux { offset, .. }
+            | Self::UnexpectedEof { offset, .. }
+            | Self::Malformed { offset, .. } => offset,
+        }
+    }
+
+    pub fn function(&self) -> Option<usize> {
+        match *self {
+            Self::UnsupportedVersion { .. } | Self::UnsupportedTypesVersion { .. } => None,
+            Self::UnknownConstantTag { function, .. }
+            | Self::InvalidOpCode { function, .. }
+            | Self::MissingAux { function, .. }
+            | Self::UnexpectedEof { function, .. }
+            | Self::Malformed { function, .. } => function,
+        }
+    }
+
+    pub(crate) fn from_parse_error(input: &[u8], error: Error) -> Self {
+        let offset = input.len() - error.input_len;
+        let function = error.function;
+        match error.reason {
+            Reason::UnsupportedVersion(version) => Self::UnsupportedVersion { offset, version },
+            Reason::UnsupportedTypesVersion(version) => {
+                Self::UnsupportedTypesVersion { offset, version }
+            }
+            Reason::UnknownConstantTag(tag) => Self::UnknownConstantTag {
+                offset,
+                function,
+                tag,
+            },
+            Reason::InvalidOpCode { pc, op_code } => Self::InvalidOpCode {
+                offset,
+                function,
+                pc,
+                op_code,
+            },
+            Reason::MissingAux { pc } => Self::MissingAux {
+                offset,
+                function,
+                pc,
+            },
+            Reason::Nom(ErrorKind::Eof) => Self::UnexpectedEof { offset, function },
+            Reason::Nom(kind) => Self::Malformed {
+                offset,
+                function,
+                kind,
+            },
+        }
+    }
+}
+
+impl fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnsupportedVersion { version, .. } => write!(
+                f,
+                "unsupported bytecode version: {} (expected {} to {})",
+                version,
+                bytecode::VERSION_MIN,
+                bytecode::VERSION_MAX
+            )?,
+            Self::UnsupportedTypesVersion { version, .. } => {
+                write!(f, "unsupported types version: {}", version)?
+            }
+            Self::UnknownConstantTag { tag, .. } => write!(f, "unknown constant tag: {}", tag)?,
+            Self::InvalidOpCode { pc, op_code, .. } => {
+                write!(f, "invalid opcode {} at pc {}", op_code, pc)?
+            }
+            Self::MissingAux { pc, .. } => {
+                write!(f, "missing aux word for instruction at pc {}", pc)?
+            }
+            Self::UnexpectedEof { .. } => write!(f, "unexpected end of input")?,
+            Self::Malformed { kind, .. } => write!(f, "malformed input ({:?})", kind)?,
+        }
+        write!(f, " at offset {:#x}", self.offset())?;
+        if let Some(function) = self.function() {
+            write!(f, " in function {}", function)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for DeserializeError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Reason {
+    UnsupportedVersion(u8),
+    UnsupportedTypesVersion(u8),
+    UnknownConstantTag(u8),
+    InvalidOpCode { pc: usize, op_code: u8 },
+    MissingAux { pc: usize },
+    Nom(ErrorKind),
+}
+
+// we only keep the length of the remaining input so that the offset
+// can be recovered once we are back at the start of the bytecode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Error {
+    input_len: usize,
+    function: Option<usize>,
+    reason: Reason,
+}
+
+impl Error {
+    pub(crate) fn new(input: &[u8], reason: Reason) -> Self {
+        Self {
+            input_len: input.len(),
+            function: None,
+            reason,
+        }
+    }
+
+    pub(crate) fn failure(input: &[u8], reason: Reason) -> nom::Err<Self> {
+        nom::Err::Failure(Self::new(input, reason))
+    }
+
+    pub(crate) fn in_function(mut self, function: usize) -> Self {
+        self.function.get_or_insert(function);
+        self
+    }
+}
+
+impl ParseError<&[u8]> for Error {
+    fn from_error_kind(input: &[u8], kind: ErrorKind) -> Self {
+        Self::new(input, Reason::Nom(kind))
+    }
+
+    fn append(_: &[u8], _: ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+pub(crate) type ParseResult<'a, T> = nom::IResult<&'a [u8], T, Error>;
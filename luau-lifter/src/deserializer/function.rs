@@ -3,16 +3,25 @@ use core::num;
 use nom::{
     complete::take,
     number::complete::{le_u32, le_u8},
-    IResult,
 };
 use nom_leb128::leb128_usize;
 
 use super::{
     constant::Constant,
+    error::{Error, ParseResult, Reason},
     list::{parse_list, parse_list_len},
+    local::Local,
+    type_info::TypeInfo,
 };
 
-use crate::{instruction::*, op_code::OpCode};
+use crate::{instruction::*, op_code::OpCode, op_code_map::OpCodeMap};
+
+// the whole module was compiled with `--!native`
+pub const FUNCTION_FLAG_NATIVE_MODULE: u8 = 1 << 0;
+// native codegen decided the function isn't worth compiling
+pub const FUNCTION_FLAG_NATIVE_COLD: u8 = 1 << 1;
+// the function has the `@native` attribute
+pub const FUNCTION_FLAG_NATIVE_FUNCTION: u8 = 1 << 2;
 
 #[derive(Debug)]
 pub struct Function {
@@ -20,7 +29,9 @@ pub struct Function {
     pub num_parameters: u8,
     pub num_upvalues: u8,
     pub is_vararg: bool,
-    //pub instructions: Vec<u32>,
+    pub flags: u8,
+    // the instruction words as they appear in the bytecode
+    pub code: Vec<u32>,
     pub instructions: Vec<Instruction>,
     pub constants: Vec<Constant>,
     pub functions: Vec<usize>,
@@ -29,47 +40,70 @@ pub struct Function {
     pub line_gap_log2: Option<u8>,
     pub line_info_delta: Option<Vec<u8>>,
     pub abs_line_info_delta: Option<Vec<u32>>,
+    pub type_info: TypeInfo,
+    pub locals: Vec<Local>,
+    // indices into the string table, 1-based
+    pub upvalue_names: Vec<usize>,
 }
 
 impl Function {
-    fn parse_instructions(vec: &Vec<u32>, encode_key: u8) -> Vec<Instruction> {
+    pub fn is_native_module(&self) -> bool {
+        self.flags & FUNCTION_FLAG_NATIVE_MODULE != 0
+    }
+
+    pub fn is_native_function(&self) -> bool {
+        self.flags & FUNCTION_FLAG_NATIVE_FUNCTION != 0
+    }
+
+    // the line each instruction was compiled from, if the chunk has line info
+    pub fn lines(&self) -> Option<Vec<usize>> {
+        let line_gap_log2 = self.line_gap_log2?;
+        let line_info_delta = self.line_info_delta.as_ref()?;
+        let abs_line_info_delta = self.abs_line_info_delta.as_ref()?;
+
+        let mut last_line = 0u32;
+        let abs_line_info = abs_line_info_delta
+            .iter()
+            .map(|&delta| {
+                last_line = last_line.wrapping_add(delta);
+                last_line
+            })
+            .collect::<Vec<_>>();
+
+        let mut last_offset = 0u8;
+        line_info_delta
+            .iter()
+            .enumerate()
+            .map(|(pc, &delta)| {
+                last_offset = last_offset.wrapping_add(delta);
+                let interval = pc.checked_shr(line_gap_log2.into()).unwrap_or(0);
+                let &line = abs_line_info.get(interval)?;
+                Some(line.wrapping_add(last_offset.into()) as usize)
+            })
+            .collect()
+    }
+
+    // `input` must point at the first instruction word so that errors can be located
+    fn parse_instructions(
+        input: &[u8],
+        vec: &[u32],
+        op_code_map: &OpCodeMap,
+    ) -> Result<Vec<Instruction>, nom::Err<Error>> {
         let mut v: Vec<Instruction> = Vec::new();
         let mut pc = 0;
 
-        loop {
-            let ins = Instruction::parse(vec[pc], encode_key).unwrap();
-            let op = match ins {
-                Instruction::BC { op_code, .. } => op_code,
-                Instruction::AD { op_code, .. } => op_code,
-                Instruction::E { op_code, .. } => op_code,
-            };
+        while pc < vec.len() {
+            let ins = Instruction::parse(vec[pc], op_code_map).map_err(|op_code| {
+                Error::failure(&input[pc * 4..], Reason::InvalidOpCode { pc, op_code })
+            })?;
+            let op = ins.op_code();
 
             // handle ops with aux values
             match op {
-                OpCode::LOP_GETGLOBAL
-                | OpCode::LOP_SETGLOBAL
-                | OpCode::LOP_GETIMPORT
-                | OpCode::LOP_GETTABLEKS
-                | OpCode::LOP_SETTABLEKS
-                | OpCode::LOP_NAMECALL
-                | OpCode::LOP_JUMPIFEQ
-                | OpCode::LOP_JUMPIFLE
-                | OpCode::LOP_JUMPIFLT
-                | OpCode::LOP_JUMPIFNOTEQ
-                | OpCode::LOP_JUMPIFNOTLE
-                | OpCode::LOP_JUMPIFNOTLT
-                | OpCode::LOP_NEWTABLE
-                | OpCode::LOP_SETLIST
-                | OpCode::LOP_FORGLOOP
-                | OpCode::LOP_LOADKX
-                | OpCode::LOP_FASTCALL2
-                | OpCode::LOP_FASTCALL2K
-                | OpCode::LOP_FASTCALL3
-                | OpCode::LOP_JUMPXEQKNIL
-                | OpCode::LOP_JUMPXEQKB
-                | OpCode::LOP_JUMPXEQKN
-                | OpCode::LOP_JUMPXEQKS => {
-                    let aux = vec[pc + 1];
+                op if op.has_aux() => {
+                    let &aux = vec.get(pc + 1).ok_or_else(|| {
+                        Error::failure(&input[pc * 4..], Reason::MissingAux { pc })
+                    })?;
                     pc += 2;
                     match ins {
                         Instruction::BC {
@@ -101,28 +135,36 @@ impl Function {
                     pc += 1;
                 }
             }
-
-            if pc == vec.len() {
-                break;
-            }
         }
 
-        v
+        Ok(v)
     }
 
-    pub(crate) fn parse(input: &[u8], encode_key: u8) -> IResult<&[u8], Self> {
+    pub(crate) fn parse<'a>(
+        input: &'a [u8],
+        op_code_map: &OpCodeMap,
+        version: u8,
+        types_version: u8,
+    ) -> ParseResult<'a, Self> {
         let (input, max_stack_size) = le_u8(input)?;
         let (input, num_parameters) = le_u8(input)?;
         let (input, num_upvalues) = le_u8(input)?;
         let (input, is_vararg) = le_u8(input)?;
 
-        let (input, flags) = le_u8(input)?;
-        let (input, _) = parse_list(input, le_u8)?;
+        // version 3 has neither flags nor type info
+        let (input, flags, type_info) = if version >= 4 {
+            let (input, flags) = le_u8(input)?;
+            let (input, type_info) = TypeInfo::parse(input, types_version)?;
+            (input, flags, type_info)
+        } else {
+            (input, 0, TypeInfo::default())
+        };
 
-        let (input, u32_instructions) = parse_list(input, le_u32)?;
+        let (code, code_length) = leb128_usize(input)?;
+        let (input, u32_instructions) = parse_list_len(code, le_u32, code_length)?;
         //let (input, instructions) = parse_list(input, Function::parse_instrution)?;
-        let instructions = Self::parse_instructions(&u32_instructions, encode_key);
-        let (input, constants) = parse_list(input, Constant::parse)?;
+        let instructions = Self::parse_instructions(code, &u32_instructions, op_code_map)?;
+        let (input, constants) = parse_list(input, |input| Constant::parse(input, version))?;
         let (input, functions) = parse_list(input, leb128_usize)?;
         let (input, line_defined) = leb128_usize(input)?;
         let (input, function_name) = leb128_usize(input)?;
@@ -144,31 +186,26 @@ impl Function {
         };
         let (input, abs_line_info_delta) = match has_line_info {
             0 => (input, None),
+            _ if u32_instructions.is_empty() => (input, Some(Vec::new())),
             _ => {
                 let (input, abs_line_info_delta) = parse_list_len(
                     input,
                     le_u32,
-                    ((u32_instructions.len() - 1) >> line_gap_log2.unwrap()) + 1,
+                    (u32_instructions.len() - 1)
+                        .checked_shr(line_gap_log2.unwrap().into())
+                        .unwrap_or(0)
+                        + 1,
                 )?;
                 (input, Some(abs_line_info_delta))
             }
         };
-        let input = match le_u8(input)? {
-            (input, 0) => input,
-            (input, _) => {
-                panic!("we have debug info");
-                let (mut input, num_locvars) = leb128_usize(input)?;
-                for _ in 0..num_locvars {
-                    (input, _) = leb128_usize(input)?;
-                    (input, _) = leb128_usize(input)?;
-                    (input, _) = leb128_usize(input)?;
-                    (input, _) = le_u8(input)?;
-                }
-                let (mut input, num_upvalues) = leb128_usize(input)?;
-                for _ in 0..num_upvalues {
-                    (input, _) = leb128_usize(input)?;
-                }
-                input
+        let (input, has_debug_info) = le_u8(input)?;
+        let (input, locals, upvalue_names) = match has_debug_info {
+            0 => (input, Vec::new(), Vec::new()),
+            _ => {
+                let (input, locals) = parse_list(input, Local::parse)?;
+                let (input, upvalue_names) = parse_list(input, leb128_usize)?;
+                (input, locals, upvalue_names)
             }
         };
         Ok((
@@ -178,6 +215,8 @@ impl Function {
                 num_parameters,
                 num_upvalues,
                 is_vararg: is_vararg != 0u8,
+                flags,
+                code: u32_instructions,
                 instructions,
                 constants,
                 functions,
@@ -186,6 +225,9 @@ impl Function {
                 line_gap_log2,
                 line_info_delta,
                 abs_line_info_delta,
+                type_info,
+                locals,
+                upvalue_names,
             },
         ))
     }
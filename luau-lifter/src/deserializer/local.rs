@@ -0,0 +1,32 @@
+use std::ops::Range;
+
+use nom::number::complete::le_u8;
+use nom_leb128::leb128_usize;
+
+use super::error::ParseResult;
+
+#[derive(Debug)]
+pub struct Local {
+    // index into the string table, 1-based
+    pub name: usize,
+    // the pcs in which the register holds this local
+    pub range: Range<usize>,
+    pub register: u8,
+}
+
+impl Local {
+    pub(crate) fn parse(input: &[u8]) -> ParseResult<'_, Self> {
+        let (input, name) = leb128_usize(input)?;
+        let (input, start_pc) = leb128_usize(input)?;
+        let (input, end_pc) = leb128_usize(input)?;
+        let (input, register) = le_u8(input)?;
+        Ok((
+            input,
+            Self {
+                name,
+                range: start_pc..end_pc,
+                register,
+            },
+        ))
+    }
+}
@@ -1,22 +1,45 @@
-use nom::{bytes::complete::take, IResult};
+use nom::bytes::complete::take;
 use nom_leb128::leb128_usize;
 
 pub mod bytecode;
 pub mod chunk;
 pub mod constant;
+pub mod error;
 pub mod function;
 mod list;
+pub mod local;
+pub mod type_info;
 
-fn parse_string(input: &[u8]) -> IResult<&[u8], Vec<u8>> {
+use error::{DeserializeError, ParseResult};
+
+use crate::op_code_map::OpCodeMap;
+
+fn parse_string(input: &[u8]) -> ParseResult<'_, Vec<u8>> {
     let (input, length) = leb128_usize(input)?;
     let (input, bytes) = take(length)(input)?;
     Ok((input, bytes.to_owned()))
 }
 
-pub fn deserialize(bytecode: &[u8], encode_key: u8) -> Result<bytecode::Bytecode, String> {
-    match bytecode::Bytecode::parse(bytecode, encode_key) {
+pub fn deserialize(
+    bytecode: &[u8],
+    encode_key: u8,
+) -> Result<bytecode::Bytecode, DeserializeError> {
+    deserialize_with_op_code_map(bytecode, &OpCodeMap::from_key(encode_key))
+}
+
+pub fn deserialize_with_op_code_map(
+    bytecode: &[u8],
+    op_code_map: &OpCodeMap,
+) -> Result<bytecode::Bytecode, DeserializeError> {
+    match bytecode::Bytecode::parse(bytecode, op_code_map) {
         Ok((_, deserialized_bytecode)) => Ok(deserialized_bytecode),
-        Err(err) => Err(err.to_string()),
+        Err(nom::Err::Error(err) | nom::Err::Failure(err)) => {
+            Err(DeserializeError::from_parse_error(bytecode, err))
+        }
+        Err(nom::Err::Incomplete(_)) => Err(DeserializeError::UnexpectedEof {
+            offset: bytecode.len(),
+            function: None,
+        }),
     }
 }
 
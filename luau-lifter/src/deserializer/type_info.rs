@@ -0,0 +1,171 @@
+use std::ops::Range;
+
+use nom::{bytes::complete::take, number::complete::le_u8};
+use nom_leb128::leb128_usize;
+
+use super::{error::ParseResult, list::parse_list_len};
+
+const TYPE_NIL: u8 = 0;
+const TYPE_BOOLEAN: u8 = 1;
+const TYPE_NUMBER: u8 = 2;
+const TYPE_STRING: u8 = 3;
+const TYPE_TABLE: u8 = 4;
+pub(crate) const TYPE_FUNCTION: u8 = 5;
+const TYPE_THREAD: u8 = 6;
+const TYPE_USERDATA: u8 = 7;
+const TYPE_VECTOR: u8 = 8;
+const TYPE_BUFFER: u8 = 9;
+const TYPE_ANY: u8 = 15;
+const TYPE_TAGGED_USERDATA_BASE: u8 = 64;
+const TYPE_TAGGED_USERDATA_END: u8 = 64 + 32;
+const TYPE_OPTIONAL_BIT: u8 = 1 << 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeTag {
+    Nil,
+    Boolean,
+    Number,
+    String,
+    Table,
+    Function,
+    Thread,
+    Userdata,
+    Vector,
+    Buffer,
+    Any,
+    // index into the userdata type names of the chunk
+    TaggedUserdata(u8),
+    Unknown(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Type {
+    pub tag: TypeTag,
+    pub optional: bool,
+}
+
+impl Type {
+    fn parse(input: &[u8]) -> ParseResult<'_, Self> {
+        let (input, r#type) = le_u8(input)?;
+        let tag = match r#type & !TYPE_OPTIONAL_BIT {
+            TYPE_NIL => TypeTag::Nil,
+            TYPE_BOOLEAN => TypeTag::Boolean,
+            TYPE_NUMBER => TypeTag::Number,
+            TYPE_STRING => TypeTag::String,
+            TYPE_TABLE => TypeTag::Table,
+            TYPE_FUNCTION => TypeTag::Function,
+            TYPE_THREAD => TypeTag::Thread,
+            TYPE_USERDATA => TypeTag::Userdata,
+            TYPE_VECTOR => TypeTag::Vector,
+            TYPE_BUFFER => TypeTag::Buffer,
+            TYPE_ANY => TypeTag::Any,
+            tag @ TYPE_TAGGED_USERDATA_BASE..TYPE_TAGGED_USERDATA_END => {
+                TypeTag::TaggedUserdata(tag - TYPE_TAGGED_USERDATA_BASE)
+            }
+            tag => TypeTag::Unknown(tag),
+        };
+        Ok((
+            input,
+            Self {
+                tag,
+                optional: r#type & TYPE_OPTIONAL_BIT != 0,
+            },
+        ))
+    }
+
+    pub(crate) fn encode(self) -> u8 {
+        let tag = match self.tag {
+            TypeTag::Nil => TYPE_NIL,
+            TypeTag::Boolean => TYPE_BOOLEAN,
+            TypeTag::Number => TYPE_NUMBER,
+            TypeTag::String => TYPE_STRING,
+            TypeTag::Table => TYPE_TABLE,
+            TypeTag::Function => TYPE_FUNCTION,
+            TypeTag::Thread => TYPE_THREAD,
+            TypeTag::Userdata => TYPE_USERDATA,
+            TypeTag::Vector => TYPE_VECTOR,
+            TypeTag::Buffer => TYPE_BUFFER,
+            TypeTag::Any => TYPE_ANY,
+            TypeTag::TaggedUserdata(index) => TYPE_TAGGED_USERDATA_BASE + index,
+            TypeTag::Unknown(tag) => tag,
+        };
+        if self.optional {
+            tag | TYPE_OPTIONAL_BIT
+        } else {
+            tag
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct TypedLocal {
+    pub r#type: Type,
+    pub register: u8,
+    pub range: Range<usize>,
+}
+
+impl TypedLocal {
+    fn parse(input: &[u8]) -> ParseResult<'_, Self> {
+        let (input, r#type) = Type::parse(input)?;
+        let (input, register) = le_u8(input)?;
+        let (input, start_pc) = leb128_usize(input)?;
+        let (input, length) = leb128_usize(input)?;
+        Ok((
+            input,
+            Self {
+                r#type,
+                register,
+                range: start_pc..start_pc + length,
+            },
+        ))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct TypeInfo {
+    // empty if the compiler didn't record a signature
+    pub parameters: Vec<Type>,
+    pub upvalues: Vec<Type>,
+    pub locals: Vec<TypedLocal>,
+}
+
+impl TypeInfo {
+    pub(crate) fn parse(input: &[u8], types_version: u8) -> ParseResult<'_, Self> {
+        let (input, size) = leb128_usize(input)?;
+        // the size is authoritative, so we skip whatever we don't understand.
+        // we still parse from `input` so that errors have the right offset.
+        let (rest, _) = take(size)(input)?;
+        let mut type_info = Self::default();
+        if size == 0 {
+            return Ok((rest, type_info));
+        }
+
+        match types_version {
+            1 => {
+                (_, type_info.parameters) = Self::parse_signature(input)?;
+            }
+            2 | 3 => {
+                let (input, signature_size) = leb128_usize(input)?;
+                let (input, upvalue_count) = leb128_usize(input)?;
+                let (input, local_count) = leb128_usize(input)?;
+                if signature_size != 0 {
+                    (_, type_info.parameters) = Self::parse_signature(input)?;
+                }
+                let (input, _) = take(signature_size)(input)?;
+                let (input, upvalues) = parse_list_len(input, Type::parse, upvalue_count)?;
+                let (_, locals) = parse_list_len(input, TypedLocal::parse, local_count)?;
+                type_info.upvalues = upvalues;
+                type_info.locals = locals;
+            }
+            _ => {}
+        }
+        Ok((rest, type_info))
+    }
+
+    // LBC_TYPE_FUNCTION, parameter count, parameter types
+    fn parse_signature(input: &[u8]) -> ParseResult<'_, Vec<Type>> {
+        let (input, _) = le_u8(input)?;
+        let (input, parameter_count) = le_u8(input)?;
+        parse_list_len(input, Type::parse, parameter_count.into())
+    }
+}
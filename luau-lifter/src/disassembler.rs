@@ -0,0 +1,385 @@
+use std::{collections::BTreeSet, fmt::Write};
+
+use crate::{
+    builtin,
+    deserializer::{
+        self, bytecode::Bytecode, chunk::Chunk, constant::Constant, function::Function,
+    },
+    instruction::Instruction,
+    op_code::OpCode,
+    op_code_map::OpCodeMap,
+    DeserializeError,
+};
+
+pub fn disassemble(bytecode: &[u8], encode_key: Option<u8>) -> Result<String, DeserializeError> {
+    let encode_key = crate::resolve_encode_key(bytecode, encode_key);
+    disassemble_with_op_code_map(bytecode, &OpCodeMap::from_key(encode_key))
+}
+
+pub fn disassemble_with_op_code_map(
+    bytecode: &[u8],
+    op_code_map: &OpCodeMap,
+) -> Result<String, DeserializeError> {
+    Ok(
+        match deserializer::deserialize_with_op_code_map(bytecode, op_code_map)? {
+            Bytecode::Error(msg) => msg,
+            Bytecode::Chunk(chunk) => {
+                let mut output = String::new();
+                for function_id in 0..chunk.functions.len() {
+                    if function_id != 0 {
+                        output.push('\n');
+                    }
+                    Disassembler::new(&chunk, function_id).write(&mut output);
+                }
+                output
+            }
+        },
+    )
+}
+
+pub(crate) fn disassemble_function(chunk: &Chunk, function_id: usize) -> String {
+    let mut output = String::new();
+    Disassembler::new(chunk, function_id).write(&mut output);
+    output
+}
+
+struct Disassembler<'a> {
+    chunk: &'a Chunk,
+    function_id: usize,
+    function: &'a Function,
+    labels: Vec<usize>,
+}
+
+impl<'a> Disassembler<'a> {
+    fn new(chunk: &'a Chunk, function_id: usize) -> Self {
+        let function = &chunk.functions[function_id];
+        let labels = function
+            .instructions
+            .iter()
+            .enumerate()
+            .filter_map(|(pc, instruction)| instruction.jump_target(pc))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        Self {
+            chunk,
+            function_id,
+            function,
+            labels,
+        }
+    }
+
+    fn string(&self, index: usize) -> Option<String> {
+        let string = self.chunk.string_table.get(index.checked_sub(1)?)?;
+        Some(format!("{:?}", String::from_utf8_lossy(string)))
+    }
+
+    fn constant_string(&self, index: usize) -> Option<String> {
+        match self.function.constants.get(index)? {
+            &Constant::String(string) => self.string(string),
+            _ => None,
+        }
+    }
+
+    fn import(&self, import: usize) -> String {
+        let count = import >> 30;
+        [(import >> 20) & 1023, (import >> 10) & 1023, import & 1023]
+            .into_iter()
+            .take(count)
+            .map(|index| match self.function.constants.get(index) {
+                Some(&Constant::String(string)) => self
+                    .chunk
+                    .string_table
+                    .get(string.wrapping_sub(1))
+                    .map(|s| String::from_utf8_lossy(s).into_owned())
+                    .unwrap_or_else(|| "?".into()),
+                _ => "?".into(),
+            })
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+
+    fn constant(&self, index: usize) -> String {
+        self.constant_in(index, &mut Vec::new())
+    }
+
+    // the path of a FASTCALL builtin, or its number if it's unknown
+    fn builtin(id: u8) -> String {
+        match builtin::builtin(id) {
+            Some(path) => path.to_string(),
+            None => format!("builtin {}", id),
+        }
+    }
+
+    // `tables` holds the table constants being printed, malformed tables can contain themselves
+    fn constant_in(&self, index: usize, tables: &mut Vec<usize>) -> String {
+        match self.function.constants.get(index) {
+            None => format!("K{} (out of range)", index),
+            Some(Constant::Nil) => "nil".into(),
+            Some(Constant::Boolean(value)) => value.to_string(),
+            Some(Constant::Number(value)) => value.to_string(),
+            Some(&Constant::String(string)) => self
+                .string(string)
+                .unwrap_or_else(|| format!("S{}", string)),
+            Some(&Constant::Import(import)) => format!("import {}", self.import(import)),
+            Some(Constant::Table(keys)) => format!(
+                "{{{}}}",
+                keys.iter()
+                    .map(|&key| self
+                        .constant_string(key)
+                        .unwrap_or_else(|| format!("K{}", key)))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Some(Constant::TableWithConstants(_)) if tables.contains(&index) => {
+                format!("K{} (recursive)", index)
+            }
+            Some(Constant::TableWithConstants(fields)) => {
+                tables.push(index);
+                let fields = fields
+                    .iter()
+                    .map(|&(key, value)| {
+                        let key = self
+                            .constant_string(key)
+                            .unwrap_or_else(|| format!("K{}", key));
+                        match value {
+                            Some(value) => {
+                                format!("{} = {}", key, self.constant_in(value, tables))
+                            }
+                            None => key,
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                tables.pop();
+                format!("{{{}}}", fields)
+            }
+            Some(Constant::Closure(function)) => format!("function {}", function),
+            Some(Constant::Vector(x, y, z, w)) => format!("vector({}, {}, {}, {})", x, y, z, w),
+        }
+    }
+
+    fn label(&self, pc: usize) -> String {
+        match self.labels.binary_search(&pc) {
+            Ok(label) => format!("L{}", label),
+            Err(_) => format!("pc {}", pc),
+        }
+    }
+
+    fn comment(&self, pc: usize, instruction: &Instruction) -> Option<String> {
+        let jump = instruction
+            .jump_target(pc)
+            .map(|target| format!("to {}", self.label(target)));
+        let comment = match *instruction {
+            Instruction::BC {
+                op_code:
+                    OpCode::LOP_GETGLOBAL
+                    | OpCode::LOP_SETGLOBAL
+                    | OpCode::LOP_GETTABLEKS
+                    | OpCode::LOP_SETTABLEKS
+                    | OpCode::LOP_NAMECALL
+                    | OpCode::LOP_LOADKX,
+                aux,
+                ..
+            } => Some(self.constant(aux as usize)),
+            Instruction::BC {
+                op_code:
+                    OpCode::LOP_ADDK
+                    | OpCode::LOP_SUBK
+                    | OpCode::LOP_MULK
+                    | OpCode::LOP_DIVK
+                    | OpCode::LOP_MODK
+                    | OpCode::LOP_POWK
+                    | OpCode::LOP_IDIVK
+                    | OpCode::LOP_ANDK
+                    | OpCode::LOP_ORK,
+                c,
+                ..
+            } => Some(self.constant(c as usize)),
+            Instruction::BC {
+                op_code: OpCode::LOP_SUBRK | OpCode::LOP_DIVRK,
+                b,
+                ..
+            } => Some(self.constant(b as usize)),
+            Instruction::BC {
+                op_code: OpCode::LOP_FASTCALL | OpCode::LOP_FASTCALL1,
+                a,
+                ..
+            } => Some(Self::builtin(a)),
+            Instruction::BC {
+                op_code: OpCode::LOP_FASTCALL2,
+                a,
+                aux,
+                ..
+            } => Some(format!("{}, R{}", Self::builtin(a), aux)),
+            Instruction::BC {
+                op_code: OpCode::LOP_FASTCALL2K,
+                a,
+                aux,
+                ..
+            } => Some(format!(
+                "{}, {}",
+                Self::builtin(a),
+                self.constant(aux as usize)
+            )),
+            Instruction::BC {
+                op_code: OpCode::LOP_FASTCALL3,
+                a,
+                aux,
+                ..
+            } => Some(format!(
+                "{}, R{}, R{}",
+                Self::builtin(a),
+                aux & 0xFF,
+                (aux >> 8) & 0xFF
+            )),
+            Instruction::BC {
+                op_code: OpCode::LOP_CAPTURE,
+                a,
+                ..
+            } => Some(
+                match a {
+                    0 => "value",
+                    1 => "reference",
+                    2 => "upvalue",
+                    _ => "unknown",
+                }
+                .into(),
+            ),
+            Instruction::AD {
+                op_code:
+                    OpCode::LOP_LOADK
+                    | OpCode::LOP_GETIMPORT
+                    | OpCode::LOP_DUPTABLE
+                    | OpCode::LOP_DUPCLOSURE,
+                d,
+                ..
+            } => Some(self.constant(d as u16 as usize)),
+            Instruction::AD {
+                op_code: OpCode::LOP_NEWCLOSURE,
+                d,
+                ..
+            } => Some(match self.function.functions.get(d as u16 as usize) {
+                Some(function) => format!("function {}", function),
+                None => "proto out of range".into(),
+            }),
+            Instruction::AD {
+                op_code:
+                    OpCode::LOP_JUMPIFEQ
+                    | OpCode::LOP_JUMPIFLE
+                    | OpCode::LOP_JUMPIFLT
+                    | OpCode::LOP_JUMPIFNOTEQ
+                    | OpCode::LOP_JUMPIFNOTLE
+                    | OpCode::LOP_JUMPIFNOTLT,
+                aux,
+                ..
+            } => Some(format!("R{}", aux)),
+            Instruction::AD {
+                op_code:
+                    op_code @ (OpCode::LOP_JUMPXEQKNIL
+                    | OpCode::LOP_JUMPXEQKB
+                    | OpCode::LOP_JUMPXEQKN
+                    | OpCode::LOP_JUMPXEQKS),
+                aux,
+                ..
+            } => {
+                let value = match op_code {
+                    OpCode::LOP_JUMPXEQKNIL => "nil".into(),
+                    OpCode::LOP_JUMPXEQKB => (aux & 1 != 0).to_string(),
+                    _ => self.constant((aux & 0xFFFFFF) as usize),
+                };
+                Some(if aux >> 31 != 0 {
+                    format!("not {}", value)
+                } else {
+                    value
+                })
+            }
+            _ => None,
+        };
+        match (comment, jump) {
+            (Some(comment), Some(jump)) => Some(format!("{}, {}", comment, jump)),
+            (comment, jump) => comment.or(jump),
+        }
+    }
+
+    fn write(&self, output: &mut String) {
+        let function = self.function;
+        let name = self.string(function.function_name);
+        writeln!(
+            output,
+            "function {}{}{} (line {})",
+            self.function_id,
+            name.map(|name| format!(" {}", name)).unwrap_or_default(),
+            if self.function_id == self.chunk.main {
+                " [main]"
+            } else {
+                ""
+            },
+            function.line_defined
+        )
+        .unwrap();
+        writeln!(
+            output,
+            "  params: {}, upvalues: {}, vararg: {}, max stack: {}",
+            function.num_parameters,
+            function.num_upvalues,
+            function.is_vararg,
+            function.max_stack_size
+        )
+        .unwrap();
+
+        if !function.constants.is_empty() {
+            writeln!(output, "  constants:").unwrap();
+            for index in 0..function.constants.len() {
+                writeln!(output, "    K{}: {}", index, self.constant(index)).unwrap();
+            }
+        }
+        if !function.functions.is_empty() {
+            writeln!(output, "  protos:").unwrap();
+            for (index, function) in function.functions.iter().enumerate() {
+                writeln!(output, "    P{}: function {}", index, function).unwrap();
+            }
+        }
+
+        writeln!(output, "  code:").unwrap();
+        let mut instructions = function.instructions.iter().enumerate();
+        while let Some((pc, instruction)) = instructions.next() {
+            if let Ok(label) = self.labels.binary_search(&pc) {
+                writeln!(output, "  L{}:", label).unwrap();
+            }
+            let op_code = instruction.op_code();
+            let name = format!("{:?}", op_code);
+            let operands = match *instruction {
+                Instruction::BC { a, b, c, .. } => format!("{} {} {}", a, b, c),
+                Instruction::AD { a, d, .. } => format!("{} {}", a, d),
+                Instruction::E { e, .. } => e.to_string(),
+            };
+            let mut line = format!(
+                "    {:<5} {:<16} {}",
+                pc,
+                name.trim_start_matches("LOP_"),
+                operands
+            );
+            if op_code.has_aux() {
+                // skip the placeholder for the aux word
+                instructions.next();
+                let aux = match *instruction {
+                    Instruction::BC { aux, .. } | Instruction::AD { aux, .. } => aux,
+                    Instruction::E { .. } => unreachable!(),
+                };
+                write!(line, " [{:#010x}]", aux).unwrap();
+            }
+            if let Some(comment) = self.comment(pc, instruction) {
+                write!(
+                    line,
+                    "{:<1$} ; {2}",
+                    "",
+                    48usize.saturating_sub(line.len()),
+                    comment
+                )
+                .unwrap();
+            }
+            writeln!(output, "{}", line).unwrap();
+        }
+    }
+}
@@ -1,6 +1,4 @@
-use std::convert::TryFrom;
-
-use crate::op_code::OpCode;
+use crate::{op_code::OpCode, op_code_map::OpCodeMap};
 
 /*
 
@@ -113,10 +111,11 @@ pub enum Instruction {
 }
 
 impl Instruction {
-    pub fn parse(insn: u32, encode_key: u8) -> Result<Instruction, nom::error::ErrorKind> {
-        let op_code = (insn & 0xFF) as u8;
-        let op_code = op_code.wrapping_mul(encode_key);
-        match op_code {
+    // on failure, returns the raw opcode
+    pub fn parse(insn: u32, op_code_map: &OpCodeMap) -> Result<Instruction, u8> {
+        let raw_op_code = (insn & 0xFF) as u8;
+        let op_code = op_code_map.get(raw_op_code).ok_or(raw_op_code)?;
+        match op_code as u8 {
             0
             | 1
             | 2
@@ -138,7 +137,7 @@ impl Instruction {
                 let (a, b, c) = Self::parse_abc(insn);
 
                 Ok(Self::BC {
-                    op_code: OpCode::try_from(op_code).unwrap(),
+                    op_code,
                     a,
                     b,
                     c,
@@ -149,7 +148,7 @@ impl Instruction {
                 let (a, d) = Self::parse_ad(insn);
 
                 Ok(Self::AD {
-                    op_code: OpCode::try_from(op_code).unwrap(),
+                    op_code,
                     a,
                     d,
                     aux: 0,
@@ -158,19 +157,81 @@ impl Instruction {
             67 | 69 => {
                 let e = Self::parse_e(insn);
 
-                Ok(Self::E {
-                    op_code: OpCode::try_from(op_code).unwrap(),
-                    e,
-                })
+                Ok(Self::E { op_code, e })
+            }
+            _ => Err(raw_op_code),
+        }
+    }
+
+    pub fn op_code(&self) -> OpCode {
+        match *self {
+            Self::BC { op_code, .. } | Self::AD { op_code, .. } | Self::E { op_code, .. } => {
+                op_code
+            }
+        }
+    }
+
+    // the pc that control may be transferred to, other than the next instruction
+    pub fn jump_target(&self, pc: usize) -> Option<usize> {
+        match *self {
+            Self::BC {
+                op_code: OpCode::LOP_LOADB,
+                c,
+                ..
+            } if c != 0 => Some(pc + 1 + c as usize),
+            // the fast path skips the fallback CALL
+            Self::BC {
+                op_code:
+                    OpCode::LOP_FASTCALL
+                    | OpCode::LOP_FASTCALL1
+                    | OpCode::LOP_FASTCALL2
+                    | OpCode::LOP_FASTCALL2K
+                    | OpCode::LOP_FASTCALL3,
+                c,
+                ..
+            } => Some(pc + 2 + c as usize),
+            Self::AD {
+                op_code:
+                    OpCode::LOP_JUMP
+                    | OpCode::LOP_JUMPBACK
+                    | OpCode::LOP_JUMPIF
+                    | OpCode::LOP_JUMPIFNOT
+                    | OpCode::LOP_JUMPIFEQ
+                    | OpCode::LOP_JUMPIFLE
+                    | OpCode::LOP_JUMPIFLT
+                    | OpCode::LOP_JUMPIFNOTEQ
+                    | OpCode::LOP_JUMPIFNOTLE
+                    | OpCode::LOP_JUMPIFNOTLT
+                    | OpCode::LOP_JUMPXEQKNIL
+                    | OpCode::LOP_JUMPXEQKB
+                    | OpCode::LOP_JUMPXEQKN
+                    | OpCode::LOP_JUMPXEQKS
+                    | OpCode::LOP_FORNPREP
+                    | OpCode::LOP_FORNLOOP
+                    | OpCode::LOP_FORGPREP
+                    | OpCode::LOP_FORGPREP_INEXT
+                    | OpCode::LOP_FORGPREP_NEXT
+                    | OpCode::LOP_FORGLOOP,
+                d,
+                ..
+            } => pc.checked_add_signed(1 + d as isize),
+            Self::E {
+                op_code: OpCode::LOP_JUMPX,
+                e,
+            } => pc.checked_add_signed(1 + e as isize),
+            _ => None,
+        }
+    }
+
+    // the inverse of `parse`, `raw_op_code` being what the opcode map decodes to the opcode
+    pub fn encode(&self, raw_op_code: u8) -> u32 {
+        let raw_op_code = raw_op_code as u32;
+        match *self {
+            Self::BC { a, b, c, .. } => {
+                raw_op_code | (a as u32) << 8 | (b as u32) << 16 | (c as u32) << 24
             }
-            97 => Ok(Self::BC {
-                op_code: OpCode::try_from(0).unwrap(),
-                a: 0,
-                b: 0,
-                c: 0,
-                aux: 0,
-            }),
-            _ => unreachable!("{}", op_code),
+            Self::AD { a, d, .. } => raw_op_code | (a as u32) << 8 | (d as u16 as u32) << 16,
+            Self::E { e, .. } => raw_op_code | (e as u32) << 8,
         }
     }
 
@@ -0,0 +1,196 @@
+use std::hash::{Hash, Hasher};
+
+use rustc_hash::FxHasher;
+
+use crate::{
+    deserializer::{
+        self, bytecode::Bytecode, chunk::Chunk, constant::Constant, function::Function,
+    },
+    instruction::Instruction,
+    op_code::OpCode,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DetectedKey {
+    pub key: u8,
+    // how much better the key scored than the best key that decodes to different opcodes,
+    // from 0 (ambiguous) to 1 (the only plausible key)
+    pub confidence: f64,
+}
+
+// vanilla and Roblox client keys first, so that they win ties
+fn candidate_keys() -> impl Iterator<Item = u8> {
+    [1, 203].into_iter().chain(
+        (1..=u8::MAX)
+            .step_by(2)
+            .filter(|&key| key != 1 && key != 203),
+    )
+}
+
+// tries every odd key, as `op * key % 256` is only invertible for those. that's up to 128
+// deserializations, so callers should only detect the key when they don't know it.
+// returns `None` if the bytecode can't be deserialized with any key.
+pub fn detect_encode_key(bytecode: &[u8]) -> Option<DetectedKey> {
+    let mut scores = Vec::new();
+    for key in candidate_keys() {
+        match deserializer::deserialize(bytecode, key) {
+            // the error message doesn't depend on the key
+            Ok(Bytecode::Error(_)) => {
+                return Some(DetectedKey {
+                    key,
+                    confidence: 0.0,
+                })
+            }
+            Ok(Bytecode::Chunk(chunk)) => {
+                scores.push((key, score(&chunk), op_codes_hash(&chunk)));
+            }
+            Err(_) => {}
+        }
+    }
+
+    let &(key, best, best_hash) =
+        scores
+            .iter()
+            .reduce(|best, score| if score.1 > best.1 { score } else { best })?;
+    // keys that decode to the same opcodes are equally correct
+    let runner_up = scores
+        .iter()
+        .filter(|&&(_, _, hash)| hash != best_hash)
+        .map(|&(_, score, _)| score)
+        .fold(0.0, f64::max);
+    Some(DetectedKey {
+        key,
+        confidence: (best - runner_up).clamp(0.0, 1.0),
+    })
+}
+
+fn op_codes_hash(chunk: &Chunk) -> u64 {
+    let mut hasher = FxHasher::default();
+    for function in &chunk.functions {
+        for instruction in &function.instructions {
+            instruction.op_code().hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+// the fraction of structural checks that pass
+fn score(chunk: &Chunk) -> f64 {
+    let mut checks = Checks::default();
+    for function in &chunk.functions {
+        check_function(function, &mut checks);
+    }
+    if checks.total == 0 {
+        return 0.0;
+    }
+    checks.passed as f64 / checks.total as f64
+}
+
+#[derive(Default)]
+struct Checks {
+    passed: usize,
+    total: usize,
+}
+
+impl Checks {
+    fn check(&mut self, passed: bool) {
+        self.total += 1;
+        self.passed += usize::from(passed);
+    }
+}
+
+fn check_function(function: &Function, checks: &mut Checks) {
+    let mut last_op_code = None;
+    let mut instructions = function.instructions.iter().enumerate();
+    while let Some((pc, instruction)) = instructions.next() {
+        let op_code = instruction.op_code();
+        last_op_code = Some(op_code);
+        if let Some(target) = instruction.jump_target(pc) {
+            checks.check(target < function.instructions.len());
+        }
+        if op_code.has_aux() {
+            // skip the placeholder for the aux word
+            instructions.next();
+            checks.check(aux_is_well_formed(function, instruction));
+        }
+    }
+    checks.check(last_op_code == Some(OpCode::LOP_RETURN));
+}
+
+fn aux_is_well_formed(function: &Function, instruction: &Instruction) -> bool {
+    let constant = |index: u32| function.constants.get(index as usize);
+    let register = |register: u32| register < function.max_stack_size.into();
+    match *instruction {
+        Instruction::BC {
+            op_code:
+                OpCode::LOP_GETGLOBAL
+                | OpCode::LOP_SETGLOBAL
+                | OpCode::LOP_GETTABLEKS
+                | OpCode::LOP_SETTABLEKS
+                | OpCode::LOP_NAMECALL,
+            aux,
+            ..
+        } => matches!(constant(aux), Some(Constant::String(_))),
+        Instruction::BC {
+            op_code: OpCode::LOP_LOADKX | OpCode::LOP_FASTCALL2K,
+            aux,
+            ..
+        } => constant(aux).is_some(),
+        Instruction::BC {
+            op_code: OpCode::LOP_FASTCALL2,
+            aux,
+            ..
+        } => register(aux),
+        Instruction::BC {
+            op_code: OpCode::LOP_FASTCALL3,
+            aux,
+            ..
+        } => register(aux & 0xFF) && register((aux >> 8) & 0xFF),
+        Instruction::AD {
+            op_code: OpCode::LOP_GETIMPORT,
+            d,
+            aux,
+            ..
+        } => match constant(d as u16 as u32) {
+            Some(&Constant::Import(import)) => import == aux as usize && matches!(aux >> 30, 1..=3),
+            _ => false,
+        },
+        Instruction::AD {
+            op_code:
+                OpCode::LOP_JUMPIFEQ
+                | OpCode::LOP_JUMPIFLE
+                | OpCode::LOP_JUMPIFLT
+                | OpCode::LOP_JUMPIFNOTEQ
+                | OpCode::LOP_JUMPIFNOTLE
+                | OpCode::LOP_JUMPIFNOTLT,
+            aux,
+            ..
+        } => register(aux),
+        Instruction::AD {
+            op_code: OpCode::LOP_JUMPXEQKNIL,
+            aux,
+            ..
+        } => aux & 0x7FFFFFFF == 0,
+        Instruction::AD {
+            op_code: OpCode::LOP_JUMPXEQKB,
+            aux,
+            ..
+        } => aux & 0x7FFFFFFE == 0,
+        Instruction::AD {
+            op_code: OpCode::LOP_JUMPXEQKN,
+            aux,
+            ..
+        } => matches!(constant(aux & 0xFFFFFF), Some(Constant::Number(_))),
+        Instruction::AD {
+            op_code: OpCode::LOP_JUMPXEQKS,
+            aux,
+            ..
+        } => matches!(constant(aux & 0xFFFFFF), Some(Constant::String(_))),
+        Instruction::AD {
+            op_code: OpCode::LOP_FORGLOOP,
+            aux,
+            ..
+        } => aux & 0xFF != 0,
+        _ => true,
+    }
+}
@@ -1,275 +1,292 @@
-mod deserializer;
-mod instruction;
+#![feature(let_chains)]
+
+mod builtin;
+pub mod deserializer;
+mod disassembler;
+pub mod instruction;
+mod key_detection;
 mod lifter;
-mod op_code;
+mod line_info;
+pub mod op_code;
+pub mod op_code_map;
+mod options;
+mod output;
+pub mod serializer;
+mod vector;
 
-use ast::{
-    local_declarations::LocalDeclarer, name_locals::name_locals, replace_locals::replace_locals,
-    Traverse,
-};
+use ast::formatter::Formatter;
 
 use by_address::ByAddress;
-use cfg::{
-    function::Function,
-    ssa::{
-        self,
-        structuring::{structure_conditionals, structure_jumps},
-    },
-};
-use indexmap::IndexMap;
 
+use disassembler::disassemble_function;
 use lifter::Lifter;
 
-//use cfg_ir::{dot, function::Function, ssa};
-use clap::Parser;
 use parking_lot::Mutex;
-use petgraph::algo::dominators::simple_fast;
-use rayon::prelude::*;
+use pipeline::{isolate, Lifted, Outcome, Panic};
 
-use anyhow::anyhow;
-use rustc_hash::FxHashMap;
 use triomphe::Arc;
-use walkdir::WalkDir;
 
-use std::{
-    fs::File,
-    io::{Read, Write},
-    path::Path,
-    time::Instant,
-};
+use std::{borrow::Cow, fmt::Write, time::Instant};
 
 use deserializer::bytecode::Bytecode;
+use op_code_map::OpCodeMap;
+
+pub use ast::formatter::IndentationMode;
+pub use deserializer::error::DeserializeError;
+pub use disassembler::{disassemble, disassemble_with_op_code_map};
+pub use key_detection::{detect_encode_key, DetectedKey};
+pub use line_info::LineInfoMode;
+pub use options::{
+    Budget, DecompileOptions, DiagnosticsLevel, FormattingOptions, NamingMode, Passes,
+};
+pub use output::{DecompileError, DecompileOutput, FunctionReport, FunctionStatus, Timing};
+pub use pipeline::install_panic_hook;
+pub use vector::{VectorConstructor, VectorOptions};
 
 #[cfg(feature = "dhat-heap")]
 #[global_allocator]
 static ALLOC: dhat::Alloc = dhat::Alloc;
 
-#[derive(Parser, Debug)]
-#[clap(about, version, author)]
-struct Args {
-    paths: Vec<String>,
-    /// Number of threads to use (0 = automatic)
-    #[clap(short, long, default_value_t = 0)]
-    threads: usize,
-    /// op = op * key % 256
-    /// For Roblox client bytecode, use 203
-    #[clap(short, long, default_value_t = 1)]
-    key: u8,
-    #[clap(short, long)]
-    recursive: bool,
-    #[clap(short, long)]
-    verbose: bool,
+// falls back to 1 when no key can be detected, so that the error is reported for vanilla bytecode
+fn resolve_encode_key(bytecode: &[u8], encode_key: Option<u8>) -> u8 {
+    encode_key.unwrap_or_else(|| detect_encode_key(bytecode).map_or(1, |detected| detected.key))
 }
 
-pub fn decompile_bytecode(bytecode: &[u8], encode_key: u8) -> String {
-    let chunk = deserializer::deserialize(bytecode, encode_key).unwrap();
-    match chunk {
-        Bytecode::Error(msg) => msg,
-        Bytecode::Chunk(chunk) => {
-            let mut lifted = Vec::new();
-            let mut stack = vec![(Arc::<Mutex<ast::Function>>::default(), chunk.main)];
-            while let Some((ast_func, func_id)) = stack.pop() {
-                let (function, upvalues, child_functions) =
-                    Lifter::lift(&chunk.functions, &chunk.string_table, func_id);
-                lifted.push((ast_func, function, upvalues));
-                stack.extend(child_functions.into_iter().map(|(a, f)| (a.0, f)));
-            }
-
-            let (main, ..) = lifted.first().unwrap().clone();
-            let mut upvalues = lifted
-                .into_iter()
-                .map(|(ast_function, function, upvalues_in)| {
-                    use std::{backtrace::Backtrace, cell::RefCell, fmt::Write, panic};
-
-                    thread_local! {
-                        static BACKTRACE: RefCell<Option<Backtrace>> = const { RefCell::new(None) };
-                    }
-
-                    let function_id = function.id;
-                    let mut args = std::panic::AssertUnwindSafe(Some((
-                        ast_function.clone(),
-                        function,
-                        upvalues_in,
-                    )));
-
-                    let prev_hook = panic::take_hook();
-                    panic::set_hook(Box::new(|_| {
-                        let trace = Backtrace::capture();
-                        BACKTRACE.with(move |b| b.borrow_mut().replace(trace));
-                    }));
-                    let result = panic::catch_unwind(move || {
-                        let (ast_function, function, upvalues_in) = args.take().unwrap();
-                        decompile_function(ast_function, function, upvalues_in)
-                    });
-                    panic::set_hook(prev_hook);
-
-                    match result {
-                        Ok(r) => r,
-                        Err(e) => {
-                            let panic_information = match e.downcast::<String>() {
-                                Ok(v) => *v,
-                                Err(e) => match e.downcast::<&str>() {
-                                    Ok(v) => v.to_string(),
-                                    _ => "Unknown Source of Error".to_owned(),
-                                },
-                            };
+/// Pass `None` as the encode key to detect it. Detection deserializes the bytecode once for
+/// each of the 128 odd keys, so pass the key when it's known
+pub fn decompile_bytecode(
+    bytecode: &[u8],
+    encode_key: Option<u8>,
+) -> Result<String, DeserializeError> {
+    decompile_bytecode_with_line_info(bytecode, encode_key, LineInfoMode::None)
+}
 
-                            let mut message = String::new();
-                            writeln!(message, "failed to decompile").unwrap();
-                            // writeln!(message, "function {} panicked at '{}'", function_id, panic_information).unwrap();
-                            // if let Some(backtrace) = BACKTRACE.with(|b| b.borrow_mut().take()) {
-                            //     write!(message, "stack backtrace:\n{}", backtrace).unwrap();
-                            // }
+pub fn decompile_bytecode_with_line_info(
+    bytecode: &[u8],
+    encode_key: Option<u8>,
+    line_info: LineInfoMode,
+) -> Result<String, DeserializeError> {
+    let mut options = DecompileOptions {
+        encode_key,
+        ..Default::default()
+    };
+    options.formatting.line_info = line_info;
+    decompile_to_string(bytecode, &options)
+}
 
-                            ast_function.lock().body.extend(
-                                message
-                                    .trim_end()
-                                    .split('\n')
-                                    .map(|s| ast::Comment::new(s.to_string()).into()),
-                            );
-                            (ByAddress(ast_function), Vec::new())
-                        }
-                    }
-                })
-                .collect::<FxHashMap<_, _>>();
+pub fn decompile_bytecode_with_op_code_map(
+    bytecode: &[u8],
+    op_code_map: &OpCodeMap,
+    line_info: LineInfoMode,
+    vectors: &VectorOptions,
+) -> Result<String, DeserializeError> {
+    let mut options = DecompileOptions {
+        op_code_map: Some(op_code_map.clone()),
+        vectors: vectors.clone(),
+        ..Default::default()
+    };
+    options.formatting.line_info = line_info;
+    decompile_to_string(bytecode, &options)
+}
 
-            let main = ByAddress(main);
-            upvalues.remove(&main);
-            let mut body = Arc::try_unwrap(main.0).unwrap().into_inner().body;
-            link_upvalues(&mut body, &mut upvalues);
-            name_locals(&mut body, true);
-            body.to_string()
-        }
+// compile errors are returned as the source, like the compiler embeds them in the bytecode
+fn decompile_to_string(
+    bytecode: &[u8],
+    options: &DecompileOptions,
+) -> Result<String, DeserializeError> {
+    match decompile(bytecode, options) {
+        Ok(output) => Ok(output.source),
+        Err(DecompileError::Compile(message)) => Ok(message),
+        Err(DecompileError::Deserialize(err)) => Err(err),
     }
 }
 
-fn decompile_function(
-    ast_function: Arc<Mutex<ast::Function>>,
-    mut function: Function,
-    upvalues_in: Vec<ast::RcLocal>,
-) -> (ByAddress<Arc<Mutex<ast::Function>>>, Vec<ast::RcLocal>) {
-    let (local_count, local_groups, upvalue_in_groups, upvalue_passed_groups) =
-        cfg::ssa::construct(&mut function, &upvalues_in);
-    let upvalue_to_group = upvalue_in_groups
-        .into_iter()
-        .chain(
-            upvalue_passed_groups
-                .into_iter()
-                .map(|m| (ast::RcLocal::default(), m)),
-        )
-        .flat_map(|(i, g)| g.into_iter().map(move |u| (u, i.clone())))
-        .collect::<IndexMap<_, _>>();
-    // TODO: do we even need this?
-    let local_to_group = local_groups
-        .into_iter()
-        .enumerate()
-        .flat_map(|(i, g)| g.into_iter().map(move |l| (l, i)))
-        .collect::<FxHashMap<_, _>>();
-    // TODO: REFACTOR: some way to write a macro that states
-    // if cfg::ssa::inline results in change then structure_jumps, structure_compound_conditionals,
-    // structure_for_loops and remove_unnecessary_params must run again.
-    // if structure_compound_conditionals results in change then dominators and post dominators
-    // must be recalculated.
-    // etc.
-    // the macro could also maybe generate an optimal ordering?
-    let mut changed = true;
-    while changed {
-        changed = false;
-
-        let dominators = simple_fast(function.graph(), function.entry().unwrap());
-        changed |= structure_jumps(&mut function, &dominators);
+pub fn decompile(
+    bytecode: &[u8],
+    options: &DecompileOptions,
+) -> Result<DecompileOutput, DecompileError> {
+    let start = Instant::now();
+    let op_code_map = match &options.op_code_map {
+        Some(op_code_map) => Cow::Borrowed(op_code_map),
+        None => Cow::Owned(OpCodeMap::from_key(resolve_encode_key(
+            bytecode,
+            options.encode_key,
+        ))),
+    };
+    let chunk = match deserializer::deserialize_with_op_code_map(bytecode, &op_code_map)? {
+        Bytecode::Error(msg) => return Err(DecompileError::Compile(msg)),
+        Bytecode::Chunk(chunk) => chunk,
+    };
+    let line_info = options.formatting.line_info;
+    let mut timing = Timing {
+        deserialize: start.elapsed(),
+        ..Default::default()
+    };
 
-        ssa::inline::inline(&mut function, &local_to_group, &upvalue_to_group);
+    let pipeline_options = pipeline::Options {
+        passes: pipeline::Passes {
+            // we can't structure method calls like this because of __namecall
+            structure_method_calls: false,
+            ..options.passes
+        },
+        budget: options.budget,
+        force_backtraces: options.diagnostics == DiagnosticsLevel::Backtrace,
+    };
+    let failure_message = |function_id: usize, panic: &Panic| {
+        let mut message = String::new();
+        if options.diagnostics == DiagnosticsLevel::None {
+            writeln!(message, "failed to decompile").unwrap();
+        } else {
+            let bytecode_function = &chunk.functions[function_id];
+            writeln!(
+                message,
+                "failed to decompile function {}{} (line {})",
+                function_id,
+                function_name(&chunk.string_table, bytecode_function.function_name)
+                    .map(|name| format!(" {:?}", name))
+                    .unwrap_or_default(),
+                bytecode_function.line_defined
+            )
+            .unwrap();
+            writeln!(message, "panicked at '{}'", panic).unwrap();
+            if options.diagnostics == DiagnosticsLevel::Backtrace
+                && let Some(backtrace) = &panic.backtrace
+            {
+                write!(message, "stack backtrace:\n{}", backtrace).unwrap();
+            }
+            // the disassembly stands in for the body so the output still shows
+            // what the function does
+            message.push_str(&disassemble_function(&chunk, function_id));
+        }
+        message
+    };
+    let function_report = |function_id: usize, status, warnings, duration| {
+        let bytecode_function = &chunk.functions[function_id];
+        FunctionReport {
+            id: function_id,
+            name: function_name(&chunk.string_table, bytecode_function.function_name),
+            line_defined: bytecode_function.line_defined,
+            status,
+            warnings,
+            duration,
+        }
+    };
 
-        if structure_conditionals(&mut function)
-        // || {
-        //     let post_dominators = post_dominators(function.graph_mut());
-        //     structure_for_loops(&mut function, &dominators, &post_dominators)
-        // }
-        // we can't structure method calls like this because of __namecall
-        // || structure_method_calls(&mut function)
+    let lift_start = Instant::now();
+    let main = Arc::<Mutex<ast::Function>>::default();
+    let mut lifted = Vec::new();
+    let mut functions = Vec::new();
+    let mut function_lines = line_info::FunctionLines::default();
+    let mut stack = vec![(main.clone(), chunk.main)];
+    while let Some((ast_func, func_id)) = stack.pop() {
+        if line_info != LineInfoMode::None
+            && let Some(lines) = chunk.functions[func_id].lines()
         {
-            changed = true;
+            function_lines.insert(ByAddress(ast_func.clone()), lines);
         }
-        let mut local_map = FxHashMap::default();
-        // TODO: loop until returns false?
-        if ssa::construct::remove_unnecessary_params(&mut function, &mut local_map) {
-            changed = true;
+        let function_start = Instant::now();
+        let result = isolate(pipeline_options.force_backtraces, || {
+            Lifter::lift(
+                &chunk.functions,
+                &chunk.string_table,
+                &chunk.userdata_types,
+                func_id,
+                &options.vectors,
+            )
+        });
+        match result {
+            Ok((function, upvalues, child_functions, warnings)) => {
+                lifted.push(Lifted {
+                    ast_function: ast_func,
+                    upvalues_in: upvalues,
+                    data: (func_id, warnings, function_start.elapsed()),
+                    function,
+                });
+                stack.extend(child_functions.into_iter().map(|(a, f)| (a.0, f)));
+            }
+            // the closures of the function are never created, so its children are skipped
+            Err(panic) => {
+                pipeline::push_comment(
+                    &mut ast_func.lock().body,
+                    &failure_message(func_id, &panic),
+                );
+                functions.push(function_report(
+                    func_id,
+                    FunctionStatus::Failed {
+                        message: panic.message,
+                    },
+                    Vec::new(),
+                    function_start.elapsed(),
+                ));
+            }
         }
-        ssa::construct::apply_local_map(&mut function, local_map);
     }
-    // cfg::dot::render_to(&function, &mut std::io::stdout()).unwrap();
-    ssa::Destructor::new(
-        &mut function,
-        upvalue_to_group,
-        upvalues_in.iter().cloned().collect(),
-        local_count,
-    )
-    .destruct();
+    timing.lift = lift_start.elapsed();
 
-    let params = std::mem::take(&mut function.parameters);
-    let is_variadic = function.is_variadic;
-    let block = Arc::new(restructure::lift(function).into());
-    LocalDeclarer::default().declare_locals(
-        // TODO: why does block.clone() not work?
-        Arc::clone(&block),
-        &upvalues_in.iter().chain(params.iter()).cloned().collect(),
-    );
+    let decompile_start = Instant::now();
+    let decompile_functions = || {
+        pipeline::decompile_functions(lifted, &pipeline_options, |&(function_id, ..), panic| {
+            failure_message(function_id, panic)
+        })
+    };
+    let (upvalues, reports) = match &options.thread_pool {
+        None => decompile_functions(),
+        Some(thread_pool) => thread_pool.install(decompile_functions),
+    };
+    functions.extend(reports.into_iter().map(|report| {
+        let (function_id, mut warnings, lift_duration) = report.data;
+        let status = match report.outcome {
+            Outcome::Decompiled { budget_warning } => {
+                warnings.extend(budget_warning);
+                FunctionStatus::Decompiled
+            }
+            Outcome::Failed(panic) => FunctionStatus::Failed {
+                message: panic.message,
+            },
+        };
+        function_report(
+            function_id,
+            status,
+            warnings,
+            lift_duration + report.duration,
+        )
+    }));
+    functions.sort_unstable_by_key(|function| function.id);
 
-    {
-        let mut ast_function = ast_function.lock();
-        ast_function.body = Arc::try_unwrap(block).unwrap().into_inner();
-        ast_function.parameters = params;
-        ast_function.is_variadic = is_variadic;
+    let main_lines = function_lines
+        .remove(&ByAddress(main.clone()))
+        .unwrap_or_default();
+    let mut body = pipeline::link(main, upvalues, options.naming == NamingMode::Generated);
+    timing.decompile = decompile_start.elapsed();
+
+    if line_info != LineInfoMode::None {
+        line_info::insert_line_comments(&mut body, &main_lines, &function_lines, line_info);
+    }
+    let mut source = format_block(&body, options.formatting.indentation);
+    if chunk.functions[chunk.main].is_native_module() {
+        source = format!("--!native\n{}", source);
+    }
+    // after the directive, which takes up the first line
+    if line_info == LineInfoMode::Padding {
+        source = line_info::pad_lines(&source);
     }
-    (ByAddress(ast_function), upvalues_in)
+    timing.total = start.elapsed();
+
+    Ok(DecompileOutput {
+        source,
+        functions,
+        timing,
+    })
 }
 
-fn link_upvalues(
-    body: &mut ast::Block,
-    upvalues: &mut FxHashMap<ByAddress<Arc<Mutex<ast::Function>>>, Vec<ast::RcLocal>>,
-) {
-    for stat in &mut body.0 {
-        stat.traverse_rvalues(&mut |rvalue| {
-            if let ast::RValue::Closure(closure) = rvalue {
-                let old_upvalues = &upvalues[&closure.function];
-                let mut function = closure.function.lock();
-                // TODO: inefficient, try constructing a map of all up -> new up first
-                // and then call replace_locals on main body
-                let mut local_map =
-                    FxHashMap::with_capacity_and_hasher(old_upvalues.len(), Default::default());
-                for (old, new) in
-                    old_upvalues
-                        .iter()
-                        .zip(closure.upvalues.iter().map(|u| match u {
-                            ast::Upvalue::Copy(l) | ast::Upvalue::Ref(l) => l,
-                        }))
-                {
-                    // println!("{} -> {}", old, new);
-                    local_map.insert(old.clone(), new.clone());
-                }
-                link_upvalues(&mut function.body, upvalues);
-                replace_locals(&mut function.body, &local_map);
-            }
-        });
-        match stat {
-            ast::Statement::If(r#if) => {
-                link_upvalues(&mut r#if.then_block.lock(), upvalues);
-                link_upvalues(&mut r#if.else_block.lock(), upvalues);
-            }
-            ast::Statement::While(r#while) => {
-                link_upvalues(&mut r#while.block.lock(), upvalues);
-            }
-            ast::Statement::Repeat(repeat) => {
-                link_upvalues(&mut repeat.block.lock(), upvalues);
-            }
-            ast::Statement::NumericFor(numeric_for) => {
-                link_upvalues(&mut numeric_for.block.lock(), upvalues);
-            }
-            ast::Statement::GenericFor(generic_for) => {
-                link_upvalues(&mut generic_for.block.lock(), upvalues);
-            }
-            _ => {}
-        }
-    }
+// the string table is 1-based, 0 means the function has no name
+fn function_name(string_table: &[Vec<u8>], index: usize) -> Option<String> {
+    let string = string_table.get(index.checked_sub(1)?)?;
+    Some(String::from_utf8_lossy(string).into_owned())
+}
+
+fn format_block(block: &ast::Block, indentation: IndentationMode) -> String {
+    let mut source = String::new();
+    Formatter::format(block, &mut source, indentation).unwrap();
+    source
 }
@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 use anyhow::Result;
 
 use by_address::ByAddress;
@@ -10,43 +12,52 @@ use rustc_hash::FxHashMap;
 use triomphe::Arc;
 
 use super::{
+    builtin::{builtin, builtin_expression, builtin_result_name, BUILTIN_VECTOR},
     deserializer::{
-        constant::Constant as BytecodeConstant, function::Function as BytecodeFunction,
+        constant::Constant as BytecodeConstant,
+        function::Function as BytecodeFunction,
+        type_info::{Type as BytecodeType, TypeTag},
     },
     instruction::Instruction,
     op_code::OpCode,
+    vector::VectorOptions,
 };
-use ast::{self};
+use ast::{self, name_locals::name_written_locals, type_system::Type};
 use cfg::{
     block::{BlockEdge, BranchType},
     function::Function,
 };
 
+// the closures created by a function and the bytecode functions they lift from
+pub type ChildFunctions = FxHashMap<ByAddress<Arc<Mutex<ast::Function>>>, usize>;
+
 pub struct Lifter<'a> {
     function_list: &'a Vec<BytecodeFunction>,
     string_table: &'a Vec<Vec<u8>>,
+    userdata_types: &'a FxHashMap<u8, usize>,
     blocks: FxHashMap<usize, NodeIndex>,
     function: Function,
-    child_functions: FxHashMap<ByAddress<Arc<Mutex<ast::Function>>>, usize>,
+    child_functions: ChildFunctions,
     register_map: FxHashMap<usize, ast::RcLocal>,
     constant_map: FxHashMap<usize, ast::Literal>,
     current_node: Option<NodeIndex>,
     upvalues: Vec<ast::RcLocal>,
+    vectors: &'a VectorOptions,
+    warnings: Vec<String>,
 }
 
 impl<'a> Lifter<'a> {
     pub fn lift(
         f_list: &'a Vec<BytecodeFunction>,
         str_list: &'a Vec<Vec<u8>>,
+        userdata_types: &'a FxHashMap<u8, usize>,
         function_id: usize,
-    ) -> (
-        Function,
-        Vec<ast::RcLocal>,
-        FxHashMap<ByAddress<Arc<Mutex<ast::Function>>>, usize>,
-    ) {
+        vectors: &'a VectorOptions,
+    ) -> (Function, Vec<ast::RcLocal>, ChildFunctions, Vec<String>) {
         let mut context = Self {
             function_list: f_list,
             string_table: str_list,
+            userdata_types,
             blocks: FxHashMap::default(),
             function: Function::new(function_id),
             child_functions: FxHashMap::default(),
@@ -54,10 +65,17 @@ impl<'a> Lifter<'a> {
             constant_map: FxHashMap::default(),
             current_node: None,
             upvalues: Vec::new(),
+            vectors,
+            warnings: Vec::new(),
         };
 
         context.lift_function();
-        (context.function, context.upvalues, context.child_functions)
+        (
+            context.function,
+            context.upvalues,
+            context.child_functions,
+            context.warnings,
+        )
     }
 
     fn lift_function(&mut self) {
@@ -91,12 +109,25 @@ impl<'a> Lifter<'a> {
             )
             .1;
 
-        for _ in 0..self.function_list[self.function.id].num_upvalues {
-            self.upvalues.push(ast::RcLocal::default());
+        for i in 0..self.function_list[self.function.id].num_upvalues {
+            let name = self.function_list[self.function.id]
+                .upvalue_names
+                .get(i as usize)
+                .and_then(|&name| self.string(name));
+            self.upvalues.push(ast::RcLocal::new(ast::Local::new(name)));
         }
 
         for i in 0..self.function_list[self.function.id].num_parameters {
-            let parameter = ast::RcLocal::default();
+            let function = &self.function_list[self.function.id];
+            // parameters are live from the start of the function, or after PREPVARARGS
+            let name = function
+                .locals
+                .iter()
+                .find(|local| {
+                    local.register == i && local.range.start <= usize::from(function.is_vararg)
+                })
+                .and_then(|local| self.string(local.name));
+            let parameter = ast::RcLocal::new(ast::Local::new(name));
             self.function.parameters.push(parameter.clone());
             self.register_map.insert(i as usize, parameter);
         }
@@ -209,9 +240,7 @@ impl<'a> Lifter<'a> {
                             .or_insert_with(|| self.function.new_block());
                     }
                     OpCode::LOP_FORGLOOP => {
-                        let dest_index = (insn_index + 1)
-                            .checked_add_signed((*d).try_into().unwrap())
-                            .unwrap();
+                        let dest_index = (insn_index + 1).checked_add_signed((*d).into()).unwrap();
                         self.blocks
                             .entry(insn_index + 1)
                             .or_insert_with(|| self.function.new_block());
@@ -246,16 +275,21 @@ impl<'a> Lifter<'a> {
         block_start: usize,
         block_end: usize,
     ) -> (Vec<ast::Statement>, Vec<(NodeIndex, BlockEdge)>) {
-        let mut statements = Vec::with_capacity((block_start..=block_end).count());
+        let mut statements: Vec<ast::Statement> =
+            Vec::with_capacity((block_start..=block_end).count());
         let mut edges = Vec::new();
 
         let mut top: Option<(ast::RValue, u8)> = None;
+        // the pc of the last fastcall, the pc of the call it falls back to, and the builtin
+        let mut fastcall: Option<(usize, usize, &'a str)> = None;
 
         let mut iter = self.function_list[self.function.id].instructions[block_start..=block_end]
             .iter()
             .enumerate();
 
         while let Some((index, instruction)) = iter.next() {
+            let first_statement = statements.len();
+            let mut builtin_result = None;
             match *instruction {
                 Instruction::BC {
                     op_code,
@@ -499,7 +533,15 @@ impl<'a> Lifter<'a> {
                     | OpCode::LOP_FASTCALL1
                     | OpCode::LOP_FASTCALL2
                     | OpCode::LOP_FASTCALL2K
-                    | OpCode::LOP_FASTCALL3 => {}
+                    | OpCode::LOP_FASTCALL3 => {
+                        let pc = block_start + index;
+                        let path = if a == BUILTIN_VECTOR {
+                            Some(self.vectors.constructor.path())
+                        } else {
+                            builtin(a)
+                        };
+                        fastcall = path.map(|path| (pc, pc + 1 + c as usize, path));
+                    }
                     OpCode::LOP_NAMECALL => {
                         let namecall_base = a;
                         let namecall_object = self.register(b as _);
@@ -577,7 +619,18 @@ impl<'a> Lifter<'a> {
                                 .collect()
                         };
 
-                        let call = ast::Call::new(self.register(a as _).into(), arguments);
+                        let pc = block_start + index;
+                        let mut callee = self.register(a as _).into();
+                        if let Some((fastcall_pc, call_pc, path)) = fastcall.take()
+                            && call_pc == pc
+                        {
+                            if self.is_aliased_builtin(fastcall_pc + 1..pc, a) {
+                                callee = builtin_expression(path);
+                            }
+                            builtin_result = builtin_result_name(path);
+                        }
+
+                        let call = ast::Call::new(callee, arguments);
 
                         if c != 0 {
                             if c == 1 {
@@ -718,6 +771,16 @@ impl<'a> Lifter<'a> {
                         }
                     }
                     OpCode::LOP_NOP => {}
+                    OpCode::LOP_BREAK => {
+                        statements.push(ast::Comment::new("debugger break".to_string()).into());
+                    }
+                    OpCode::LOP_LOADKX => {
+                        let constant = self.constant(aux as _);
+                        let target = self.register(a as _);
+                        let statement =
+                            ast::Assign::new(vec![target.into()], vec![constant.into()]);
+                        statements.push(statement.into());
+                    }
                     OpCode::LOP_SUBRK | OpCode::LOP_DIVRK => {
                         let op = match op_code {
                             OpCode::LOP_SUBRK => ast::BinaryOperation::Sub,
@@ -735,7 +798,8 @@ impl<'a> Lifter<'a> {
                             .into(),
                         );
                     }
-                    _ => unreachable!("{:?}", instruction),
+                    // including stray captures, which are normally consumed by the closure before them
+                    _ => statements.push(self.unsupported(instruction)),
                 },
                 Instruction::AD { op_code, a, d, aux } => match op_code {
                     OpCode::LOP_LOADK => {
@@ -1191,10 +1255,11 @@ impl<'a> Lifter<'a> {
                         ));
                     }
                     OpCode::LOP_DUPTABLE => {
+                        let table = self.table_template(d as usize);
                         statements.push(
                             ast::Assign::new(
                                 vec![self.register(a as _).into()],
-                                vec![ast::Table::default().into()],
+                                vec![table.into()],
                             )
                             .into(),
                         );
@@ -1215,15 +1280,7 @@ impl<'a> Lifter<'a> {
                             },
                             _ => unreachable!(),
                         };
-                        let func_name_index = self.function_list[func_index].function_name;
-                        let func_name = if func_name_index == 0 {
-                            None
-                        } else {
-                            Some(
-                                String::from_utf8_lossy(&self.string_table[func_name_index - 1])
-                                    .into_owned(),
-                            )
-                        };
+                        let func_name = self.string(self.function_list[func_index].function_name);
 
                         let func = &self.function_list[func_index];
                         let mut upvalues_passed = Vec::with_capacity(func.num_upvalues.into());
@@ -1251,7 +1308,18 @@ impl<'a> Lifter<'a> {
                         let function = Arc::<Mutex<_>>::default();
                         self.child_functions
                             .insert(ByAddress(function.clone()), func_index);
-                        function.lock().name = func_name;
+                        {
+                            let mut function = function.lock();
+                            function.name = func_name;
+                            function.is_native =
+                                self.function_list[func_index].is_native_function();
+                            function.parameter_types = self.function_list[func_index]
+                                .type_info
+                                .parameters
+                                .iter()
+                                .map(|&r#type| self.r#type(r#type))
+                                .collect();
+                        }
                         statements.push(
                             ast::Assign::new(
                                 vec![dest_local.into()],
@@ -1264,7 +1332,9 @@ impl<'a> Lifter<'a> {
                             .into(),
                         );
                     }
-                    _ => unreachable!("{:?}", instruction),
+                    // the interpreter falls through to the bytecode when native code isn't available
+                    OpCode::LOP_NATIVECALL => {}
+                    _ => statements.push(self.unsupported(instruction)),
                 },
                 Instruction::E { op_code, e } => match op_code {
                     OpCode::LOP_JUMPX => {
@@ -1275,9 +1345,31 @@ impl<'a> Lifter<'a> {
                             BlockEdge::new(BranchType::Unconditional),
                         ));
                     }
-                    _ => unreachable!("{:?}", instruction),
+                    OpCode::LOP_COVERAGE => {}
+                    _ => statements.push(self.unsupported(instruction)),
                 },
-                _ => unimplemented!("{:?}", instruction),
+            }
+
+            let mut next_pc = iter
+                .clone()
+                .next()
+                .map_or(block_end + 1, |(i, _)| block_start + i);
+            // skip the placeholders for aux values
+            while let Some(Instruction::BC {
+                op_code: OpCode::LOP_NOP,
+                ..
+            }) = self.function_list[self.function.id]
+                .instructions
+                .get(next_pc)
+            {
+                next_pc += 1;
+            }
+            for statement in &mut statements[first_statement..] {
+                statement.set_pc_range(block_start + index..next_pc);
+            }
+            self.name_written_locals(&mut statements, first_statement, next_pc);
+            if let Some(name) = builtin_result {
+                self.name_builtin_results(&mut statements, first_statement, name);
             }
         }
 
@@ -1289,6 +1381,7 @@ impl<'a> Lifter<'a> {
             && !Self::is_terminator(self.function_list[self.function.id].instructions[last_index])
         {
             if last_index + 1 == self.function_list[self.function.id].instructions.len() {
+                self.warnings.push("block does not return".to_string());
                 statements
                     .push(ast::Comment::new("warning: block does not return".to_string()).into());
             } else {
@@ -1302,30 +1395,181 @@ impl<'a> Lifter<'a> {
         (statements, edges)
     }
 
+    // debug info names a local from the pc right after the instructions that initialize it
+    fn name_written_locals(
+        &self,
+        statements: &mut Vec<ast::Statement>,
+        first_statement: usize,
+        next_pc: usize,
+    ) {
+        let function = &self.function_list[self.function.id];
+        if function.locals.is_empty() {
+            return;
+        }
+
+        name_written_locals(statements, first_statement, |local| {
+            let (&register, _) = self
+                .register_map
+                .iter()
+                .find(|(_, register_local)| *register_local == local)?;
+            let debug_local = function.locals.iter().find(|debug_local| {
+                debug_local.register as usize == register && debug_local.range.start == next_pc
+            })?;
+            let name = self.string(debug_local.name)?;
+            Some(ast::RcLocal::new(ast::Local::new(Some(name))))
+        });
+    }
+
+    // the compiler only emits a fastcall if it knows the callee is the builtin,
+    // so a callee read from a local alias or an upvalue can be replaced with it
+    fn is_aliased_builtin(&self, setup: Range<usize>, register: u8) -> bool {
+        self.function_list[self.function.id].instructions[setup]
+            .iter()
+            .rev()
+            .find_map(|instruction| match *instruction {
+                Instruction::BC { op_code, a, .. } | Instruction::AD { op_code, a, .. }
+                    if a == register =>
+                {
+                    Some(op_code)
+                }
+                _ => None,
+            })
+            .is_some_and(|op_code| matches!(op_code, OpCode::LOP_MOVE | OpCode::LOP_GETUPVAL))
+    }
+
+    // like `name_written_locals`, for results of a builtin call that debug info didn't name
+    fn name_builtin_results(
+        &self,
+        statements: &mut Vec<ast::Statement>,
+        first_statement: usize,
+        name: &str,
+    ) {
+        let ast::Statement::Assign(assign) = &mut statements[first_statement] else {
+            return;
+        };
+        let mut copies = Vec::new();
+        for lvalue in &mut assign.left {
+            let ast::LValue::Local(local) = lvalue else {
+                continue;
+            };
+            if !self.register_map.values().any(|register| register == local) {
+                continue;
+            }
+            let named_local = ast::RcLocal::new(ast::Local::new(Some(name.to_string())));
+            copies.push(
+                ast::Assign::new(vec![local.clone().into()], vec![named_local.clone().into()])
+                    .into(),
+            );
+            *local = named_local;
+        }
+        statements.extend(copies);
+    }
+
+    fn r#type(&self, r#type: BytecodeType) -> Type {
+        let converted_type = match r#type.tag {
+            TypeTag::Nil => Type::Nil,
+            TypeTag::Boolean => Type::Boolean,
+            TypeTag::Number => Type::Number,
+            TypeTag::String => Type::String,
+            TypeTag::Table => Type::Table {
+                indexer: Box::new((Type::Any, Type::Any)),
+                fields: Default::default(),
+            },
+            // the signature isn't recorded
+            TypeTag::Function => Type::Function(vec![Type::VarArg], vec![Type::VarArg]),
+            TypeTag::Thread => Type::Thread,
+            TypeTag::Userdata => Type::Userdata(None),
+            TypeTag::Vector => Type::Vector,
+            TypeTag::Buffer => Type::Buffer,
+            TypeTag::TaggedUserdata(index) => Type::Userdata(
+                self.userdata_types
+                    .get(&index)
+                    .and_then(|&name| self.string(name)),
+            ),
+            TypeTag::Any | TypeTag::Unknown(_) => return Type::Any,
+        };
+        if r#type.optional && converted_type != Type::Nil {
+            Type::Optional(Box::new(converted_type))
+        } else {
+            converted_type
+        }
+    }
+
+    // string table indices are 1-based, 0 means no string
+    fn string(&self, index: usize) -> Option<String> {
+        index
+            .checked_sub(1)
+            .and_then(|index| self.string_table.get(index))
+            .map(|string| String::from_utf8_lossy(string).into_owned())
+    }
+
+    fn unsupported(&mut self, instruction: &Instruction) -> ast::Statement {
+        let message = format!("unsupported instruction: {:?}", instruction);
+        self.warnings.push(message.clone());
+        ast::Comment::new(message).into()
+    }
+
     fn register(&mut self, index: usize) -> ast::RcLocal {
         self.register_map.entry(index).or_default().clone()
     }
 
+    // falls back to nil for constants that aren't literals, such as imports,
+    // or that don't exist in malformed bytecode
     fn constant(&mut self, index: usize) -> ast::Literal {
-        let converted_constant = match self.function_list[self.function.id]
-            .constants
-            .get(index)
-            .unwrap()
-        {
-            BytecodeConstant::Nil => ast::Literal::Nil,
-            BytecodeConstant::Boolean(v) => ast::Literal::Boolean(*v),
-            BytecodeConstant::Number(v) => ast::Literal::Number(*v),
-            BytecodeConstant::String(v) => {
-                // TODO: what does the official deserializer do if v == 0?
-                ast::Literal::String(self.string_table[*v - 1].clone())
+        if let Some(constant) = self.constant_map.get(&index) {
+            return constant.clone();
+        }
+        let converted_constant = self.literal(index).unwrap_or_else(|| {
+            self.warnings.push(format!("invalid constant K{}", index));
+            ast::Literal::Nil
+        });
+        self.constant_map.insert(index, converted_constant.clone());
+        converted_constant
+    }
+
+    fn literal(&self, index: usize) -> Option<ast::Literal> {
+        Some(
+            match self.function_list[self.function.id].constants.get(index)? {
+                BytecodeConstant::Nil => ast::Literal::Nil,
+                BytecodeConstant::Boolean(v) => ast::Literal::Boolean(*v),
+                BytecodeConstant::Number(v) => ast::Literal::Number(*v),
+                BytecodeConstant::String(v) => {
+                    ast::Literal::String(self.string_table.get(v.checked_sub(1)?)?.clone())
+                }
+                &BytecodeConstant::Vector(x, y, z, w) => self.vectors.literal(x, y, z, w),
+                _ => return None,
+            },
+        )
+    }
+
+    // fields without a constant value are assigned by the instructions that follow.
+    // a template that refers to invalid constants is left empty.
+    fn table_template(&mut self, index: usize) -> ast::Table {
+        // templates from before version 7 only have keys
+        let fields = match self.function_list[self.function.id].constants.get(index) {
+            Some(BytecodeConstant::TableWithConstants(fields)) => Some(fields.clone()),
+            Some(BytecodeConstant::Table(keys)) => {
+                Some(keys.iter().map(|&key| (key, None)).collect())
+            }
+            _ => None,
+        };
+        let fields = fields.and_then(|fields| {
+            let mut template = Vec::new();
+            for (key, value) in fields {
+                let key = self.literal(key)?;
+                // fields without a constant are set by the instructions that follow
+                if let Some(value) = value {
+                    template.push((Some(key.into()), self.literal(value)?.into()));
+                }
             }
-            BytecodeConstant::Vector(x, y, z, _) => ast::Literal::Vector(*x, *y, *z),
-            _ => unimplemented!(),
+            Some(template)
+        });
+        let Some(fields) = fields else {
+            self.warnings
+                .push(format!("invalid table template K{}", index));
+            return ast::Table::default();
         };
-        self.constant_map
-            .entry(index)
-            .or_insert(converted_constant)
-            .clone()
+        ast::Table(fields)
     }
 
     fn block_to_node(&self, insn_index: usize) -> NodeIndex {
@@ -0,0 +1,105 @@
+use ast::Traverse;
+use by_address::ByAddress;
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use triomphe::Arc;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum LineInfoMode {
+    #[default]
+    None,
+    /// Emit a `-- line N` comment before statements that start a new line
+    Comments,
+    /// Pad the output with empty lines so statements land on their original line
+    Padding,
+}
+
+pub(crate) type FunctionLines = FxHashMap<ByAddress<Arc<Mutex<ast::Function>>>, Vec<usize>>;
+
+// the padding markers start with a control character, which strings escape and names can't
+// contain, so that comments of the same form elsewhere in the output aren't taken for markers
+const PADDING_MARKER: &str = "\u{1}line ";
+
+pub(crate) fn insert_line_comments(
+    block: &mut ast::Block,
+    lines: &[usize],
+    function_lines: &FunctionLines,
+    mode: LineInfoMode,
+) {
+    let mut last_line = None;
+    for mut statement in std::mem::take(&mut block.0) {
+        statement.traverse_rvalues(&mut |rvalue| {
+            if let ast::RValue::Closure(closure) = rvalue {
+                let lines = function_lines
+                    .get(&closure.function)
+                    .map_or(&[][..], |lines| lines);
+                insert_line_comments(
+                    &mut closure.function.lock().body,
+                    lines,
+                    function_lines,
+                    mode,
+                );
+            }
+        });
+        match &statement {
+            ast::Statement::If(r#if) => {
+                insert_line_comments(&mut r#if.then_block.lock(), lines, function_lines, mode);
+                insert_line_comments(&mut r#if.else_block.lock(), lines, function_lines, mode);
+            }
+            ast::Statement::While(r#while) => {
+                insert_line_comments(&mut r#while.block.lock(), lines, function_lines, mode);
+            }
+            ast::Statement::Repeat(repeat) => {
+                insert_line_comments(&mut repeat.block.lock(), lines, function_lines, mode);
+            }
+            ast::Statement::NumericFor(numeric_for) => {
+                insert_line_comments(&mut numeric_for.block.lock(), lines, function_lines, mode);
+            }
+            ast::Statement::GenericFor(generic_for) => {
+                insert_line_comments(&mut generic_for.block.lock(), lines, function_lines, mode);
+            }
+            _ => {}
+        }
+
+        // inlining can leave instructions from earlier lines in a statement
+        let line = statement
+            .pc_range()
+            .and_then(|pc_range| lines.get(pc_range.clone())?.iter().min().copied());
+        if let Some(line) = line
+            && last_line != Some(line)
+        {
+            let text = match mode {
+                LineInfoMode::Padding => format!("{}{}", PADDING_MARKER, line),
+                _ => format!("line {}", line),
+            };
+            block.push(ast::Comment::new(text).into());
+            last_line = Some(line);
+        }
+        block.push(statement);
+    }
+}
+
+// replaces the padding markers with empty lines so that the statement that
+// follows each of them is on that line, when there is room for it
+pub(crate) fn pad_lines(source: &str) -> String {
+    let mut output = Vec::new();
+    let mut target_line = None;
+    for line in source.lines() {
+        if let Some(line_number) = line
+            .trim_start()
+            .strip_prefix("-- ")
+            .and_then(|marker| marker.strip_prefix(PADDING_MARKER))
+            .and_then(|n| n.parse::<usize>().ok())
+        {
+            target_line = Some(line_number);
+            continue;
+        }
+        if let Some(target_line) = target_line.take() {
+            while output.len() + 1 < target_line {
+                output.push("");
+            }
+        }
+        output.push(line);
+    }
+    output.join("\n")
+}
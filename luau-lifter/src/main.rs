@@ -1,10 +1,427 @@
-fn main() {
-    let file_name = std::env::args().nth(1).expect("expected exactly one file");
-    let key = std::env::args()
-        .nth(2)
-        .or_else(|| None)
-        .map(|s| if s == "-e" { 203 } else { panic!() })
-        .unwrap_or(1);
-    let bytecode = std::fs::read(file_name).expect("failed to read file");
-    println!("{}", luau_lifter::decompile_bytecode(&bytecode, key));
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use clap::Parser;
+use pipeline::isolate;
+use rayon::prelude::*;
+use walkdir::WalkDir;
+
+use luau_lifter::{
+    op_code_map::{suggest_op_code_map, OpCodeMap},
+    Budget, DecompileOptions, DecompileOutput, DiagnosticsLevel, FormattingOptions, FunctionReport,
+    FunctionStatus, IndentationMode, LineInfoMode, NamingMode, VectorConstructor, VectorOptions,
+};
+
+#[derive(Parser, Debug)]
+#[clap(about, version, author)]
+struct Args {
+    /// Bytecode files, or directories of them
+    #[clap(required = true)]
+    paths: Vec<PathBuf>,
+    /// Number of threads to use (0 = automatic)
+    #[clap(short, long, default_value_t = 0)]
+    threads: usize,
+    /// op = op * key % 256, or `auto` to detect it.
+    /// For Roblox client bytecode, use 203
+    #[clap(short, long, default_value = "1")]
+    key: EncodeKey,
+    /// Walk directories recursively
+    #[clap(short, long)]
+    recursive: bool,
+    /// Print the warnings, failures and timings of each file
+    #[clap(short, long)]
+    verbose: bool,
+    /// Write the outputs to this directory, mirroring the inputs, instead of next to them
+    #[clap(short, long, conflicts_with = "stdout")]
+    output: Option<PathBuf>,
+    /// Print the outputs instead of writing them
+    #[clap(long)]
+    stdout: bool,
+    /// Use an opcode map instead of the key, see `--suggest-op-map`
+    #[clap(long)]
+    op_map: Option<PathBuf>,
+    /// Print an opcode map suggested by the inputs and exit
+    #[clap(long)]
+    suggest_op_map: bool,
+    /// Write a disassembly instead of decompiling
+    #[clap(long)]
+    disassemble: bool,
+    #[clap(long, value_enum, default_value_t = LineInfoMode::None)]
+    line_info: LineInfoMode,
+    #[clap(long, value_enum, default_value_t = NamingMode::DebugInfo)]
+    naming: NamingMode,
+    /// Indent with this many spaces instead of tabs
+    #[clap(long)]
+    indent: Option<u8>,
+    /// What to emit for functions that fail to decompile
+    #[clap(long, value_enum, default_value_t = DiagnosticsLevel::None)]
+    diagnostics: DiagnosticsLevel,
+    /// Iterations of the structuring passes per function (0 = unlimited)
+    #[clap(long)]
+    max_iterations: Option<usize>,
+    /// Seconds to spend structuring each function
+    #[clap(long)]
+    time_limit: Option<f64>,
+    /// The function vector constants are constructed with
+    #[clap(long)]
+    vector_constructor: Option<VectorConstructor>,
+    /// Vectors have four components
+    #[clap(long)]
+    vector4: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum EncodeKey {
+    Auto,
+    Key(u8),
+}
+
+impl FromStr for EncodeKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            _ => s
+                .parse()
+                .map(Self::Key)
+                .map_err(|_| "expected a key or `auto`".to_string()),
+        }
+    }
+}
+
+struct Input {
+    path: PathBuf,
+    // where the output goes under `--output`
+    relative: PathBuf,
+}
+
+struct FileReport {
+    input: PathBuf,
+    result: anyhow::Result<Processed>,
+}
+
+struct Processed {
+    // `None` when printing to stdout
+    output: Option<PathBuf>,
+    text: String,
+    // `None` for disassemblies
+    decompiled: Option<DecompileOutput>,
+    duration: Duration,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    luau_lifter::install_panic_hook();
+    if args.threads != 0 {
+        let result = rayon::ThreadPoolBuilder::new()
+            .num_threads(args.threads)
+            .build_global();
+        if let Err(err) = result {
+            eprintln!("error: failed to start the thread pool: {}", err);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let mut success = true;
+    let mut inputs = Vec::new();
+    for path in &args.paths {
+        if let Err(err) = collect_inputs(path, args.recursive, &mut inputs) {
+            eprintln!("error: {:#}", err);
+            success = false;
+        }
+    }
+
+    if args.suggest_op_map {
+        let mut samples = Vec::new();
+        for input in &inputs {
+            match fs::read(&input.path) {
+                Ok(bytecode) => samples.push(bytecode),
+                Err(err) => {
+                    eprintln!("error: failed to read {}: {}", input.path.display(), err);
+                    success = false;
+                }
+            }
+        }
+        print!("{}", suggest_op_code_map(samples.iter().map(Vec::as_slice)));
+        return if success {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        };
+    }
+
+    let options = match decompile_options(&args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("error: {:#}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // inputs with the same name in different directories, or with different extensions,
+    // would overwrite each other's output
+    let mut outputs = HashMap::new();
+    inputs.retain(|input| {
+        let Some(output) = output_path(&args, input) else {
+            return true;
+        };
+        match outputs.entry(output) {
+            Entry::Vacant(entry) => {
+                entry.insert(input.path.clone());
+                true
+            }
+            Entry::Occupied(entry) => {
+                eprintln!(
+                    "error: {} and {} would both be written to {}",
+                    entry.get().display(),
+                    input.path.display(),
+                    entry.key().display()
+                );
+                success = false;
+                false
+            }
+        }
+    });
+
+    let start = Instant::now();
+    let reports = inputs
+        .par_iter()
+        .map(|input| FileReport {
+            input: input.path.clone(),
+            // a panic outside of the isolated functions fails the file, not the run
+            result: isolate(false, || process(&args, &options, input))
+                .unwrap_or_else(|panic| Err(anyhow::anyhow!("panicked at '{}'", panic))),
+        })
+        .collect::<Vec<_>>();
+
+    let mut decompiled = 0;
+    let mut incomplete = 0;
+    let mut failed = 0;
+    for report in &reports {
+        match &report.result {
+            Ok(processed) => {
+                if args.stdout {
+                    if reports.len() > 1 {
+                        println!("-- {}", report.input.display());
+                    }
+                    println!("{}", processed.text);
+                }
+                let complete = processed
+                    .decompiled
+                    .as_ref()
+                    .is_none_or(DecompileOutput::is_complete);
+                if complete {
+                    decompiled += 1;
+                } else {
+                    incomplete += 1;
+                }
+                eprintln!("{}", summary(&report.input, processed, args.verbose));
+            }
+            Err(err) => {
+                failed += 1;
+                eprintln!("failed {}: {:#}", report.input.display(), err);
+            }
+        }
+    }
+    eprintln!(
+        "{} decompiled, {} incomplete, {} failed in {:?}",
+        decompiled,
+        incomplete,
+        failed,
+        start.elapsed()
+    );
+
+    if success && incomplete == 0 && failed == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn collect_inputs(path: &Path, recursive: bool, inputs: &mut Vec<Input>) -> anyhow::Result<()> {
+    let metadata =
+        fs::metadata(path).with_context(|| format!("failed to read {}", path.display()))?;
+    if !metadata.is_dir() {
+        inputs.push(Input {
+            path: path.to_path_buf(),
+            relative: PathBuf::from(path.file_name().unwrap_or(path.as_os_str())),
+        });
+        return Ok(());
+    }
+
+    let walker = WalkDir::new(path)
+        .max_depth(if recursive { usize::MAX } else { 1 })
+        .sort_by_file_name();
+    for entry in walker {
+        let entry = entry.with_context(|| format!("failed to walk {}", path.display()))?;
+        // skip the outputs of earlier runs
+        if !entry.file_type().is_file()
+            || entry
+                .path()
+                .extension()
+                .is_some_and(|extension| extension == "lua" || extension == "asm")
+        {
+            continue;
+        }
+        inputs.push(Input {
+            path: entry.path().to_path_buf(),
+            relative: entry.path().strip_prefix(path).unwrap().to_path_buf(),
+        });
+    }
+    Ok(())
+}
+
+fn decompile_options(args: &Args) -> anyhow::Result<DecompileOptions> {
+    let op_code_map = match &args.op_map {
+        Some(path) => {
+            let source = fs::read_to_string(path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            Some(OpCodeMap::parse(&source).context("failed to parse opcode map")?)
+        }
+        None => None,
+    };
+    let mut budget = Budget::default();
+    if let Some(max_iterations) = args.max_iterations {
+        budget.max_iterations = (max_iterations != 0).then_some(max_iterations);
+    }
+    budget.time_limit = args.time_limit.map(Duration::from_secs_f64);
+
+    Ok(DecompileOptions {
+        encode_key: match args.key {
+            EncodeKey::Auto => None,
+            EncodeKey::Key(key) => Some(key),
+        },
+        op_code_map,
+        naming: args.naming,
+        formatting: FormattingOptions {
+            indentation: args
+                .indent
+                .map_or(IndentationMode::Tab, IndentationMode::Spaces),
+            line_info: args.line_info,
+        },
+        vectors: VectorOptions {
+            constructor: args.vector_constructor.clone().unwrap_or_default(),
+            four_wide: args.vector4,
+        },
+        budget,
+        diagnostics: args.diagnostics,
+        ..Default::default()
+    })
+}
+
+fn process(args: &Args, options: &DecompileOptions, input: &Input) -> anyhow::Result<Processed> {
+    let start = Instant::now();
+    let bytecode = fs::read(&input.path).context("failed to read file")?;
+    let (text, decompiled) = if args.disassemble {
+        let text = match &options.op_code_map {
+            Some(op_code_map) => luau_lifter::disassemble_with_op_code_map(&bytecode, op_code_map),
+            None => luau_lifter::disassemble(&bytecode, options.encode_key),
+        }?;
+        (text, None)
+    } else {
+        let mut output = luau_lifter::decompile(&bytecode, options)?;
+        (std::mem::take(&mut output.source), Some(output))
+    };
+    let duration = start.elapsed();
+
+    let output = output_path(args, input);
+    if let Some(output) = &output {
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        fs::write(output, &text)
+            .with_context(|| format!("failed to write {}", output.display()))?;
+    }
+
+    Ok(Processed {
+        output,
+        text: if args.stdout { text } else { String::new() },
+        decompiled,
+        duration,
+    })
+}
+
+// `None` when printing to stdout
+fn output_path(args: &Args, input: &Input) -> Option<PathBuf> {
+    if args.stdout {
+        return None;
+    }
+    let extension = if args.disassemble { "asm" } else { "lua" };
+    let mut output = match &args.output {
+        Some(directory) => directory.join(&input.relative),
+        None => input.path.clone(),
+    }
+    .with_extension(extension);
+    // never overwrite the input
+    if output == input.path {
+        output = output.with_extension(format!("dec.{}", extension));
+    }
+    Some(output)
+}
+
+fn summary(input: &Path, processed: &Processed, verbose: bool) -> String {
+    let mut summary = String::new();
+    let status = match &processed.decompiled {
+        Some(decompiled) if !decompiled.is_complete() => "incomplete",
+        _ => "ok",
+    };
+    write!(summary, "{} {}", status, input.display()).unwrap();
+    if let Some(output) = &processed.output {
+        write!(summary, " -> {}", output.display()).unwrap();
+    }
+    let Some(decompiled) = &processed.decompiled else {
+        write!(summary, " ({:?})", processed.duration).unwrap();
+        return summary;
+    };
+
+    write!(
+        summary,
+        " ({} functions, {} failed, {} warnings, {:?})",
+        decompiled.functions.len(),
+        decompiled.failures().count(),
+        decompiled.warnings().count(),
+        processed.duration
+    )
+    .unwrap();
+    if verbose {
+        for function in &decompiled.functions {
+            if let FunctionStatus::Failed { message } = &function.status {
+                write!(summary, "\n  {} failed: {}", describe(function), message).unwrap();
+            }
+        }
+        for (function, warning) in decompiled.warnings() {
+            write!(summary, "\n  {}: {}", describe(function), warning).unwrap();
+        }
+        let timing = &decompiled.timing;
+        write!(
+            summary,
+            "\n  deserialize {:?}, lift {:?}, decompile {:?}",
+            timing.deserialize, timing.lift, timing.decompile
+        )
+        .unwrap();
+    }
+    summary
+}
+
+fn describe(function: &FunctionReport) -> String {
+    format!(
+        "function {}{} (line {})",
+        function.id,
+        function
+            .name
+            .as_ref()
+            .map(|name| format!(" {:?}", name))
+            .unwrap_or_default(),
+        function.line_defined
+    )
 }
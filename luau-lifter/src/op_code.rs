@@ -1,7 +1,7 @@
 use num_enum::TryFromPrimitive;
 
 #[repr(u8)]
-#[derive(Debug, TryFromPrimitive, Eq, PartialEq, Copy, Clone)]
+#[derive(Debug, TryFromPrimitive, Eq, PartialEq, Hash, Copy, Clone)]
 #[allow(non_camel_case_types)]
 pub enum OpCode {
     // NOP: noop
@@ -368,3 +368,35 @@ pub enum OpCode {
     // Enum entry for number of opcodes, not a valid opcode by itself!
     LOP__COUNT,
 }
+
+impl OpCode {
+    // the instruction is followed by an aux word
+    pub fn has_aux(self) -> bool {
+        matches!(
+            self,
+            OpCode::LOP_GETGLOBAL
+                | OpCode::LOP_SETGLOBAL
+                | OpCode::LOP_GETIMPORT
+                | OpCode::LOP_GETTABLEKS
+                | OpCode::LOP_SETTABLEKS
+                | OpCode::LOP_NAMECALL
+                | OpCode::LOP_JUMPIFEQ
+                | OpCode::LOP_JUMPIFLE
+                | OpCode::LOP_JUMPIFLT
+                | OpCode::LOP_JUMPIFNOTEQ
+                | OpCode::LOP_JUMPIFNOTLE
+                | OpCode::LOP_JUMPIFNOTLT
+                | OpCode::LOP_NEWTABLE
+                | OpCode::LOP_SETLIST
+                | OpCode::LOP_FORGLOOP
+                | OpCode::LOP_LOADKX
+                | OpCode::LOP_FASTCALL2
+                | OpCode::LOP_FASTCALL2K
+                | OpCode::LOP_FASTCALL3
+                | OpCode::LOP_JUMPXEQKNIL
+                | OpCode::LOP_JUMPXEQKB
+                | OpCode::LOP_JUMPXEQKN
+                | OpCode::LOP_JUMPXEQKS
+        )
+    }
+}
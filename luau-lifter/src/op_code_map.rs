@@ -0,0 +1,364 @@
+use std::{collections::HashMap, fmt};
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    deserializer::{self, bytecode::Bytecode, constant::Constant, function::Function},
+    op_code::OpCode,
+};
+
+// maps raw opcode bytes to opcodes, for builds that don't use the standard numbering
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpCodeMap {
+    op_codes: [Option<OpCode>; 256],
+}
+
+impl Default for OpCodeMap {
+    fn default() -> Self {
+        Self {
+            op_codes: [None; 256],
+        }
+    }
+}
+
+impl OpCodeMap {
+    // op = op * key % 256
+    pub fn from_key(encode_key: u8) -> Self {
+        let mut map = Self::default();
+        for raw_op_code in 0..=u8::MAX {
+            match raw_op_code.wrapping_mul(encode_key) {
+                97 => map.insert(raw_op_code, OpCode::LOP_NOP),
+                op_code => {
+                    if let Ok(op_code) = OpCode::try_from(op_code)
+                        && op_code != OpCode::LOP__COUNT
+                    {
+                        map.insert(raw_op_code, op_code);
+                    }
+                }
+            }
+        }
+        map
+    }
+
+    pub fn get(&self, raw_op_code: u8) -> Option<OpCode> {
+        self.op_codes[raw_op_code as usize]
+    }
+
+    pub fn insert(&mut self, raw_op_code: u8, op_code: OpCode) {
+        self.op_codes[raw_op_code as usize] = Some(op_code);
+    }
+
+    /// Parses either a JSON object (`{"12": "GETIMPORT", ...}`) or lines of `<raw> <opcode>`.
+    /// Raw opcodes can be decimal or `0x` hexadecimal, `#` starts a comment and the
+    /// `LOP_` prefix of opcode names is optional.
+    pub fn parse(source: &str) -> Result<Self, OpCodeMapError> {
+        let entries = if source.trim_start().starts_with('{') {
+            serde_json::from_str::<HashMap<String, String>>(source)
+                .map_err(|err| OpCodeMapError::Json(err.to_string()))?
+                .into_iter()
+                .map(|(raw_op_code, op_code)| (0, raw_op_code, op_code))
+                .collect::<Vec<_>>()
+        } else {
+            let mut entries = Vec::new();
+            for (index, line) in source.lines().enumerate() {
+                let line = line.split('#').next().unwrap().trim();
+                if line.is_empty() {
+                    continue;
+                }
+                match line.split_whitespace().collect::<Vec<_>>()[..] {
+                    [raw_op_code, op_code] => {
+                        entries.push((index + 1, raw_op_code.to_owned(), op_code.to_owned()))
+                    }
+                    _ => return Err(OpCodeMapError::MalformedLine(index + 1)),
+                }
+            }
+            entries
+        };
+
+        let mut map = Self::default();
+        for (line, raw_op_code, op_code) in entries {
+            let raw_op_code = parse_raw_op_code(&raw_op_code)
+                .ok_or(OpCodeMapError::InvalidRawOpCode { line, raw_op_code })?;
+            let op_code = op_code_from_name(&op_code)
+                .ok_or(OpCodeMapError::UnknownOpCode { line, op_code })?;
+            if map.get(raw_op_code).is_some() {
+                return Err(OpCodeMapError::DuplicateRawOpCode { line, raw_op_code });
+            }
+            map.insert(raw_op_code, op_code);
+        }
+        Ok(map)
+    }
+}
+
+// the text format accepted by `OpCodeMap::parse`
+impl fmt::Display for OpCodeMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (raw_op_code, op_code) in self.op_codes.iter().enumerate() {
+            if let Some(op_code) = op_code {
+                writeln!(f, "{:#04x} {}", raw_op_code, op_code_name(*op_code))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_raw_op_code(raw_op_code: &str) -> Option<u8> {
+    match raw_op_code.strip_prefix("0x") {
+        Some(hex) => u8::from_str_radix(hex, 16).ok(),
+        None => raw_op_code.parse().ok(),
+    }
+}
+
+fn op_code_name(op_code: OpCode) -> String {
+    format!("{:?}", op_code)
+        .trim_start_matches("LOP_")
+        .to_owned()
+}
+
+fn op_code_from_name(name: &str) -> Option<OpCode> {
+    let name = name.strip_prefix("LOP_").unwrap_or(name);
+    (0..OpCode::LOP__COUNT as u8)
+        .filter_map(|op_code| OpCode::try_from(op_code).ok())
+        .find(|&op_code| op_code_name(op_code).eq_ignore_ascii_case(name))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OpCodeMapError {
+    Json(String),
+    MalformedLine(usize),
+    InvalidRawOpCode { line: usize, raw_op_code: String },
+    UnknownOpCode { line: usize, op_code: String },
+    DuplicateRawOpCode { line: usize, raw_op_code: u8 },
+}
+
+impl fmt::Display for OpCodeMapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Json(err) => write!(f, "invalid json: {}", err),
+            Self::MalformedLine(line) => {
+                write!(f, "expected `<raw opcode> <opcode>` on line {}", line)
+            }
+            Self::InvalidRawOpCode { line, raw_op_code } => {
+                write!(f, "invalid raw opcode `{}` on line {}", raw_op_code, line)
+            }
+            Self::UnknownOpCode { line, op_code } => {
+                write!(f, "unknown opcode `{}` on line {}", op_code, line)
+            }
+            Self::DuplicateRawOpCode { line, raw_op_code } => {
+                write!(
+                    f,
+                    "raw opcode {:#04x} is mapped twice on line {}",
+                    raw_op_code, line
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for OpCodeMapError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuggestedOpCode {
+    pub op_code: OpCode,
+    pub raw_op_code: u8,
+    // how many times the evidence pointed at the raw opcode, out of all evidence for the opcode
+    pub votes: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpCodeMapSuggestion {
+    pub op_codes: Vec<SuggestedOpCode>,
+    // raw opcodes that are almost always followed by a string constant index,
+    // so they are likely GETGLOBAL, SETGLOBAL, GETTABLEKS, SETTABLEKS or NAMECALL
+    pub string_aux: Vec<u8>,
+}
+
+impl OpCodeMapSuggestion {
+    pub fn op_code_map(&self) -> OpCodeMap {
+        let mut map = OpCodeMap::default();
+        for suggested in &self.op_codes {
+            map.insert(suggested.raw_op_code, suggested.op_code);
+        }
+        map
+    }
+}
+
+// the text format accepted by `OpCodeMap::parse`, with the evidence in comments
+impl fmt::Display for OpCodeMapSuggestion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for suggested in &self.op_codes {
+            writeln!(
+                f,
+                "{:#04x} {:<16} # {}/{}",
+                suggested.raw_op_code,
+                op_code_name(suggested.op_code),
+                suggested.votes,
+                suggested.total
+            )?;
+        }
+        if !self.string_aux.is_empty() {
+            writeln!(
+                f,
+                "# string constant aux (GETGLOBAL, SETGLOBAL, GETTABLEKS, SETTABLEKS or NAMECALL): {}",
+                self.string_aux
+                    .iter()
+                    .map(|raw_op_code| format!("{:#04x}", raw_op_code))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Statistics {
+    votes: FxHashMap<OpCode, FxHashMap<u8, usize>>,
+    // raw opcode -> (followed by a string constant index, occurrences)
+    string_aux: FxHashMap<u8, (usize, usize)>,
+}
+
+impl Statistics {
+    fn vote(&mut self, op_code: OpCode, raw_op_code: u8) {
+        *self
+            .votes
+            .entry(op_code)
+            .or_default()
+            .entry(raw_op_code)
+            .or_default() += 1;
+    }
+}
+
+fn op(word: u32) -> u8 {
+    (word & 0xFF) as u8
+}
+
+fn a(word: u32) -> u8 {
+    ((word >> 8) & 0xFF) as u8
+}
+
+fn d(word: u32) -> usize {
+    (word >> 16) as usize
+}
+
+// suggests a mapping from properties that hold for any bytecode produced by the
+// Luau compiler, such as every function ending in RETURN. the samples must
+// have been compiled by the same build.
+pub fn suggest_op_code_map<'a>(samples: impl IntoIterator<Item = &'a [u8]>) -> OpCodeMapSuggestion {
+    // every raw opcode decodes to NOP, so that the structure of the chunk can be
+    // read without knowing the mapping or which instructions carry an aux word
+    let mut nop_map = OpCodeMap::default();
+    for raw_op_code in 0..=u8::MAX {
+        nop_map.insert(raw_op_code, OpCode::LOP_NOP);
+    }
+
+    let mut statistics = Statistics::default();
+    for sample in samples {
+        if let Ok(Bytecode::Chunk(chunk)) =
+            deserializer::deserialize_with_op_code_map(sample, &nop_map)
+        {
+            for function in &chunk.functions {
+                collect_statistics(function, &chunk.functions, &mut statistics);
+            }
+        }
+    }
+
+    let mut suggestion = OpCodeMapSuggestion::default();
+    for (op_code, votes) in statistics.votes {
+        let total = votes.values().sum();
+        let (&raw_op_code, &votes) = votes
+            .iter()
+            .max_by_key(|&(&raw_op_code, &votes)| (votes, std::cmp::Reverse(raw_op_code)))
+            .unwrap();
+        // the evidence is noisy since aux words look like instructions too
+        if votes * 2 > total {
+            suggestion.op_codes.push(SuggestedOpCode {
+                op_code,
+                raw_op_code,
+                votes,
+                total,
+            });
+        }
+    }
+    // a raw opcode can only map to one opcode, keep the one with the most support
+    suggestion.op_codes.sort_by(|a, b| {
+        (b.votes * a.total)
+            .cmp(&(a.votes * b.total))
+            .then(b.votes.cmp(&a.votes))
+    });
+    let mut taken = [false; 256];
+    suggestion
+        .op_codes
+        .retain(|suggested| !std::mem::replace(&mut taken[suggested.raw_op_code as usize], true));
+    suggestion
+        .op_codes
+        .sort_by_key(|suggested| suggested.raw_op_code);
+
+    suggestion.string_aux = statistics
+        .string_aux
+        .into_iter()
+        .filter(|&(raw_op_code, (string_aux, occurrences))| {
+            !taken[raw_op_code as usize] && occurrences >= 4 && string_aux * 10 >= occurrences * 9
+        })
+        .map(|(raw_op_code, _)| raw_op_code)
+        .collect();
+    suggestion.string_aux.sort_unstable();
+    suggestion
+}
+
+fn collect_statistics(function: &Function, functions: &[Function], statistics: &mut Statistics) {
+    let code = &function.code;
+    let Some(&last) = code.last() else {
+        return;
+    };
+    statistics.vote(OpCode::LOP_RETURN, op(last));
+    let prepares_varargs = function.is_vararg && a(code[0]) == function.num_parameters;
+    if prepares_varargs {
+        statistics.vote(OpCode::LOP_PREPVARARGS, op(code[0]));
+    }
+
+    for (pc, &word) in code.iter().enumerate() {
+        let next = code.get(pc + 1).copied();
+        let constant = function.constants.get(d(word));
+
+        if let Some(next) = next {
+            let string_aux = statistics.string_aux.entry(op(word)).or_default();
+            string_aux.1 += 1;
+            if let Some(Constant::String(_)) = function.constants.get(next as usize) {
+                string_aux.0 += 1;
+            }
+        }
+
+        // the aux word of GETIMPORT is the import itself
+        if let (Some(&Constant::Import(import)), Some(next)) = (constant, next)
+            && import == next as usize
+        {
+            statistics.vote(OpCode::LOP_GETIMPORT, op(word));
+            continue;
+        }
+
+        // closures are followed by a CAPTURE for every upvalue
+        let closure = match constant {
+            Some(&Constant::Closure(closure)) => Some((OpCode::LOP_DUPCLOSURE, closure)),
+            _ => function
+                .functions
+                .get(d(word))
+                .map(|&closure| (OpCode::LOP_NEWCLOSURE, closure)),
+        };
+        // PREPVARARGS and CAPTURE often have a D of 0, which looks like the first child
+        if let Some((op_code, closure)) = closure
+            && !(pc == 0 && prepares_varargs)
+            && let Some(closure) = functions.get(closure)
+            && closure.num_upvalues != 0
+            && let Some(captures) = code.get(pc + 1..pc + 1 + closure.num_upvalues as usize)
+            && op(captures[0]) != op(word)
+            && captures
+                .iter()
+                .all(|&capture| op(capture) == op(captures[0]) && a(capture) <= 2)
+        {
+            statistics.vote(op_code, op(word));
+            statistics.vote(OpCode::LOP_CAPTURE, op(captures[0]));
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use std::sync::Arc;
+
+use ast::formatter::IndentationMode;
+
+use crate::{line_info::LineInfoMode, op_code_map::OpCodeMap, vector::VectorOptions};
+
+pub use pipeline::{Budget, Passes};
+
+#[derive(Debug, Clone)]
+pub struct DecompileOptions {
+    /// `None` detects the key with `detect_encode_key`, which deserializes the bytecode
+    /// once per candidate key, so it's opt-in
+    pub encode_key: Option<u8>,
+    /// Takes precedence over `encode_key`
+    pub op_code_map: Option<OpCodeMap>,
+    pub naming: NamingMode,
+    pub formatting: FormattingOptions,
+    pub vectors: VectorOptions,
+    pub passes: Passes,
+    pub budget: Budget,
+    pub diagnostics: DiagnosticsLevel,
+    /// The pool to decompile functions on, `None` uses the current pool
+    pub thread_pool: Option<Arc<rayon::ThreadPool>>,
+}
+
+impl Default for DecompileOptions {
+    fn default() -> Self {
+        Self {
+            encode_key: Some(1),
+            op_code_map: None,
+            naming: NamingMode::default(),
+            formatting: FormattingOptions::default(),
+            vectors: VectorOptions::default(),
+            passes: Passes::default(),
+            budget: Budget::default(),
+            diagnostics: DiagnosticsLevel::default(),
+            thread_pool: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum NamingMode {
+    /// Keep the names from debug info and generate the rest
+    #[default]
+    DebugInfo,
+    /// Generate every name, ignoring debug info
+    Generated,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FormattingOptions {
+    pub indentation: IndentationMode,
+    pub line_info: LineInfoMode,
+}
+
+/// What to emit in place of the body of a function that failed to decompile
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DiagnosticsLevel {
+    /// Only a `-- failed to decompile` comment
+    #[default]
+    None,
+    /// The function, the panic message and a disassembly of the function
+    Details,
+    /// Like `details`, with a backtrace of the panic. Backtraces are only recorded once
+    /// `install_panic_hook` has been called
+    Backtrace,
+}
@@ -0,0 +1,87 @@
+use std::{fmt, time::Duration};
+
+use crate::deserializer::error::DeserializeError;
+
+#[derive(Debug, Clone)]
+pub struct DecompileOutput {
+    pub source: String,
+    /// In the order the functions appear in the bytecode
+    pub functions: Vec<FunctionReport>,
+    pub timing: Timing,
+}
+
+impl DecompileOutput {
+    pub fn is_complete(&self) -> bool {
+        self.functions
+            .iter()
+            .all(|function| function.status == FunctionStatus::Decompiled)
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &FunctionReport> {
+        self.functions
+            .iter()
+            .filter(|function| function.status != FunctionStatus::Decompiled)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = (&FunctionReport, &str)> {
+        self.functions.iter().flat_map(|function| {
+            function
+                .warnings
+                .iter()
+                .map(move |warning| (function, warning.as_str()))
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FunctionReport {
+    pub id: usize,
+    /// The debug name, if the function has one
+    pub name: Option<String>,
+    pub line_defined: usize,
+    pub status: FunctionStatus,
+    pub warnings: Vec<String>,
+    /// Time spent lifting and structuring this function
+    pub duration: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FunctionStatus {
+    Decompiled,
+    /// The decompiler panicked, the body is replaced with a comment, see `DiagnosticsLevel`
+    Failed {
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Timing {
+    pub deserialize: Duration,
+    pub lift: Duration,
+    pub decompile: Duration,
+    pub total: Duration,
+}
+
+#[derive(Debug)]
+pub enum DecompileError {
+    Deserialize(DeserializeError),
+    /// The bytecode holds a compile error in place of a chunk
+    Compile(String),
+}
+
+impl fmt::Display for DecompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Deserialize(err) => write!(f, "{}", err),
+            Self::Compile(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for DecompileError {}
+
+impl From<DeserializeError> for DecompileError {
+    fn from(err: DeserializeError) -> Self {
+        Self::Deserialize(err)
+    }
+}
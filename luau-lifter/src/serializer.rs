@@ -0,0 +1,293 @@
+use std::fmt;
+
+use crate::{
+    deserializer::{
+        bytecode::Bytecode,
+        chunk::Chunk,
+        constant::{self, Constant},
+        function::Function,
+        type_info::{self, TypeInfo},
+    },
+    instruction::Instruction,
+    op_code::OpCode,
+    op_code_map::OpCodeMap,
+};
+
+// writes bytecode that `Bytecode::parse` reads back with the same opcode map.
+// fails if an opcode used by the chunk isn't in the map, like with an even encode key.
+pub fn serialize(bytecode: &Bytecode, op_code_map: &OpCodeMap) -> Result<Vec<u8>, SerializeError> {
+    // the lowest raw opcode that decodes to each opcode
+    let mut raw_op_codes = [None; 256];
+    for raw_op_code in (0..=u8::MAX).rev() {
+        if let Some(op_code) = op_code_map.get(raw_op_code) {
+            raw_op_codes[op_code as usize] = Some(raw_op_code);
+        }
+    }
+    let mut serializer = Serializer {
+        output: Vec::new(),
+        raw_op_codes,
+    };
+    match bytecode {
+        Bytecode::Error(msg) => {
+            serializer.write_u8(0);
+            serializer.output.extend_from_slice(msg.as_bytes());
+        }
+        Bytecode::Chunk(chunk) => serializer.write_chunk(chunk)?,
+    }
+    Ok(serializer.output)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SerializeError {
+    UnmappedOpCode {
+        function: usize,
+        pc: usize,
+        op_code: OpCode,
+    },
+}
+
+impl fmt::Display for SerializeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnmappedOpCode {
+                function,
+                pc,
+                op_code,
+            } => write!(
+                f,
+                "no raw opcode maps to {:?} at pc {} in function {}",
+                op_code, pc, function
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SerializeError {}
+
+struct Serializer {
+    output: Vec<u8>,
+    raw_op_codes: [Option<u8>; 256],
+}
+
+impl Serializer {
+    fn write_u8(&mut self, value: u8) {
+        self.output.push(value);
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.output.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_leb128(&mut self, mut value: usize) {
+        loop {
+            let byte = (value & 0x7F) as u8;
+            value >>= 7;
+            if value == 0 {
+                self.write_u8(byte);
+                break;
+            }
+            self.write_u8(byte | 0x80);
+        }
+    }
+
+    fn write_string(&mut self, string: &[u8]) {
+        self.write_leb128(string.len());
+        self.output.extend_from_slice(string);
+    }
+
+    fn write_chunk(&mut self, chunk: &Chunk) -> Result<(), SerializeError> {
+        self.write_u8(chunk.version);
+        if chunk.version >= 4 {
+            self.write_u8(chunk.types_version);
+        }
+        self.write_leb128(chunk.string_table.len());
+        for string in &chunk.string_table {
+            self.write_string(string);
+        }
+        if chunk.types_version == 3 {
+            let mut userdata_types = chunk.userdata_types.iter().collect::<Vec<_>>();
+            userdata_types.sort_unstable();
+            for (&index, &name) in userdata_types {
+                self.write_u8(index + 1);
+                self.write_leb128(name);
+            }
+            self.write_u8(0);
+        }
+        self.write_leb128(chunk.functions.len());
+        for (function_id, function) in chunk.functions.iter().enumerate() {
+            self.write_function(function, function_id, chunk.version, chunk.types_version)?;
+        }
+        self.write_leb128(chunk.main);
+        Ok(())
+    }
+
+    fn write_function(
+        &mut self,
+        function: &Function,
+        function_id: usize,
+        version: u8,
+        types_version: u8,
+    ) -> Result<(), SerializeError> {
+        self.write_u8(function.max_stack_size);
+        self.write_u8(function.num_parameters);
+        self.write_u8(function.num_upvalues);
+        self.write_u8(function.is_vararg.into());
+        if version >= 4 {
+            self.write_u8(function.flags);
+            self.write_type_info(&function.type_info, types_version);
+        }
+
+        self.write_leb128(function.instructions.len());
+        let mut instructions = function.instructions.iter().enumerate();
+        while let Some((pc, instruction)) = instructions.next() {
+            let op_code = instruction.op_code();
+            let raw_op_code =
+                self.raw_op_codes[op_code as usize].ok_or(SerializeError::UnmappedOpCode {
+                    function: function_id,
+                    pc,
+                    op_code,
+                })?;
+            self.write_u32(instruction.encode(raw_op_code));
+            match *instruction {
+                Instruction::BC { op_code, aux, .. } | Instruction::AD { op_code, aux, .. }
+                    if op_code.has_aux() =>
+                {
+                    // the parser follows the instruction with a placeholder for the aux word
+                    instructions.next();
+                    self.write_u32(aux);
+                }
+                _ => {}
+            }
+        }
+
+        self.write_leb128(function.constants.len());
+        for constant in &function.constants {
+            self.write_constant(constant);
+        }
+        self.write_leb128(function.functions.len());
+        for &function in &function.functions {
+            self.write_leb128(function);
+        }
+        self.write_leb128(function.line_defined);
+        self.write_leb128(function.function_name);
+
+        match (
+            function.line_gap_log2,
+            &function.line_info_delta,
+            &function.abs_line_info_delta,
+        ) {
+            (Some(line_gap_log2), Some(line_info_delta), Some(abs_line_info_delta)) => {
+                self.write_u8(1);
+                self.write_u8(line_gap_log2);
+                self.output.extend_from_slice(line_info_delta);
+                for &delta in abs_line_info_delta {
+                    self.write_u32(delta);
+                }
+            }
+            _ => self.write_u8(0),
+        }
+
+        if function.locals.is_empty() && function.upvalue_names.is_empty() {
+            self.write_u8(0);
+        } else {
+            self.write_u8(1);
+            self.write_leb128(function.locals.len());
+            for local in &function.locals {
+                self.write_leb128(local.name);
+                self.write_leb128(local.range.start);
+                self.write_leb128(local.range.end);
+                self.write_u8(local.register);
+            }
+            self.write_leb128(function.upvalue_names.len());
+            for &name in &function.upvalue_names {
+                self.write_leb128(name);
+            }
+        }
+        Ok(())
+    }
+
+    fn write_type_info(&mut self, type_info: &TypeInfo, types_version: u8) {
+        let mut signature = Vec::new();
+        if !type_info.parameters.is_empty() {
+            signature.push(type_info::TYPE_FUNCTION);
+            signature.push(type_info.parameters.len() as u8);
+            signature.extend(type_info.parameters.iter().map(|r#type| r#type.encode()));
+        }
+
+        let mut inner = Self {
+            output: Vec::new(),
+            raw_op_codes: self.raw_op_codes,
+        };
+        match types_version {
+            1 => inner.output = signature,
+            2 | 3
+                if !signature.is_empty()
+                    || !type_info.upvalues.is_empty()
+                    || !type_info.locals.is_empty() =>
+            {
+                inner.write_leb128(signature.len());
+                inner.write_leb128(type_info.upvalues.len());
+                inner.write_leb128(type_info.locals.len());
+                inner.output.extend_from_slice(&signature);
+                for r#type in &type_info.upvalues {
+                    inner.write_u8(r#type.encode());
+                }
+                for local in &type_info.locals {
+                    inner.write_u8(local.r#type.encode());
+                    inner.write_u8(local.register);
+                    inner.write_leb128(local.range.start);
+                    inner.write_leb128(local.range.len());
+                }
+            }
+            _ => {}
+        }
+        self.write_string(&inner.output);
+    }
+
+    fn write_constant(&mut self, constant: &Constant) {
+        match *constant {
+            Constant::Nil => self.write_u8(constant::CONSTANT_NIL),
+            Constant::Boolean(value) => {
+                self.write_u8(constant::CONSTANT_BOOLEAN);
+                self.write_u8(value.into());
+            }
+            Constant::Number(value) => {
+                self.write_u8(constant::CONSTANT_NUMBER);
+                self.output.extend_from_slice(&value.to_le_bytes());
+            }
+            Constant::String(index) => {
+                self.write_u8(constant::CONSTANT_STRING);
+                self.write_leb128(index);
+            }
+            Constant::Import(import) => {
+                self.write_u8(constant::CONSTANT_IMPORT);
+                self.write_u32(import as u32);
+            }
+            Constant::Table(ref keys) => {
+                self.write_u8(constant::CONSTANT_TABLE);
+                self.write_leb128(keys.len());
+                for &key in keys {
+                    self.write_leb128(key);
+                }
+            }
+            Constant::TableWithConstants(ref fields) => {
+                self.write_u8(constant::CONSTANT_TABLE_WITH_CONSTANTS);
+                self.write_leb128(fields.len());
+                for &(key, value) in fields {
+                    self.write_leb128(key);
+                    self.write_u32(value.map_or(u32::MAX, |value| value as u32));
+                }
+            }
+            Constant::Closure(function) => {
+                self.write_u8(constant::CONSTANT_CLOSURE);
+                self.write_leb128(function);
+            }
+            Constant::Vector(x, y, z, w) => {
+                self.write_u8(constant::CONSTANT_VECTOR);
+                for component in [x, y, z, w] {
+                    self.output.extend_from_slice(&component.to_le_bytes());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use std::{convert::Infallible, fmt, str::FromStr};
+
+// the function vector constants are constructed with
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum VectorConstructor {
+    // Roblox
+    #[default]
+    Vector3New,
+    // the standalone Luau vector library
+    VectorCreate,
+    Custom(String),
+}
+
+impl VectorConstructor {
+    pub fn path(&self) -> &str {
+        match self {
+            Self::Vector3New => "Vector3.new",
+            Self::VectorCreate => "vector.create",
+            Self::Custom(path) => path,
+        }
+    }
+}
+
+impl FromStr for VectorConstructor {
+    type Err = Infallible;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        Ok(match path {
+            "Vector3.new" => Self::Vector3New,
+            "vector.create" => Self::VectorCreate,
+            _ => Self::Custom(path.to_string()),
+        })
+    }
+}
+
+impl fmt::Display for VectorConstructor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.path())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct VectorOptions {
+    pub constructor: VectorConstructor,
+    // the VM was built with `LUA_VECTOR_SIZE == 4`. the w component of constants
+    // is printed regardless if it's non-zero, so that it isn't lost.
+    pub four_wide: bool,
+}
+
+impl VectorOptions {
+    pub(crate) fn literal(&self, x: f32, y: f32, z: f32, w: f32) -> ast::Literal {
+        ast::Literal::Vector(ast::Vector {
+            constructor: self.constructor.path().to_string(),
+            x,
+            y,
+            z,
+            w: (self.four_wide || w != 0.0).then_some(w),
+        })
+    }
+}
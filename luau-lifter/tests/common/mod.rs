@@ -0,0 +1,87 @@
+// each test crate uses a different part of this
+#![allow(dead_code)]
+
+use luau_lifter::{
+    deserializer::{chunk::Chunk, constant::Constant, function::Function, type_info::TypeInfo},
+    instruction::Instruction,
+    op_code::OpCode,
+    op_code_map::OpCodeMap,
+};
+
+pub fn abc(op_code: OpCode, a: u8, b: u8, c: u8) -> u32 {
+    op_code as u32 | (a as u32) << 8 | (b as u32) << 16 | (c as u32) << 24
+}
+
+pub fn ad(op_code: OpCode, a: u8, d: i16) -> u32 {
+    op_code as u32 | (a as u32) << 8 | (d as u16 as u32) << 16
+}
+
+// like the deserializer, aux words go into the instruction followed by a placeholder
+pub fn instructions(code: &[u32]) -> Vec<Instruction> {
+    let op_code_map = OpCodeMap::from_key(1);
+    let mut instructions = Vec::new();
+    let mut words = code.iter();
+    while let Some(&word) = words.next() {
+        let instruction = match Instruction::parse(word, &op_code_map).unwrap() {
+            Instruction::BC {
+                op_code, a, b, c, ..
+            } if op_code.has_aux() => Instruction::BC {
+                op_code,
+                a,
+                b,
+                c,
+                aux: *words.next().unwrap(),
+            },
+            Instruction::AD { op_code, a, d, .. } if op_code.has_aux() => Instruction::AD {
+                op_code,
+                a,
+                d,
+                aux: *words.next().unwrap(),
+            },
+            instruction => {
+                instructions.push(instruction);
+                continue;
+            }
+        };
+        instructions.push(instruction);
+        instructions.push(Instruction::parse(0, &op_code_map).unwrap());
+    }
+    instructions
+}
+
+// a vararg function without debug or type info, `code` being encoded with key 1
+pub fn function(code: &[u32], constants: Vec<Constant>) -> Function {
+    Function {
+        max_stack_size: 8,
+        num_parameters: 0,
+        num_upvalues: 0,
+        is_vararg: true,
+        flags: 0,
+        code: code.to_vec(),
+        instructions: instructions(code),
+        constants,
+        functions: Vec::new(),
+        line_defined: 0,
+        function_name: 0,
+        line_gap_log2: None,
+        line_info_delta: None,
+        abs_line_info_delta: None,
+        type_info: TypeInfo::default(),
+        locals: Vec::new(),
+        upvalue_names: Vec::new(),
+    }
+}
+
+pub fn chunk(string_table: &[&str], functions: Vec<Function>, main: usize) -> Chunk {
+    Chunk {
+        version: 6,
+        types_version: 3,
+        string_table: string_table
+            .iter()
+            .map(|string| string.as_bytes().to_vec())
+            .collect(),
+        userdata_types: Default::default(),
+        functions,
+        main,
+    }
+}
@@ -0,0 +1,26 @@
+mod common;
+
+use common::{abc, chunk, function};
+use luau_lifter::{
+    deserializer::bytecode::Bytecode, disassemble, op_code::OpCode, op_code_map::OpCodeMap,
+    serializer::serialize,
+};
+
+#[test]
+fn names_fastcall_builtins() {
+    let code = [
+        abc(OpCode::LOP_FASTCALL1, 1, 0, 1),
+        abc(OpCode::LOP_FASTCALL1, 200, 0, 0),
+        abc(OpCode::LOP_RETURN, 0, 1, 0),
+    ];
+    let chunk = chunk(&[], vec![function(&code, Vec::new())], 0);
+    let bytecode = serialize(&Bytecode::Chunk(chunk), &OpCodeMap::from_key(1)).unwrap();
+    let disassembly = disassemble(&bytecode, Some(1)).unwrap();
+    let comments = disassembly
+        .lines()
+        .filter(|line| line.contains("FASTCALL1"))
+        .map(|line| line.split_once("; ").unwrap().1)
+        .collect::<Vec<_>>();
+    // unknown builtins fall back to their number
+    assert_eq!(comments, ["assert, to L0", "builtin 200, to L0"]);
+}
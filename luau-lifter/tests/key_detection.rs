@@ -0,0 +1,81 @@
+mod common;
+
+use common::{abc, ad, chunk, function};
+use luau_lifter::{
+    decompile_bytecode,
+    deserializer::{bytecode::Bytecode, constant::Constant},
+    detect_encode_key,
+    op_code::OpCode,
+    op_code_map::OpCodeMap,
+    serializer::serialize,
+};
+
+// for i = 1, 10 do if x then print(x.y) end end
+fn bytecode(key: u8) -> Vec<u8> {
+    // one name, the string constant 3
+    let print = 1 << 30 | 3 << 20;
+    let code = [
+        ad(OpCode::LOP_LOADN, 2, 1),
+        ad(OpCode::LOP_LOADN, 0, 10),
+        ad(OpCode::LOP_LOADN, 1, 1),
+        ad(OpCode::LOP_FORNPREP, 0, 11),
+        abc(OpCode::LOP_GETGLOBAL, 3, 0, 0),
+        1,
+        ad(OpCode::LOP_JUMPIFNOT, 3, 7),
+        ad(OpCode::LOP_GETIMPORT, 3, 0),
+        print,
+        abc(OpCode::LOP_GETGLOBAL, 4, 0, 0),
+        1,
+        abc(OpCode::LOP_GETTABLEKS, 4, 4, 0),
+        2,
+        abc(OpCode::LOP_CALL, 3, 2, 1),
+        ad(OpCode::LOP_FORNLOOP, 0, -11),
+        abc(OpCode::LOP_RETURN, 0, 1, 0),
+    ];
+    let constants = vec![
+        Constant::Import(print as usize),
+        Constant::String(2),
+        Constant::String(3),
+        Constant::String(1),
+    ];
+    let chunk = chunk(&["print", "x", "y"], vec![function(&code, constants)], 0);
+    serialize(&Bytecode::Chunk(chunk), &OpCodeMap::from_key(key)).unwrap()
+}
+
+#[test]
+fn detects_key_1() {
+    let detected = detect_encode_key(&bytecode(1)).unwrap();
+    assert_eq!(detected.key, 1);
+    assert!(detected.confidence > 0.0, "{:?}", detected);
+}
+
+#[test]
+fn detects_key_203() {
+    let detected = detect_encode_key(&bytecode(203)).unwrap();
+    assert_eq!(detected.key, 203);
+    assert!(detected.confidence > 0.0, "{:?}", detected);
+}
+
+#[test]
+fn detects_other_keys() {
+    let detected = detect_encode_key(&bytecode(77)).unwrap();
+    assert_eq!(detected.key, 77);
+}
+
+#[test]
+fn detected_key_decompiles() {
+    let bytecode = bytecode(203);
+    assert_eq!(
+        decompile_bytecode(&bytecode, None).unwrap(),
+        decompile_bytecode(&bytecode, Some(203)).unwrap()
+    );
+}
+
+#[test]
+fn rejects_invalid_bytecode() {
+    // an unsupported version can't be decoded with any key
+    let mut bytecode = bytecode(1);
+    bytecode[0] = 0xFF;
+    assert_eq!(detect_encode_key(&bytecode), None);
+    assert_eq!(detect_encode_key(&[]), None);
+}
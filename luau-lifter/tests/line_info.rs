@@ -0,0 +1,55 @@
+mod common;
+
+use common::{abc, ad, chunk, function};
+use luau_lifter::{
+    decompile_bytecode_with_line_info,
+    deserializer::{
+        bytecode::Bytecode,
+        constant::Constant,
+        function::{Function, FUNCTION_FLAG_NATIVE_MODULE},
+    },
+    op_code::OpCode,
+    op_code_map::OpCodeMap,
+    serializer::serialize,
+    LineInfoMode,
+};
+
+// print(), on line 3
+fn bytecode(flags: u8) -> Vec<u8> {
+    let print = 1 << 30 | 1 << 20;
+    let main = Function {
+        max_stack_size: 1,
+        flags,
+        line_gap_log2: Some(0),
+        line_info_delta: Some(vec![0; 4]),
+        abs_line_info_delta: Some(vec![3, 0, 0, 0]),
+        ..function(
+            &[
+                ad(OpCode::LOP_GETIMPORT, 0, 0),
+                print,
+                abc(OpCode::LOP_CALL, 0, 1, 1),
+                abc(OpCode::LOP_RETURN, 0, 1, 0),
+            ],
+            vec![Constant::Import(print as usize), Constant::String(1)],
+        )
+    };
+    let chunk = chunk(&["print"], vec![main], 0);
+    serialize(&Bytecode::Chunk(chunk), &OpCodeMap::from_key(1)).unwrap()
+}
+
+fn padded(bytecode: &[u8]) -> String {
+    decompile_bytecode_with_line_info(bytecode, Some(1), LineInfoMode::Padding).unwrap()
+}
+
+#[test]
+fn pads_statements_to_their_line() {
+    assert_eq!(padded(&bytecode(0)), "\n\nprint()");
+}
+
+#[test]
+fn pads_after_the_native_directive() {
+    assert_eq!(
+        padded(&bytecode(FUNCTION_FLAG_NATIVE_MODULE)),
+        "--!native\n\nprint()"
+    );
+}
@@ -0,0 +1,195 @@
+mod common;
+
+use common::{abc, ad, chunk, function};
+use luau_lifter::{
+    deserializer::{bytecode::Bytecode, constant::Constant, function::Function},
+    op_code::OpCode,
+    op_code_map::{suggest_op_code_map, OpCodeMap, OpCodeMapError},
+    serializer::serialize,
+};
+
+// raw = op * 37 + 11, which reaches every raw opcode once
+fn shuffled_op_code_map() -> OpCodeMap {
+    let mut op_code_map = OpCodeMap::default();
+    for op_code in 0..OpCode::LOP__COUNT as u8 {
+        op_code_map.insert(
+            op_code.wrapping_mul(37).wrapping_add(11),
+            OpCode::try_from(op_code).unwrap(),
+        );
+    }
+    op_code_map
+}
+
+// local a, b = 5, 6
+// print(function() return a end, function() return a, b end)
+fn sample(op_code_map: &OpCodeMap) -> Vec<u8> {
+    let print = 1 << 30 | 1 << 20;
+    let main = Function {
+        functions: vec![1, 2],
+        ..function(
+            &[
+                abc(OpCode::LOP_PREPVARARGS, 0, 0, 0),
+                ad(OpCode::LOP_LOADN, 0, 5),
+                ad(OpCode::LOP_LOADN, 1, 6),
+                ad(OpCode::LOP_GETIMPORT, 2, 0),
+                print,
+                ad(OpCode::LOP_NEWCLOSURE, 3, 0),
+                abc(OpCode::LOP_CAPTURE, 0, 0, 0),
+                ad(OpCode::LOP_DUPCLOSURE, 4, 2),
+                abc(OpCode::LOP_CAPTURE, 0, 0, 0),
+                abc(OpCode::LOP_CAPTURE, 0, 1, 0),
+                abc(OpCode::LOP_CALL, 2, 3, 1),
+                abc(OpCode::LOP_RETURN, 0, 1, 0),
+            ],
+            vec![
+                Constant::Import(print as usize),
+                Constant::String(1),
+                Constant::Closure(2),
+            ],
+        )
+    };
+    let first = Function {
+        num_upvalues: 1,
+        is_vararg: false,
+        ..function(
+            &[
+                abc(OpCode::LOP_GETUPVAL, 0, 0, 0),
+                abc(OpCode::LOP_RETURN, 0, 2, 0),
+            ],
+            Vec::new(),
+        )
+    };
+    let second = Function {
+        num_upvalues: 2,
+        is_vararg: false,
+        ..function(
+            &[
+                abc(OpCode::LOP_GETUPVAL, 0, 0, 0),
+                abc(OpCode::LOP_GETUPVAL, 1, 1, 0),
+                abc(OpCode::LOP_RETURN, 0, 3, 0),
+            ],
+            Vec::new(),
+        )
+    };
+    let chunk = chunk(&["print"], vec![main, first, second], 0);
+    serialize(&Bytecode::Chunk(chunk), op_code_map).unwrap()
+}
+
+#[test]
+fn parses_lines() {
+    let op_code_map = OpCodeMap::parse(
+        "# a comment\n\
+         \n\
+         0x16 RETURN\n\
+         12   LOP_GETIMPORT # with a trailing comment\n\
+         0xff namecall\n",
+    )
+    .unwrap();
+    assert_eq!(op_code_map.get(0x16), Some(OpCode::LOP_RETURN));
+    assert_eq!(op_code_map.get(12), Some(OpCode::LOP_GETIMPORT));
+    assert_eq!(op_code_map.get(0xFF), Some(OpCode::LOP_NAMECALL));
+    assert_eq!(op_code_map.get(0), None);
+}
+
+#[test]
+fn parses_json() {
+    let op_code_map = OpCodeMap::parse(r#"{"22": "RETURN", "0x0c": "LOP_GETIMPORT"}"#).unwrap();
+    assert_eq!(op_code_map.get(22), Some(OpCode::LOP_RETURN));
+    assert_eq!(op_code_map.get(12), Some(OpCode::LOP_GETIMPORT));
+}
+
+#[test]
+fn parses_its_own_output() {
+    for op_code_map in [OpCodeMap::from_key(203), shuffled_op_code_map()] {
+        assert_eq!(
+            OpCodeMap::parse(&op_code_map.to_string()).unwrap(),
+            op_code_map
+        );
+    }
+}
+
+#[test]
+fn rejects_malformed_maps() {
+    assert_eq!(
+        OpCodeMap::parse("1 RETURN\n2\n"),
+        Err(OpCodeMapError::MalformedLine(2))
+    );
+    assert_eq!(
+        OpCodeMap::parse("1 RETURN extra"),
+        Err(OpCodeMapError::MalformedLine(1))
+    );
+    assert_eq!(
+        OpCodeMap::parse("256 RETURN"),
+        Err(OpCodeMapError::InvalidRawOpCode {
+            line: 1,
+            raw_op_code: "256".to_string()
+        })
+    );
+    assert_eq!(
+        OpCodeMap::parse("1 NOP\n0xzz RETURN"),
+        Err(OpCodeMapError::InvalidRawOpCode {
+            line: 2,
+            raw_op_code: "0xzz".to_string()
+        })
+    );
+    assert_eq!(
+        OpCodeMap::parse("1 NOTANOPCODE"),
+        Err(OpCodeMapError::UnknownOpCode {
+            line: 1,
+            op_code: "NOTANOPCODE".to_string()
+        })
+    );
+    assert_eq!(
+        OpCodeMap::parse("1 NOP\n0x01 RETURN"),
+        Err(OpCodeMapError::DuplicateRawOpCode {
+            line: 2,
+            raw_op_code: 1
+        })
+    );
+    assert!(matches!(
+        OpCodeMap::parse(r#"{"1": 2}"#),
+        Err(OpCodeMapError::Json(_))
+    ));
+}
+
+#[test]
+fn suggests_a_shuffled_map() {
+    let op_code_map = shuffled_op_code_map();
+    let samples = [sample(&op_code_map), sample(&op_code_map)];
+    let suggestion = suggest_op_code_map(samples.iter().map(Vec::as_slice));
+
+    // every suggestion is right
+    for suggested in &suggestion.op_codes {
+        assert_eq!(
+            op_code_map.get(suggested.raw_op_code),
+            Some(suggested.op_code),
+            "{:?}",
+            suggested
+        );
+    }
+    // and every opcode with evidence in the samples is found
+    let suggested = suggestion.op_code_map();
+    for op_code in [
+        OpCode::LOP_RETURN,
+        OpCode::LOP_PREPVARARGS,
+        OpCode::LOP_GETIMPORT,
+        OpCode::LOP_NEWCLOSURE,
+        OpCode::LOP_DUPCLOSURE,
+        OpCode::LOP_CAPTURE,
+    ] {
+        assert!(
+            (0..=u8::MAX).any(|raw_op_code| suggested.get(raw_op_code) == Some(op_code)),
+            "{:?} wasn't suggested:\n{}",
+            op_code,
+            suggestion
+        );
+    }
+}
+
+#[test]
+fn suggests_nothing_without_samples() {
+    assert_eq!(suggest_op_code_map(std::iter::empty()), Default::default());
+    assert!(suggest_op_code_map([&b"not bytecode"[..]])
+        .op_codes
+        .is_empty());
+}
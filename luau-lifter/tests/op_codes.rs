@@ -0,0 +1,338 @@
+mod common;
+
+use std::{panic::AssertUnwindSafe, sync::Arc};
+
+use common::{abc, ad, chunk, function};
+use luau_lifter::{
+    decompile,
+    deserializer::{bytecode::Bytecode, chunk::Chunk, constant::Constant, function::Function},
+    instruction::Instruction,
+    op_code::OpCode,
+    op_code_map::OpCodeMap,
+    serializer::serialize,
+    Budget, DecompileError, DecompileOptions, DecompileOutput, DiagnosticsLevel, FunctionReport,
+    FunctionStatus,
+};
+
+const STRING: u32 = 0;
+const NUMBER: u32 = 1;
+const IMPORT: i16 = 2;
+const CLOSURE: i16 = 3;
+const TABLE: i16 = 4;
+
+// a function that uses `op_code` with operands that make sense for it
+fn code(op_code: OpCode) -> Vec<u32> {
+    let r#return = abc(OpCode::LOP_RETURN, 0, 1, 0);
+    let mut code = match op_code {
+        OpCode::LOP_RETURN => vec![],
+        OpCode::LOP_LOADB => vec![abc(op_code, 0, 1, 0)],
+        OpCode::LOP_CONCAT => vec![abc(op_code, 0, 1, 2)],
+        OpCode::LOP_NAMECALL => vec![
+            abc(op_code, 0, 1, 0),
+            STRING,
+            abc(OpCode::LOP_CALL, 0, 2, 1),
+        ],
+        OpCode::LOP_ADDK
+        | OpCode::LOP_SUBK
+        | OpCode::LOP_MULK
+        | OpCode::LOP_DIVK
+        | OpCode::LOP_MODK
+        | OpCode::LOP_POWK
+        | OpCode::LOP_IDIVK
+        | OpCode::LOP_ANDK
+        | OpCode::LOP_ORK
+        | OpCode::LOP_SUBRK
+        | OpCode::LOP_DIVRK => vec![abc(op_code, 0, NUMBER as u8, NUMBER as u8)],
+        OpCode::LOP_FASTCALL2K => vec![abc(op_code, 0, 1, 0), NUMBER],
+        OpCode::LOP_GETIMPORT => vec![ad(op_code, 0, IMPORT), 1 << 30],
+        OpCode::LOP_DUPCLOSURE => vec![ad(op_code, 0, CLOSURE)],
+        OpCode::LOP_DUPTABLE => vec![ad(op_code, 0, TABLE)],
+        OpCode::LOP_JUMPXEQKB => vec![ad(op_code, 0, 0), 1],
+        OpCode::LOP_JUMPXEQKN => vec![ad(op_code, 0, 0), NUMBER],
+        OpCode::LOP_FORNPREP | OpCode::LOP_FORNLOOP => vec![
+            ad(OpCode::LOP_FORNPREP, 0, 1),
+            ad(OpCode::LOP_FORNLOOP, 0, -1),
+        ],
+        OpCode::LOP_FORGLOOP => vec![
+            ad(OpCode::LOP_FORGPREP, 0, 0),
+            ad(OpCode::LOP_FORGLOOP, 0, -1),
+            1,
+        ],
+        OpCode::LOP_FORGPREP | OpCode::LOP_FORGPREP_INEXT | OpCode::LOP_FORGPREP_NEXT => {
+            vec![ad(op_code, 0, 0), ad(OpCode::LOP_FORGLOOP, 0, -1), 1]
+        }
+        _ => {
+            let word = match Instruction::parse(op_code as u32, &OpCodeMap::from_key(1)).unwrap() {
+                Instruction::BC { .. } => abc(op_code, 0, 1, 1),
+                Instruction::AD { .. } => ad(op_code, 0, 0),
+                Instruction::E { .. } => op_code as u32,
+            };
+            if op_code.has_aux() {
+                // both a string constant and a register
+                vec![word, STRING]
+            } else {
+                vec![word]
+            }
+        }
+    };
+    code.push(r#return);
+    code
+}
+
+#[test]
+fn every_op_code_lifts() {
+    for op_code in 0..OpCode::LOP__COUNT as u8 {
+        let op_code = OpCode::try_from(op_code).unwrap();
+        let constants = vec![
+            Constant::String(1),
+            Constant::Number(1.0),
+            Constant::Import(1 << 30),
+            Constant::Closure(1),
+            Constant::Table(vec![STRING as usize]),
+        ];
+        let main = Function {
+            functions: vec![1],
+            // GETUPVAL and SETUPVAL use the second upvalue
+            num_upvalues: 2,
+            ..function(&code(op_code), constants)
+        };
+        let child = function(&[abc(OpCode::LOP_RETURN, 0, 1, 0)], Vec::new());
+        let chunk = chunk(&["x", "y"], vec![main, child], 0);
+        let bytecode = serialize(&Bytecode::Chunk(chunk), &OpCodeMap::from_key(1)).unwrap();
+
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            decompile(&bytecode, &DecompileOptions::default())
+        }));
+        let output = match result {
+            Ok(Ok(output)) => output,
+            Ok(Err(err)) => panic!("failed to decompile {:?}: {}", op_code, err),
+            Err(_) => panic!("failed to lift {:?}", op_code),
+        };
+        // a function that panics is reported rather than failing the whole chunk
+        for function in &output.functions {
+            assert_eq!(
+                function.status,
+                FunctionStatus::Decompiled,
+                "failed to decompile function {} with {:?}",
+                function.id,
+                op_code
+            );
+        }
+        assert!(!output.source.contains("failed to decompile"));
+    }
+}
+
+#[test]
+fn invalid_constants_are_warnings() {
+    let code = [
+        ad(OpCode::LOP_DUPTABLE, 0, 1),
+        ad(OpCode::LOP_LOADK, 1, 5),
+        abc(OpCode::LOP_RETURN, 0, 3, 0),
+    ];
+    let constants = vec![
+        Constant::Import(1 << 30),
+        // the import isn't a valid key
+        Constant::TableWithConstants(vec![(2, Some(3)), (0, Some(3))]),
+        Constant::String(1),
+        Constant::Number(1.0),
+    ];
+    let chunk = Chunk {
+        // tables with constants were added in version 7
+        version: 7,
+        ..chunk(&["x"], vec![function(&code, constants)], 0)
+    };
+    let bytecode = serialize(&Bytecode::Chunk(chunk), &OpCodeMap::from_key(1)).unwrap();
+    let output = decompile(&bytecode, &DecompileOptions::default()).unwrap();
+    assert_eq!(output.source, "return {}, nil");
+    assert!(output.is_complete());
+    assert_eq!(
+        output
+            .warnings()
+            .map(|(_, warning)| warning)
+            .collect::<Vec<_>>(),
+        ["invalid table template K1", "invalid constant K5"]
+    );
+}
+
+// `return { x = 1 }`, with a template in the format before version 7, which only has keys
+fn old_table_template(keys: Vec<usize>) -> DecompileOutput {
+    let code = [
+        ad(OpCode::LOP_DUPTABLE, 0, 1),
+        ad(OpCode::LOP_LOADN, 1, 1),
+        abc(OpCode::LOP_SETTABLEKS, 1, 0, 0),
+        0,
+        abc(OpCode::LOP_RETURN, 0, 2, 0),
+    ];
+    let constants = vec![
+        Constant::String(1),
+        Constant::Table(keys),
+        Constant::Import(1 << 30),
+    ];
+    let chunk = chunk(&["x"], vec![function(&code, constants)], 0);
+    let bytecode = serialize(&Bytecode::Chunk(chunk), &OpCodeMap::from_key(1)).unwrap();
+    decompile(&bytecode, &DecompileOptions::default()).unwrap()
+}
+
+#[test]
+fn lifts_old_table_templates() {
+    let output = old_table_template(vec![0]);
+    assert_eq!(output.source, "return {\n\tx = 1\n}");
+    assert_eq!(output.warnings().count(), 0);
+    // the import isn't a valid key
+    let output = old_table_template(vec![2]);
+    assert_eq!(output.source, "return {\n\tx = 1\n}");
+    assert_eq!(
+        output
+            .warnings()
+            .map(|(_, warning)| warning)
+            .collect::<Vec<_>>(),
+        ["invalid table template K1"]
+    );
+}
+
+// `local x = builtin(5) return x, x`, calling the global `name` with builtin `id`
+fn builtin_call(name: &str, id: u8) -> String {
+    let code = [
+        ad(OpCode::LOP_LOADN, 0, 5),
+        abc(OpCode::LOP_FASTCALL1, id, 0, 3),
+        ad(OpCode::LOP_GETIMPORT, 1, 1),
+        1 << 30,
+        abc(OpCode::LOP_MOVE, 2, 0, 0),
+        abc(OpCode::LOP_CALL, 1, 2, 2),
+        abc(OpCode::LOP_MOVE, 2, 1, 0),
+        abc(OpCode::LOP_RETURN, 1, 3, 0),
+    ];
+    let constants = vec![Constant::String(1), Constant::Import(1 << 30)];
+    let chunk = chunk(&[name], vec![function(&code, constants)], 0);
+    let bytecode = serialize(&Bytecode::Chunk(chunk), &OpCodeMap::from_key(1)).unwrap();
+    decompile(&bytecode, &DecompileOptions::default())
+        .unwrap()
+        .source
+}
+
+#[test]
+fn names_builtin_results() {
+    assert_eq!(
+        builtin_call("tostring", 63),
+        "local str = tostring(5)\nreturn str, str"
+    );
+    // naming it `assert` would shadow the global
+    assert_eq!(
+        builtin_call("assert", 1),
+        "local v1 = assert(5)\nreturn v1, v1"
+    );
+}
+
+#[test]
+fn lift_failures_are_reported() {
+    // jumps past the end of the function
+    let code = [
+        ad(OpCode::LOP_JUMP, 0, 100),
+        abc(OpCode::LOP_RETURN, 0, 1, 0),
+    ];
+    let chunk = chunk(&[], vec![function(&code, Vec::new())], 0);
+    let bytecode = serialize(&Bytecode::Chunk(chunk), &OpCodeMap::from_key(1)).unwrap();
+    let output = decompile(&bytecode, &DecompileOptions::default()).unwrap();
+    assert_eq!(output.source, "-- failed to decompile");
+    assert!(matches!(
+        output.functions[..],
+        [FunctionReport {
+            id: 0,
+            status: FunctionStatus::Failed { .. },
+            ..
+        }]
+    ));
+}
+
+#[test]
+fn diagnostics_replace_failed_bodies() {
+    let code = [
+        ad(OpCode::LOP_JUMP, 0, 100),
+        abc(OpCode::LOP_RETURN, 0, 1, 0),
+    ];
+    let chunk = chunk(&[], vec![function(&code, Vec::new())], 0);
+    let bytecode = serialize(&Bytecode::Chunk(chunk), &OpCodeMap::from_key(1)).unwrap();
+    let options = DecompileOptions {
+        diagnostics: DiagnosticsLevel::Details,
+        ..Default::default()
+    };
+    let output = decompile(&bytecode, &options).unwrap();
+    let mut lines = output.source.lines();
+    assert_eq!(
+        lines.next(),
+        Some("-- failed to decompile function 0 (line 0)")
+    );
+    assert!(lines.next().unwrap().starts_with("-- panicked at '"));
+    // followed by the disassembly
+    assert!(output.source.contains("JUMP"), "{}", output.source);
+}
+
+#[test]
+fn exhausted_budgets_are_warnings() {
+    let code = [abc(OpCode::LOP_RETURN, 0, 1, 0)];
+    let chunk = chunk(&[], vec![function(&code, Vec::new())], 0);
+    let bytecode = serialize(&Bytecode::Chunk(chunk), &OpCodeMap::from_key(1)).unwrap();
+    let options = DecompileOptions {
+        budget: Budget {
+            max_iterations: Some(0),
+            time_limit: None,
+        },
+        ..Default::default()
+    };
+    let output = decompile(&bytecode, &options).unwrap();
+    assert!(output.is_complete());
+    assert_eq!(
+        output
+            .warnings()
+            .map(|(_, warning)| warning)
+            .collect::<Vec<_>>(),
+        ["structuring stopped after 0 iterations"]
+    );
+    assert!(output
+        .source
+        .starts_with("-- warning: structuring stopped after 0 iterations"));
+}
+
+#[test]
+fn decompiles_on_a_thread_pool() {
+    let main = Function {
+        functions: vec![1],
+        ..function(&code(OpCode::LOP_NEWCLOSURE), Vec::new())
+    };
+    let child = function(&code(OpCode::LOP_LOADB), Vec::new());
+    let chunk = chunk(&[], vec![main, child], 0);
+    let bytecode = serialize(&Bytecode::Chunk(chunk), &OpCodeMap::from_key(1)).unwrap();
+    let thread_pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(2)
+        .build()
+        .unwrap();
+    let options = DecompileOptions {
+        thread_pool: Some(Arc::new(thread_pool)),
+        ..Default::default()
+    };
+    let output = decompile(&bytecode, &options).unwrap();
+    assert!(output.is_complete());
+    assert_eq!(
+        output.source,
+        decompile(&bytecode, &DecompileOptions::default())
+            .unwrap()
+            .source
+    );
+}
+
+#[test]
+fn invalid_function_indices_are_errors() {
+    let code = [abc(OpCode::LOP_RETURN, 0, 1, 0)];
+    for (main, functions) in [(1, Vec::new()), (0, vec![1])] {
+        let main_function = Function {
+            functions,
+            ..function(&code, Vec::new())
+        };
+        let chunk = chunk(&[], vec![main_function], main);
+        let bytecode = serialize(&Bytecode::Chunk(chunk), &OpCodeMap::from_key(1)).unwrap();
+        assert!(matches!(
+            decompile(&bytecode, &DecompileOptions::default()),
+            Err(DecompileError::Deserialize(_))
+        ));
+    }
+}
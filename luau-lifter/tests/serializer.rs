@@ -0,0 +1,164 @@
+mod common;
+
+use common::{abc, ad, function, instructions};
+use luau_lifter::{
+    deserializer::{
+        bytecode::Bytecode,
+        chunk::Chunk,
+        constant::Constant,
+        deserialize_with_op_code_map,
+        function::Function,
+        local::Local,
+        type_info::{Type, TypeInfo, TypeTag, TypedLocal},
+    },
+    op_code::OpCode,
+    op_code_map::OpCodeMap,
+    serializer::{serialize, SerializeError},
+};
+
+fn chunk() -> Chunk {
+    let main = Function {
+        max_stack_size: 4,
+        num_parameters: 1,
+        functions: vec![0],
+        line_gap_log2: Some(2),
+        line_info_delta: Some(vec![0, 1, 0, 0, 1, 0, 2]),
+        abs_line_info_delta: Some(vec![1, 2]),
+        type_info: TypeInfo {
+            parameters: vec![Type {
+                tag: TypeTag::Number,
+                optional: true,
+            }],
+            upvalues: Vec::new(),
+            locals: vec![TypedLocal {
+                r#type: Type {
+                    tag: TypeTag::String,
+                    optional: false,
+                },
+                register: 1,
+                range: 1..6,
+            }],
+        },
+        locals: vec![Local {
+            name: 2,
+            range: 1..6,
+            register: 1,
+        }],
+        ..function(
+            &[
+                ad(OpCode::LOP_LOADK, 1, 0),
+                ad(OpCode::LOP_GETIMPORT, 2, 3),
+                1 << 30 | 1 << 20,
+                ad(OpCode::LOP_DUPTABLE, 3, 6),
+                ad(OpCode::LOP_NEWCLOSURE, 3, 0),
+                abc(OpCode::LOP_CALL, 2, 2, 1),
+                abc(OpCode::LOP_RETURN, 0, 1, 0),
+            ],
+            vec![
+                Constant::Number(1.5),
+                Constant::Nil,
+                Constant::Boolean(true),
+                Constant::Import(1 << 30 | 1 << 20),
+                Constant::String(1),
+                Constant::Vector(1.0, 2.0, 3.0, 0.0),
+                Constant::Table(vec![4]),
+                Constant::Closure(1),
+                Constant::TableWithConstants(vec![(4, Some(0)), (4, None)]),
+            ],
+        )
+    };
+    let child = Function {
+        max_stack_size: 1,
+        num_upvalues: 1,
+        is_vararg: false,
+        line_defined: 3,
+        function_name: 3,
+        upvalue_names: vec![2],
+        ..function(
+            &[
+                abc(OpCode::LOP_GETUPVAL, 0, 0, 0),
+                abc(OpCode::LOP_RETURN, 0, 2, 0),
+            ],
+            Vec::new(),
+        )
+    };
+    Chunk {
+        version: 7,
+        ..common::chunk(&["print", "x", "f", "k"], vec![child, main], 1)
+    }
+}
+
+// `code` holds the raw words, which differ between opcode maps
+fn without_code(bytecode: Bytecode) -> String {
+    let Bytecode::Chunk(mut chunk) = bytecode else {
+        panic!("expected a chunk, got {:?}", bytecode);
+    };
+    for function in &mut chunk.functions {
+        function.code.clear();
+    }
+    format!("{:?}", Bytecode::Chunk(chunk))
+}
+
+fn assert_round_trips(op_code_map: &OpCodeMap) {
+    let expected = without_code(Bytecode::Chunk(chunk()));
+    let bytes = serialize(&Bytecode::Chunk(chunk()), op_code_map).unwrap();
+    let deserialized = deserialize_with_op_code_map(&bytes, op_code_map).unwrap();
+    let reserialized = serialize(&deserialized, op_code_map).unwrap();
+    assert_eq!(without_code(deserialized), expected);
+    assert_eq!(reserialized, bytes);
+}
+
+#[test]
+fn round_trips_with_key_1() {
+    assert_round_trips(&OpCodeMap::from_key(1));
+}
+
+#[test]
+fn round_trips_with_key_203() {
+    let op_code_map = OpCodeMap::from_key(203);
+    let bytes = serialize(&Bytecode::Chunk(chunk()), &op_code_map).unwrap();
+    // the opcodes are encoded, so key 1 writes something else
+    assert_ne!(
+        serialize(&Bytecode::Chunk(chunk()), &OpCodeMap::from_key(1)).unwrap(),
+        bytes
+    );
+    assert_round_trips(&op_code_map);
+}
+
+#[test]
+fn round_trips_with_op_code_map() {
+    // reverse the standard numbering
+    let mut op_code_map = OpCodeMap::default();
+    for op_code in 0..OpCode::LOP__COUNT as u8 {
+        op_code_map.insert(u8::MAX - op_code, OpCode::try_from(op_code).unwrap());
+    }
+    assert_round_trips(&op_code_map);
+}
+
+#[test]
+fn round_trips_errors() {
+    let op_code_map = OpCodeMap::from_key(1);
+    let bytecode = Bytecode::Error("[string \"x\"]:1: syntax error".to_string());
+    let bytes = serialize(&bytecode, &op_code_map).unwrap();
+    let Bytecode::Error(message) = deserialize_with_op_code_map(&bytes, &op_code_map).unwrap()
+    else {
+        panic!("expected an error");
+    };
+    assert_eq!(message, "[string \"x\"]:1: syntax error");
+}
+
+#[test]
+fn unmapped_op_codes_fail() {
+    // even keys only reach even opcodes
+    let mut chunk = chunk();
+    chunk.functions[0].instructions = instructions(&[abc(OpCode::LOP_LOADB, 0, 1, 0)]);
+    let err = serialize(&Bytecode::Chunk(chunk), &OpCodeMap::from_key(2)).unwrap_err();
+    assert_eq!(
+        err,
+        SerializeError::UnmappedOpCode {
+            function: 0,
+            pc: 0,
+            op_code: OpCode::LOP_LOADB,
+        }
+    );
+}
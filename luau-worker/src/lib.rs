@@ -12,6 +12,9 @@ const AUTH_SECRET: &str = "ymjKH2O3BbO3bDSsKmpo3ek3vHxIWYLQfj0";
 struct DecompileMessage {
     id: String,
     encoded_bytecode: String,
+    // a key or `auto`, defaults to the vanilla key
+    #[serde(default)]
+    key: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -20,6 +23,18 @@ struct DecompileResponse {
     decompilation: String,
 }
 
+// `auto` detects the key, see `luau_lifter::detect_encode_key`
+fn encode_key(key: Option<&str>, default: u8) -> std::result::Result<Option<u8>, String> {
+    match key {
+        None => Ok(Some(default)),
+        Some("auto") => Ok(None),
+        Some(key) => key
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("invalid key: {}", key)),
+    }
+}
+
 #[event(fetch, respond_with_errors)]
 pub async fn main(req: Request, env: Env, _ctx: worker::Context) -> Result<Response> {
     console_error_panic_hook::set_once();
@@ -53,9 +68,12 @@ pub async fn main(req: Request, env: Env, _ctx: worker::Context) -> Result<Respo
                         let bytecode = BASE64_STANDARD
                             .decode(msg.encoded_bytecode)
                             .expect("bytecode must be base64 encoded");
+                        let decompilation = encode_key(msg.key.as_deref(), 1).and_then(|key| {
+                            decompile_bytecode(&bytecode, key).map_err(|err| err.to_string())
+                        });
                         let resp = DecompileResponse {
                             id: msg.id,
-                            decompilation: decompile_bytecode(&bytecode, 1),
+                            decompilation: decompilation.unwrap_or_else(|err| err),
                         };
                         server
                             .send_with_str(serde_json::to_string(&resp).unwrap())
@@ -77,9 +95,23 @@ pub async fn main(req: Request, env: Env, _ctx: worker::Context) -> Result<Respo
                 return Response::error("invalid license", 403);
             }
 
+            // the Roblox client key unless `?key=` says otherwise
+            let url = req.url()?;
+            let key = url
+                .query_pairs()
+                .find(|(name, _)| name == "key")
+                .map(|(_, key)| key.into_owned());
+            let key = match encode_key(key.as_deref(), 203) {
+                Ok(key) => key,
+                Err(err) => return Response::error(err, 400),
+            };
+
             let encoded_bytecode = req.bytes().await?;
             match BASE64_STANDARD.decode(encoded_bytecode) {
-                Ok(bytecode) => Response::ok(decompile_bytecode(&bytecode, 203)),
+                Ok(bytecode) => match decompile_bytecode(&bytecode, key) {
+                    Ok(decompilation) => Response::ok(decompilation),
+                    Err(err) => Response::error(err.to_string(), 400),
+                },
                 Err(_) => Response::error("invalid bytecode", 400),
             }
         })
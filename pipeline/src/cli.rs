@@ -0,0 +1,31 @@
+use std::{
+    fs::File,
+    io::{Read, Write},
+    path::Path,
+    time::Instant,
+};
+
+/// Decompiles `path` into `<name>.<extension>` in the working directory, for the
+/// binaries of the lifters.
+pub fn decompile_file<E: std::error::Error + Send + Sync + 'static>(
+    path: &Path,
+    extension: &str,
+    decompile_bytecode: impl FnOnce(&[u8]) -> Result<String, E>,
+) -> anyhow::Result<()> {
+    crate::install_panic_hook();
+
+    let mut input = File::open(path)?;
+    let mut buffer = vec![0; input.metadata()?.len() as usize];
+    input.read_exact(&mut buffer)?;
+
+    let start = Instant::now();
+    let res = decompile_bytecode(&buffer)?;
+    let duration = start.elapsed();
+
+    // TODO: use BufWriter?
+    let mut out = File::create(path.with_extension(extension).file_name().unwrap())?;
+    writeln!(out, "-- decompiled by Sentinel (took {:?})", duration)?;
+    writeln!(out, "{}", res)?;
+
+    Ok(())
+}
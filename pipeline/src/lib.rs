@@ -0,0 +1,345 @@
+#![feature(let_chains)]
+
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use ast::{
+    local_declarations::LocalDeclarer, name_locals::name_locals, replace_locals::replace_locals,
+    Traverse,
+};
+use by_address::ByAddress;
+use cfg::{
+    function::Function,
+    ssa::{
+        self,
+        structuring::{structure_conditionals, structure_jumps, structure_method_calls},
+    },
+};
+use indexmap::IndexMap;
+use parking_lot::Mutex;
+use petgraph::algo::dominators::simple_fast;
+use rayon::prelude::*;
+use rustc_hash::FxHashMap;
+use triomphe::Arc;
+
+pub mod cli;
+mod panic;
+
+pub use panic::{install_panic_hook, isolate, Panic};
+
+pub type Upvalues = FxHashMap<ByAddress<Arc<Mutex<ast::Function>>>, Vec<ast::RcLocal>>;
+
+/// The structuring passes to run on each function. Disabling a pass leaves more `goto`s
+/// and temporaries in the output, which can help to narrow down a bad transformation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Passes {
+    pub structure_jumps: bool,
+    pub inline: bool,
+    pub structure_conditionals: bool,
+    /// Can't be used when `__namecall` makes `a:b()` differ from `a.b(a)`, as in Luau
+    pub structure_method_calls: bool,
+}
+
+impl Default for Passes {
+    fn default() -> Self {
+        Self {
+            structure_jumps: true,
+            inline: true,
+            structure_conditionals: true,
+            structure_method_calls: true,
+        }
+    }
+}
+
+/// Limits on the structuring loop of each function, there are none by default. The time
+/// limit is checked between iterations, so a single pass that doesn't terminate can still
+/// overrun it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Budget {
+    pub max_iterations: Option<usize>,
+    pub time_limit: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Options {
+    pub passes: Passes,
+    pub budget: Budget,
+    /// Capture a backtrace of each panic even if `RUST_BACKTRACE` isn't set
+    pub force_backtraces: bool,
+}
+
+#[derive(Debug)]
+pub enum DecompileError<E> {
+    Deserialize(E),
+    /// The lifter panicked, so there are no functions to decompile
+    Lift(Panic),
+}
+
+impl<E: fmt::Display> fmt::Display for DecompileError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Deserialize(err) => write!(f, "{}", err),
+            Self::Lift(panic) => write!(f, "failed to lift: {}", panic),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for DecompileError<E> {}
+
+/// A function that has been lifted and is waiting to be decompiled
+pub struct Lifted<T> {
+    pub ast_function: Arc<Mutex<ast::Function>>,
+    pub function: Function,
+    pub upvalues_in: Vec<ast::RcLocal>,
+    /// Passed through to the report, to tell the functions apart
+    pub data: T,
+}
+
+pub struct Report<T> {
+    pub data: T,
+    pub outcome: Outcome,
+    pub duration: Duration,
+}
+
+pub enum Outcome {
+    Decompiled { budget_warning: Option<String> },
+    Failed(Panic),
+}
+
+/// Decompiles the functions on the current rayon pool, they are independent until their
+/// upvalues are linked. The body of a function that panics is replaced with a comment
+/// of each line of `on_failure`.
+pub fn decompile_functions<T: Send>(
+    lifted: Vec<Lifted<T>>,
+    options: &Options,
+    on_failure: impl Fn(&T, &Panic) -> String + Sync,
+) -> (Upvalues, Vec<Report<T>>) {
+    lifted
+        .into_par_iter()
+        .map(|lifted| {
+            let start = Instant::now();
+            let ast_function = lifted.ast_function.clone();
+            let result = isolate(options.force_backtraces, || {
+                decompile_function(
+                    lifted.ast_function,
+                    lifted.function,
+                    lifted.upvalues_in,
+                    options,
+                )
+            });
+            let (upvalues, outcome) = match result {
+                Ok((upvalues_in, budget_warning)) => {
+                    (upvalues_in, Outcome::Decompiled { budget_warning })
+                }
+                Err(panic) => {
+                    push_comment(
+                        &mut ast_function.lock().body,
+                        &on_failure(&lifted.data, &panic),
+                    );
+                    (Vec::new(), Outcome::Failed(panic))
+                }
+            };
+            let report = Report {
+                data: lifted.data,
+                outcome,
+                duration: start.elapsed(),
+            };
+            ((ByAddress(ast_function), upvalues), report)
+        })
+        .unzip()
+}
+
+/// Pushes a comment of each line of `message`, for functions that failed to decompile
+pub fn push_comment(body: &mut ast::Block, message: &str) {
+    body.extend(
+        message
+            .trim_end()
+            .split('\n')
+            .map(|s| ast::Comment::new(s.to_string()).into()),
+    );
+}
+
+/// Links the upvalues of every closure in the main function and names its locals
+pub fn link(
+    main: Arc<Mutex<ast::Function>>,
+    mut upvalues: Upvalues,
+    rename_locals: bool,
+) -> ast::Block {
+    let main = ByAddress(main);
+    upvalues.remove(&main);
+    let mut body = match Arc::try_unwrap(main.0) {
+        Ok(main) => main.into_inner().body,
+        Err(main) => std::mem::take(&mut main.lock().body),
+    };
+    link_upvalues(&mut body, &upvalues);
+    name_locals(&mut body, rename_locals);
+    body
+}
+
+// returns the upvalues the function was lifted with, and a warning if it ran out of budget
+fn decompile_function(
+    ast_function: Arc<Mutex<ast::Function>>,
+    mut function: Function,
+    upvalues_in: Vec<ast::RcLocal>,
+    options: &Options,
+) -> (Vec<ast::RcLocal>, Option<String>) {
+    let start = Instant::now();
+    let Options { passes, budget, .. } = options;
+    let (local_count, local_groups, upvalue_in_groups, upvalue_passed_groups) =
+        cfg::ssa::construct(&mut function, &upvalues_in);
+    let upvalue_to_group = upvalue_in_groups
+        .into_iter()
+        .chain(
+            upvalue_passed_groups
+                .into_iter()
+                .map(|m| (ast::RcLocal::default(), m)),
+        )
+        .flat_map(|(i, g)| g.into_iter().map(move |u| (u, i.clone())))
+        .collect::<IndexMap<_, _>>();
+    // TODO: do we even need this?
+    let local_to_group = local_groups
+        .into_iter()
+        .enumerate()
+        .flat_map(|(i, g)| g.into_iter().map(move |l| (l, i)))
+        .collect::<FxHashMap<_, _>>();
+    // TODO: REFACTOR: some way to write a macro that states
+    // if cfg::ssa::inline results in change then structure_jumps, structure_compound_conditionals,
+    // structure_for_loops and remove_unnecessary_params must run again.
+    // if structure_compound_conditionals results in change then dominators and post dominators
+    // must be recalculated.
+    // etc.
+    // the macro could also maybe generate an optimal ordering?
+    // obfuscated control flow can keep the passes changing the function forever, when the
+    // budget runs out we carry on with what has been structured so far
+    let mut budget_warning = None;
+    let mut iterations = 0;
+    let mut changed = true;
+    while changed {
+        if let Some(max_iterations) = budget.max_iterations
+            && iterations >= max_iterations
+        {
+            budget_warning = Some(format!(
+                "structuring stopped after {} iterations",
+                max_iterations
+            ));
+            break;
+        }
+        if let Some(time_limit) = budget.time_limit
+            && start.elapsed() >= time_limit
+        {
+            budget_warning = Some(format!(
+                "structuring stopped after {:.2}s",
+                time_limit.as_secs_f64()
+            ));
+            break;
+        }
+        iterations += 1;
+        changed = false;
+
+        if passes.structure_jumps {
+            let dominators = simple_fast(function.graph(), function.entry().unwrap());
+            changed |= structure_jumps(&mut function, &dominators);
+        }
+
+        if passes.inline {
+            ssa::inline::inline(&mut function, &local_to_group, &upvalue_to_group);
+        }
+
+        if passes.structure_conditionals && structure_conditionals(&mut function)
+        // || {
+        //     let post_dominators = post_dominators(function.graph_mut());
+        //     structure_for_loops(&mut function, &dominators, &post_dominators)
+        // }
+            || passes.structure_method_calls && structure_method_calls(&mut function)
+        {
+            changed = true;
+        }
+        let mut local_map = FxHashMap::default();
+        // TODO: loop until returns false?
+        if ssa::construct::remove_unnecessary_params(&mut function, &mut local_map) {
+            changed = true;
+        }
+        ssa::construct::apply_local_map(&mut function, local_map);
+    }
+    ssa::Destructor::new(
+        &mut function,
+        upvalue_to_group,
+        upvalues_in.iter().cloned().collect(),
+        local_count,
+    )
+    .destruct();
+
+    let params = std::mem::take(&mut function.parameters);
+    let is_variadic = function.is_variadic;
+    let block = Arc::new(restructure::lift(function).into());
+    LocalDeclarer::default().declare_locals(
+        // TODO: why does block.clone() not work?
+        Arc::clone(&block),
+        &upvalues_in.iter().chain(params.iter()).cloned().collect(),
+    );
+
+    let mut body = Arc::try_unwrap(block).unwrap().into_inner();
+    if let Some(warning) = &budget_warning {
+        body.insert(0, ast::Comment::new(format!("warning: {}", warning)).into());
+    }
+    {
+        let mut ast_function = ast_function.lock();
+        ast_function.body = body;
+        ast_function.parameters = params;
+        ast_function.is_variadic = is_variadic;
+    }
+    (upvalues_in, budget_warning)
+}
+
+fn link_upvalues(body: &mut ast::Block, upvalues: &Upvalues) {
+    for stat in &mut body.0 {
+        stat.traverse_rvalues(&mut |rvalue| {
+            if let ast::RValue::Closure(closure) = rvalue
+                && let Some(old_upvalues) = upvalues.get(&closure.function)
+            {
+                let mut function = closure.function.lock();
+                // TODO: inefficient, try constructing a map of all up -> new up first
+                // and then call replace_locals on main body
+                let mut local_map =
+                    FxHashMap::with_capacity_and_hasher(old_upvalues.len(), Default::default());
+                for (old, new) in
+                    old_upvalues
+                        .iter()
+                        .zip(closure.upvalues.iter().map(|u| match u {
+                            ast::Upvalue::Copy(l) | ast::Upvalue::Ref(l) => l,
+                        }))
+                {
+                    // keep the name from debug info of either side
+                    let name = old.0 .0.lock().0.clone();
+                    if name.is_some() && new.0 .0.lock().0.is_none() {
+                        new.0 .0.lock().0 = name;
+                    }
+                    local_map.insert(old.clone(), new.clone());
+                }
+                link_upvalues(&mut function.body, upvalues);
+                replace_locals(&mut function.body, &local_map);
+            }
+        });
+        match stat {
+            ast::Statement::If(r#if) => {
+                link_upvalues(&mut r#if.then_block.lock(), upvalues);
+                link_upvalues(&mut r#if.else_block.lock(), upvalues);
+            }
+            ast::Statement::While(r#while) => {
+                link_upvalues(&mut r#while.block.lock(), upvalues);
+            }
+            ast::Statement::Repeat(repeat) => {
+                link_upvalues(&mut repeat.block.lock(), upvalues);
+            }
+            ast::Statement::NumericFor(numeric_for) => {
+                link_upvalues(&mut numeric_for.block.lock(), upvalues);
+            }
+            ast::Statement::GenericFor(generic_for) => {
+                link_upvalues(&mut generic_for.block.lock(), upvalues);
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use std::{
+    backtrace::Backtrace,
+    cell::{Cell, RefCell},
+    fmt,
+    panic::{self, AssertUnwindSafe},
+    sync::Once,
+};
+
+thread_local! {
+    // `Some` while the thread runs an isolated closure, with whether to force a backtrace
+    static ISOLATION: Cell<Option<bool>> = const { Cell::new(None) };
+    static BACKTRACE: RefCell<Option<Backtrace>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug)]
+pub struct Panic {
+    pub message: String,
+    /// Only captured once `install_panic_hook` has been called
+    pub backtrace: Option<Backtrace>,
+}
+
+impl fmt::Display for Panic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Installs a panic hook that records the backtraces of panics in `isolate` instead of
+/// printing them. Other panics go to the hook that was installed before. The hook is
+/// global, so this is left to binaries; it only installs the hook the first time.
+pub fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let prev_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| match ISOLATION.get() {
+            Some(force_backtrace) => {
+                let backtrace = if force_backtrace {
+                    Backtrace::force_capture()
+                } else {
+                    Backtrace::capture()
+                };
+                BACKTRACE.with(|b| b.borrow_mut().replace(backtrace));
+            }
+            None => prev_hook(info),
+        }));
+    });
+}
+
+/// Runs `f`, turning a panic into an error so that one bad function doesn't take down
+/// the rest of the chunk.
+pub fn isolate<T>(force_backtrace: bool, f: impl FnOnce() -> T) -> Result<T, Panic> {
+    let prev_isolation = ISOLATION.replace(Some(force_backtrace));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    ISOLATION.set(prev_isolation);
+    result.map_err(|payload| {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => match payload.downcast::<&str>() {
+                Ok(message) => message.to_string(),
+                Err(_) => "Unknown Source of Error".to_owned(),
+            },
+        };
+        Panic {
+            message,
+            backtrace: BACKTRACE.with(|b| b.borrow_mut().take()),
+        }
+    })
+}
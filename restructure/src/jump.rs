@@ -39,6 +39,7 @@ impl super::GraphStructurer {
                         right: vec![cond],
                         prefix: true,
                         parallel: false,
+                        pc_range: None,
                     }
                     .into(),
                 ),